@@ -2,7 +2,7 @@
 use std::collections::HashMap;
 use std::io::Cursor;
 use std::io::Write as _;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::sync::{OnceLock, atomic::AtomicUsize};
 use std::time::{Duration, Instant};
@@ -13,7 +13,7 @@ use cipher::{block_padding::NoPadding, BlockDecryptMut, KeyInit};
 use image::ImageFormat;
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 static LOG_WRITER: OnceLock<Mutex<std::io::BufWriter<std::fs::File>>> = OnceLock::new();
 static LOG_LINE_COUNT: AtomicUsize = AtomicUsize::new(0);
@@ -23,6 +23,24 @@ static REGISTER_COOKIE_JAR: OnceLock<Arc<reqwest::cookie::Jar>> = OnceLock::new(
 static JM_API_BASES: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
 static JM_API_BASE_INDEX: AtomicUsize = AtomicUsize::new(0);
 static READ_PROGRESS_DB: OnceLock<Result<sled::Db, String>> = OnceLock::new();
+static CACHE_STATS_DB: OnceLock<Result<sled::Db, String>> = OnceLock::new();
+// Serializes read-modify-write of the cache index and its aggregates.
+static CACHE_INDEX_LOCK: Mutex<()> = Mutex::new(());
+static COVER_EVICTION_RUNNING: AtomicBool = AtomicBool::new(false);
+static EMERGENCY_EVICTION_RUNNING: AtomicBool = AtomicBool::new(false);
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+static APP_DIRS: OnceLock<AppDirs> = OnceLock::new();
+static CACHE_MOVE_RUNNING: AtomicBool = AtomicBool::new(false);
+static PAGE_MEMORY_CACHE: OnceLock<Mutex<PageMemoryCache>> = OnceLock::new();
+static DOWNLOAD_WORKER_RUNNING: AtomicBool = AtomicBool::new(false);
+static DOWNLOAD_ACTIVE: Mutex<Option<ActiveDownload>> = Mutex::new(None);
+static LIBRARY_SYNC_LOCK: Mutex<()> = Mutex::new(());
+static LOCAL_EVICTION_RUNNING: AtomicBool = AtomicBool::new(false);
+// Bytes extracted into `local/` since its budget was last checked.
+static LOCAL_EXTRACTED_SINCE_CHECK: AtomicU64 = AtomicU64::new(0);
+// Millis until which the API is treated as unreachable in auto offline mode.
+static NETWORK_DOWN_UNTIL: AtomicI64 = AtomicI64::new(0);
+static ATOMIC_WRITE_SEQ: AtomicUsize = AtomicUsize::new(0);
 
 const JM_HEADER_VER: &str = "1.7.5";
 const JM_APP_VERSION: &str = "2.0.6";
@@ -50,6 +68,16 @@ macro_rules! logl {
     };
 }
 
+// Where config, data, cache and logs live when no `JM_*_DIR` override is set.
+#[derive(Debug, Clone)]
+struct AppDirs {
+    config: std::path::PathBuf,
+    data: std::path::PathBuf,
+    cache: std::path::PathBuf,
+    logs: std::path::PathBuf,
+    portable: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 struct AppConfig {
@@ -59,6 +87,37 @@ struct AppConfig {
     api_base_list: Vec<String>,
     #[serde(default)]
     session_cookies: HashMap<String, String>,
+    #[serde(default)]
+    cover_cache_max_bytes: Option<u64>,
+    #[serde(default)]
+    min_free_bytes: Option<u64>,
+    // User-chosen cache root; the cache lives in `<root>/jmcomic-cache`.
+    #[serde(default)]
+    cache_root: Option<String>,
+    #[serde(default)]
+    memory_cache_max_bytes: Option<u64>,
+    // Komga/Kavita-style mirror of downloads; disabled when unset.
+    #[serde(default)]
+    library_dir: Option<String>,
+    // auto (default) | on | off
+    #[serde(default)]
+    offline_mode: Option<String>,
+    // Queue new chapters of favorites for download; per-favorite rules override this.
+    #[serde(default)]
+    auto_download: Option<bool>,
+    #[serde(default)]
+    auto_download_max_jobs: Option<u64>,
+    // Auto downloads stop queueing once the read cache reaches this size.
+    #[serde(default)]
+    auto_download_max_bytes: Option<u64>,
+    // Image transfer caps in bytes per second; unset is unlimited.
+    #[serde(default)]
+    bandwidth_background_bps: Option<u64>,
+    #[serde(default)]
+    bandwidth_interactive_bps: Option<u64>,
+    // "HH:MM-HH:MM" local-time ranges when downloads may run; empty means always.
+    #[serde(default)]
+    background_windows: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +142,46 @@ struct ReadCacheStats {
   total_comics: u64,
   updated_at: i64,
   elapsed_ms: u64,
+  #[serde(default)]
+  thumb_bytes: u64,
+  #[serde(default)]
+  thumb_files: u64,
+  #[serde(default)]
+  cover_bytes: u64,
+  #[serde(default)]
+  cover_files: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ReadCacheRepairResult {
+    scanned_files: u64,
+    removed_files: u64,
+    removed_bytes: u64,
+    elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadPageRef {
+    url: String,
+    num: i64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadDisplaySize {
+    #[serde(default)]
+    max_width: Option<u32>,
+    #[serde(default)]
+    scale: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PageThumbnail {
+    index: usize,
+    path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -121,1711 +220,3344 @@ struct ReadCacheComicStats {
     files: u64,
     bytes: u64,
     updated_at: i64,
+    #[serde(default)]
+    thumb_files: u64,
+    #[serde(default)]
+    thumb_bytes: u64,
+    #[serde(default)]
+    accessed_at: i64,
 }
 
-#[derive(Debug, Clone)]
-struct ReadCacheDirStats {
+// One cached file, keyed by its path relative to the cache dir (`read/<aid>/<file>`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheIndexEntry {
+    area: String,
     aid: String,
+    #[serde(default)]
+    chapter_id: Option<String>,
+    key: String,
     bytes: u64,
-    newest_ms: i64,
+    created_at: i64,
+    accessed_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Per-comic eviction rules; pinned comics are skipped by the global LRU pass.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ReadProgressEntry {
+struct CacheComicPolicy {
     aid: String,
-    updated_at: i64,
-    #[serde(default)]
-    chapter_id: Option<String>,
-    #[serde(default)]
-    chapter_sort: Option<String>,
     #[serde(default)]
-    chapter_name: Option<String>,
+    pinned: bool,
     #[serde(default)]
-    page_index: Option<i64>,
+    quota_bytes: Option<u64>,
+    updated_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct FollowStateEntry {
+struct ReadCacheComicUsage {
     aid: String,
-    last_known_chapter_id: String,
-    last_known_chapter_sort: Option<String>,
-    updated_at: i64,
+    files: u64,
+    bytes: u64,
+    thumb_bytes: u64,
+    total_bytes: u64,
+    accessed_at: i64,
+    pinned: bool,
+    quota_bytes: Option<u64>,
+    title: Option<String>,
+    author: Option<String>,
+    cover_url: Option<String>,
+    cover_path: Option<String>,
+    last_read_at: Option<i64>,
+    last_chapter_name: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct FollowUpdateEntry {
-    aid: String,
-    latest_chapter_id: String,
-    latest_chapter_sort: Option<String>,
-    updated_at: i64,
+struct ReadCacheChapterUsage {
+    chapter_id: Option<String>,
+    pages: u64,
+    bytes: u64,
+    accessed_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct LocalFavoriteView {
-    aid: String,
-    title: String,
-    author: String,
-    cover_url: String,
-    added_at: i64,
-    updated_at: i64,
-    latest_chapter_sort: Option<String>,
+struct DiskSpaceStatus {
+    cache_dir: String,
+    cache_free_bytes: Option<u64>,
+    cache_total_bytes: Option<u64>,
+    cache_used_bytes: u64,
+    data_dir: String,
+    data_free_bytes: Option<u64>,
+    data_total_bytes: Option<u64>,
+    data_used_bytes: u64,
+    min_free_bytes: u64,
+    low: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+// `(len, mtime)` of the backing file; `None` for bytes that only live in memory.
+type FileValidator = Option<(u64, Option<std::time::SystemTime>)>;
+
+struct PageMemoryEntry {
+    bytes: Arc<Vec<u8>>,
+    validator: FileValidator,
+    tick: u64,
+}
+
+// Byte-budgeted LRU of recently served page bytes; `order` maps use tick -> key.
+#[derive(Default)]
+struct PageMemoryCache {
+    entries: HashMap<String, PageMemoryEntry>,
+    order: std::collections::BTreeMap<u64, String>,
+    tick: u64,
+    used_bytes: u64,
+    budget_bytes: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct LocalFavoritesListResponse {
-    total: u64,
-    filtered: u64,
-    list: Vec<LocalFavoriteView>,
+struct MemoryCacheStats {
+    budget_bytes: u64,
+    used_bytes: u64,
+    entries: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct LatestChapterEntry {
-    aid: String,
-    latest_chapter_id: String,
-    latest_chapter_sort: Option<String>,
-    updated_at: i64,
+struct CacheMoveProgress {
+    phase: String,
+    done_files: u64,
+    total_files: u64,
+    done_bytes: u64,
+    total_bytes: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct LatestScanEntry {
+struct CacheMoveResult {
+    from: String,
+    to: String,
+    files: u64,
+    bytes: u64,
+    old_removed: bool,
+    elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct CacheEvictionOptions {
+    max_bytes: Option<u64>,
+    dry_run: bool,
+    // Treat local favorites as pinned; defaults to true.
+    protect_favorites: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct CacheEvictionComic {
     aid: String,
-    scanned_at: i64,
+    pages: u64,
+    bytes: u64,
+    quota_pages: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct CacheEvictionReport {
+    dry_run: bool,
+    evicted_pages: u64,
+    evicted_files: u64,
+    evicted_bytes: u64,
+    comics: Vec<CacheEvictionComic>,
+    stats: ReadCacheStats,
 }
 
+// One page of a download job; `num` is the descramble segment count.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ComicExtraEntry {
-    id: String,
+struct DownloadPage {
+    name: String,
+    url: String,
+    num: i64,
     #[serde(default)]
-    page_count: u64,
+    done: bool,
     #[serde(default)]
-    updated_at: i64,
+    error: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ExportResult {
-    path: String,
+struct DownloadChapter {
+    chapter_id: String,
+    #[serde(default)]
+    sort: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    // False until the page list and scramble nums have been fetched.
+    #[serde(default)]
+    resolved: bool,
+    // Non-empty entries in the chapter API's `images` list when the chapter was resolved.
+    #[serde(default)]
+    expected_pages: Option<u64>,
+    #[serde(default)]
+    pages: Vec<DownloadPage>,
 }
 
-struct LocalFavoritesStore {
-    tree: Option<sled::Tree>,
-    init_error: Option<String>,
+// Persisted in `download_jobs`; `state` is queued | running | paused | failed | done.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DownloadJob {
+    id: String,
+    kind: String,
+    aid: String,
+    #[serde(default)]
+    title: String,
+    // Album metadata kept for exports, so they can run without the network.
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    description: String,
+    state: String,
+    #[serde(default)]
+    chapters: Vec<DownloadChapter>,
+    #[serde(default)]
+    img_base: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    total_pages: u64,
+    #[serde(default)]
+    done_pages: u64,
+    #[serde(default)]
+    failed_pages: u64,
+    // Queued by the auto-download rule rather than by the user.
+    #[serde(default)]
+    auto: bool,
+    created_at: i64,
+    updated_at: i64,
 }
 
-impl LocalFavoritesStore {
-    fn open() -> Self {
-        let base_dir = match resolve_data_dir() {
-            Ok(p) => p,
-            Err(e) => {
-                logl!("[tauri][localfav] resolve data dir failed: {e}");
-                return Self {
-                    tree: None,
-                    init_error: Some(e),
-                };
-            }
-        };
-
-        let db_dir = base_dir.join("local-favorites.sled");
-        match sled::open(&db_dir)
-            .and_then(|db| db.open_tree("local_favorites").map(|t| (db, t)))
-        {
-            Ok((_db, tree)) => {
-                logl!("[tauri][localfav] sled opened at {:?}", db_dir);
-                Self {
-                    tree: Some(tree),
-                    init_error: None,
-                }
-            }
-            Err(e) => {
-                let msg = format!("open sled failed at {:?}: {e}", db_dir);
-                logl!("[tauri][localfav] {msg}");
-                Self {
-                    tree: None,
-                    init_error: Some(msg),
-                }
-            }
-        }
-    }
-
-    fn tree(&self) -> Result<&sled::Tree, String> {
-        self.tree
-            .as_ref()
-            .ok_or_else(|| self.init_error.clone().unwrap_or_else(|| "local favorites store unavailable".into()))
-    }
+// Job without page lists, used by the list command and `download-progress` events.
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DownloadJobSummary {
+    id: String,
+    kind: String,
+    aid: String,
+    title: String,
+    state: String,
+    error: Option<String>,
+    chapters: u64,
+    resolved_chapters: u64,
+    total_pages: u64,
+    done_pages: u64,
+    failed_pages: u64,
+    auto: bool,
+    created_at: i64,
+    updated_at: i64,
 }
 
-#[derive(Debug, Deserialize)]
+// Size and md5 of a downloaded page as first written; `file` is relative to `read/<aid>/`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ApiLoginResponse {
-    code: i64,
+struct ManifestPage {
+    name: String,
+    url: String,
+    num: i64,
     #[serde(default)]
-    error_msg: String,
+    file: Option<String>,
     #[serde(default)]
-    message: String,
-    data: Option<String>,
+    size: u64,
+    #[serde(default)]
+    md5: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ApiEnvelope {
-    code: i64,
+struct ManifestChapter {
+    chapter_id: String,
     #[serde(default)]
-    error_msg: String,
+    sort: Option<String>,
     #[serde(default)]
-    message: String,
-    data: serde_json::Value,
+    name: Option<String>,
+    // Image count reported by the chapter API.
+    expected_pages: u64,
+    pages: Vec<ManifestPage>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ApiUserInfo {
-    uid: serde_json::Value,
-    username: String,
-    #[serde(rename = "level_name")]
-    level_name: String,
-    #[serde(deserialize_with = "de_i64")]
-    level: i64,
-    #[serde(deserialize_with = "de_i64")]
-    coin: i64,
+// Persisted in `download_manifests`, one per album across its album and chapter jobs.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DownloadManifest {
+    aid: String,
     #[serde(default)]
-    gender: String,
-    #[serde(rename = "album_favorites")]
-    #[serde(deserialize_with = "de_i64")]
-    favorites: i64,
-    #[serde(rename = "album_favorites_max")]
-    #[serde(deserialize_with = "de_i64")]
-    can_favorites: i64,
-    #[serde(default, deserialize_with = "de_i64_default")]
-    exp: i64,
-    #[serde(rename = "nextLevelExp", default, deserialize_with = "de_i64_default")]
-    next_level_exp: i64,
+    title: String,
+    chapters: Vec<ManifestChapter>,
+    created_at: i64,
+    updated_at: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct LoginResult {
-    user: ApiUserInfo,
-    cookies: HashMap<String, String>,
+struct IntegrityIssue {
+    chapter_id: String,
+    index: u64,
+    name: String,
+    // missing | truncated | undecodable | mismatch | count, or repair when repairing failed
+    problem: String,
+    detail: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ImagePayload {
-    mime: String,
-    data_b64: String,
+struct IntegrityReport {
+    aid: String,
+    title: String,
+    chapters: u64,
+    expected_pages: u64,
+    checked_pages: u64,
+    ok_pages: u64,
+    issues: Vec<IntegrityIssue>,
+    repaired_pages: u64,
+    elapsed_ms: u64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ApiBaseLatency {
-    base: String,
-    ms: i64,
-    ok: bool,
-    status: Option<u16>,
+struct IntegrityProgress {
+    aid: String,
+    // verify | repair
+    phase: String,
+    done: u64,
+    total: u64,
 }
 
-#[derive(Default)]
-struct CancelRegistry {
-    map: Mutex<HashMap<String, (Arc<AtomicBool>, i64)>>,
-}
-
-impl CancelRegistry {
-    fn cleanup_locked(map: &mut HashMap<String, (Arc<AtomicBool>, i64)>, now: i64) {
-        // Keep memory bounded: drop tokens not touched for ~10 minutes.
-        const TTL_SECS: i64 = 10 * 60;
-        map.retain(|_, (_, ts)| now.saturating_sub(*ts) <= TTL_SECS);
-
-        // Hard cap as a safety net.
-        const MAX: usize = 512;
-        if map.len() <= MAX {
-            return;
-        }
-        // Remove oldest entries first.
-        let mut keys: Vec<(String, i64)> = map.iter().map(|(k, (_, ts))| (k.clone(), *ts)).collect();
-        keys.sort_by_key(|(_, ts)| *ts);
-        for (k, _) in keys.into_iter().take(map.len().saturating_sub(MAX)) {
-            map.remove(&k);
-        }
-    }
-
-    fn token_for(&self, key: &str) -> Arc<AtomicBool> {
-        let mut map = self.map.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
-        Self::cleanup_locked(&mut map, now);
-        map.entry(key.to_string())
-            .and_modify(|(_, ts)| *ts = now)
-            .or_insert_with(|| (Arc::new(AtomicBool::new(false)), now))
-            .0
-            .clone()
-    }
-
-    fn cancel(&self, key: &str) {
-        let mut map = self.map.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
-        Self::cleanup_locked(&mut map, now);
-        let token = map
-            .entry(key.to_string())
-            .and_modify(|(t, ts)| {
-                t.store(true, Ordering::Relaxed);
-                *ts = now;
-            })
-            .or_insert_with(|| {
-                let t = Arc::new(AtomicBool::new(true));
-                (t, now)
-            })
-            .0
-            .clone();
-        token.store(true, Ordering::Relaxed);
-    }
+// Per-favorite override of the global auto-download switch, kept in `auto_download_rules`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AutoDownloadRule {
+    aid: String,
+    enabled: bool,
+    updated_at: i64,
 }
 
-fn log_file_path() -> std::path::PathBuf {
-    if let Ok(dir) = std::env::var("JM_LOG_DIR") {
-        return std::path::PathBuf::from(dir).join("jm.log");
-    }
-
-    let exe_dir = std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|x| x.to_path_buf()));
-
-    let primary = exe_dir
-        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")))
-        .join("jmcomic-logs")
-        .join("jm.log");
-
-    primary
+// New chapters after `from_chapter_id` that a limit kept from being queued.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AutoDownloadPending {
+    aid: String,
+    from_chapter_id: String,
+    deferred_at: i64,
 }
 
-fn get_log_writer() -> Option<&'static Mutex<std::io::BufWriter<std::fs::File>>> {
-    LOG_WRITER.get_or_init(|| {
-        let primary = log_file_path();
-
-        let try_open = |path: &std::path::Path| -> std::io::Result<std::io::BufWriter<std::fs::File>> {
-            if let Some(parent) = path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
-            let file = std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(path)?;
-            Ok(std::io::BufWriter::new(file))
-        };
-
-        let writer = try_open(&primary).or_else(|_| {
-            let fallback = std::env::temp_dir().join("jmcomic-logs").join("jm.log");
-            try_open(&fallback)
-        });
-
-        Mutex::new(writer.unwrap_or_else(|_| {
-            // Last resort: /dev/null style sink, so logging doesn't panic.
-            let file = if cfg!(windows) {
-                std::fs::OpenOptions::new()
-                    .write(true)
-                    .open("NUL")
-                    .unwrap_or_else(|_| std::fs::File::create(std::env::temp_dir().join("jm.log")).unwrap())
-            } else {
-                std::fs::OpenOptions::new()
-                    .write(true)
-                    .open("/dev/null")
-                    .unwrap_or_else(|_| std::fs::File::create(std::env::temp_dir().join("jm.log")).unwrap())
-            };
-            std::io::BufWriter::new(file)
-        }))
-    });
-    Some(LOG_WRITER.get().expect("just initialized"))
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AutoDownloadLogEntry {
+    aid: String,
+    title: String,
+    chapter_id: String,
+    #[serde(default)]
+    chapter_sort: Option<String>,
+    #[serde(default)]
+    chapter_name: Option<String>,
+    #[serde(default)]
+    job_id: Option<String>,
+    // queued | deferred | failed
+    outcome: String,
+    #[serde(default)]
+    reason: Option<String>,
+    at: i64,
 }
 
-fn config_path() -> Result<std::path::PathBuf, String> {
-    Ok(resolve_data_dir()?.join("config.json"))
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AutoDownloadSettings {
+    enabled: bool,
+    max_jobs: u64,
+    max_bytes: Option<u64>,
+    rules: Vec<AutoDownloadRule>,
 }
 
-fn api_domain_cache_path() -> Result<std::path::PathBuf, String> {
-    Ok(resolve_data_dir()?.join("api-domain-list.json"))
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ExportProgress {
+    aid: String,
+    format: String,
+    // resolve | download | write | done
+    phase: String,
+    done: u64,
+    total: u64,
 }
 
-fn load_api_domain_cache() -> Vec<String> {
-    let path = match api_domain_cache_path() {
-        Ok(p) => p,
-        Err(_) => return Vec::new(),
-    };
-    let bytes = match std::fs::read(&path) {
-        Ok(b) => b,
-        Err(_) => return Vec::new(),
-    };
-    let raw: Vec<String> = match serde_json::from_slice(&bytes) {
-        Ok(v) => v,
-        Err(_) => return Vec::new(),
-    };
-    let mut out = Vec::new();
-    for item in raw {
-        if let Some(v) = normalize_api_base_root(&item) {
-            if !out.contains(&v) {
-                out.push(v);
-            }
-        }
-    }
-    out
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ComicExportResult {
+    files: Vec<String>,
+    chapters: u64,
+    pages: u64,
+    bytes: u64,
+    downloaded_pages: u64,
+    elapsed_ms: u64,
 }
 
-fn save_api_domain_cache(list: &[String]) -> Result<(), String> {
-    let path = api_domain_cache_path()?;
-    let tmp = path.with_extension("json.tmp");
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
-    }
-    let bytes =
-        serde_json::to_vec_pretty(list).map_err(|e| format!("serialize api domain cache failed: {e}"))?;
-    std::fs::write(&tmp, &bytes).map_err(|e| format!("write api domain cache tmp failed: {e}"))?;
-    std::fs::rename(&tmp, &path).map_err(|e| format!("commit api domain cache failed: {e}"))?;
-    Ok(())
+// Pages of one chapter, resolved to cached files in reading order.
+struct ExportChapter {
+    sort: Option<String>,
+    name: Option<String>,
+    pages: Vec<std::path::PathBuf>,
 }
 
-fn read_progress_db() -> Result<sled::Db, String> {
-    let res = READ_PROGRESS_DB.get_or_init(|| {
-        let data_dir = resolve_data_dir()?;
-        let path = data_dir.join("read-progress.sled");
-        let import_dir = data_dir.join("read-progress.import");
-        if import_dir.exists() {
-            let ts = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-            let backup = data_dir.join(format!("read-progress.sled.backup.{ts}"));
-            if path.exists() {
-                let _ = std::fs::rename(&path, &backup);
-            }
-            if let Err(e) = std::fs::rename(&import_dir, &path) {
-                return Err(format!("apply read progress import failed: {e}"));
-            }
-            logl!("[tauri][read] applied imported read progress db");
-        }
-        match sled::open(&path) {
-            Ok(db) => Ok(db),
-            Err(e) => {
-                let msg = e.to_string();
-                let lower = msg.to_ascii_lowercase();
-                let should_repair = lower.contains("corrupt")
-                    || lower.contains("checksum")
-                    || lower.contains("invalid")
-                    || lower.contains("incompatible");
-                if !should_repair {
-                    return Err(format!("open read progress db failed: {e}"));
-                }
-
-                let ts = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-                let backup = data_dir.join(format!("read-progress.sled.broken.{ts}"));
-                if let Err(rename_err) = std::fs::rename(&path, &backup) {
-                    return Err(format!(
-                        "open read progress db failed: {e}; rename failed: {rename_err}"
-                    ));
-                }
-                logl!(
-                    "[tauri][read] read progress db corrupted, moved to {:?}",
-                    backup
-                );
-                sled::open(&path)
-                    .map_err(|e| format!("open read progress db failed after repair: {e}"))
-            }
-        }
-    });
-    match res {
-        Ok(db) => Ok(db.clone()),
-        Err(e) => Err(e.clone()),
-    }
+struct ExportPlan {
+    aid: String,
+    title: String,
+    authors: Vec<String>,
+    tags: Vec<String>,
+    description: String,
+    chapters: Vec<ExportChapter>,
+    // Chapters in the whole series, when the full chapter list is known.
+    series_count: Option<usize>,
+    // Pages that had to be fetched before exporting.
+    downloaded: u64,
 }
 
-fn read_progress_tree() -> Result<sled::Tree, String> {
-    read_progress_db()?
-        .open_tree("read_progress")
-        .map_err(|e| format!("open read progress tree failed: {e}"))
+// Mirror folder state, kept as `.jm-library.json` inside each series folder.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LibraryManifest {
+    aid: String,
+    #[serde(default)]
+    title: String,
+    // chapter id -> archive in the series folder
+    #[serde(default)]
+    chapters: HashMap<String, LibraryChapterFile>,
 }
 
-fn read_follow_tree() -> Result<sled::Tree, String> {
-    read_progress_db()?
-        .open_tree("read_follow_state")
-        .map_err(|e| format!("open read follow tree failed: {e}"))
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LibraryChapterFile {
+    file: String,
+    pages: u64,
+    updated_at: i64,
 }
 
-fn read_update_tree() -> Result<sled::Tree, String> {
-    read_progress_db()?
-        .open_tree("read_updates")
-        .map_err(|e| format!("open read updates tree failed: {e}"))
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LibrarySyncReport {
+    comics: u64,
+    written: u64,
+    renamed: u64,
+    unchanged: u64,
+    incomplete: u64,
+    errors: Vec<String>,
 }
 
-fn read_latest_tree() -> Result<sled::Tree, String> {
-    read_progress_db()?
-        .open_tree("read_latest")
-        .map_err(|e| format!("open read latest tree failed: {e}"))
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TransferClassStatus {
+    // Bytes per second; `None` is unlimited.
+    limit: Option<u64>,
+    // Average over the last few seconds.
+    bytes_per_sec: u64,
+    total_bytes: u64,
 }
 
-fn read_latest_seen_tree() -> Result<sled::Tree, String> {
-    read_progress_db()?
-        .open_tree("read_latest_seen")
-        .map_err(|e| format!("open read latest seen tree failed: {e}"))
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct BandwidthStatus {
+    interactive: TransferClassStatus,
+    background: TransferClassStatus,
+    windows: Vec<String>,
+    // False while outside every background window; background transfers wait.
+    background_allowed: bool,
 }
 
-fn read_comic_extra_tree() -> Result<sled::Tree, String> {
-    read_progress_db()?
-        .open_tree("comic_extra")
-        .map_err(|e| format!("open comic extra tree failed: {e}"))
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct OfflineStatus {
+    mode: String,
+    // Auto mode is currently serving snapshots because the API was unreachable.
+    network_down: bool,
+    retry_at: Option<i64>,
 }
 
-fn export_read_progress_zip(path: &std::path::Path) -> Result<(), String> {
-    let data_dir = resolve_data_dir()?;
-    let db_dir = data_dir.join("read-progress.sled");
-    if !db_dir.exists() {
-        return Err("read progress db not found".to_string());
-    }
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct OfflineComicStatus {
+    aid: String,
+    title: String,
+    has_album_snapshot: bool,
+    chapters: u64,
+    complete_chapters: u64,
+    total_pages: u64,
+    cached_pages: u64,
+    // Album, every chapter and every page are available without the network.
+    full: bool,
+}
 
-    if let Some(Ok(db)) = READ_PROGRESS_DB.get() {
-        let _ = db.flush();
-    }
+// An imported CBZ/ZIP or image folder; `pages` are archive entry names or paths relative
+// to `source`, in reading order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LocalComic {
+    id: String,
+    source: String,
+    // archive | folder
+    kind: String,
+    title: String,
+    #[serde(default)]
+    series: Option<String>,
+    #[serde(default)]
+    number: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    description: String,
+    pages: Vec<String>,
+    // Archive size and mtime at import; a change invalidates extracted pages.
+    #[serde(default)]
+    source_size: u64,
+    #[serde(default)]
+    source_modified: i64,
+    imported_at: i64,
+    updated_at: i64,
+}
 
-    let file = std::fs::File::create(path).map_err(|e| format!("create export file failed: {e}"))?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LocalComicView {
+    id: String,
+    source: String,
+    kind: String,
+    title: String,
+    series: Option<String>,
+    number: Option<String>,
+    authors: Vec<String>,
+    tags: Vec<String>,
+    description: String,
+    page_count: u64,
+    // False when the archive or folder has been moved away since import.
+    available: bool,
+    imported_at: i64,
+    updated_at: i64,
+}
 
-    let mut stack = vec![db_dir.clone()];
-    while let Some(dir) = stack.pop() {
-        for entry in std::fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))? {
-            let entry = entry.map_err(|e| format!("read dir entry failed: {e}"))?;
-            let path = entry.path();
-            let rel = path.strip_prefix(&data_dir).unwrap_or(&path);
-            let name = rel.to_string_lossy().replace('\\', "/");
-            if path.is_dir() {
-                zip.add_directory(format!("{name}/"), options)
-                    .map_err(|e| format!("zip add dir failed: {e}"))?;
-                stack.push(path);
-            } else {
-                zip.start_file(name, options)
-                    .map_err(|e| format!("zip start file failed: {e}"))?;
-                let mut f = std::fs::File::open(&path)
-                    .map_err(|e| format!("zip open file failed: {e}"))?;
-                std::io::copy(&mut f, &mut zip)
-                    .map_err(|e| format!("zip write file failed: {e}"))?;
-            }
-        }
-    }
+// The job the download worker is currently running; `stop_to` is the state a command asked for.
+struct ActiveDownload {
+    id: String,
+    stop: Arc<AtomicBool>,
+    stop_to: Option<String>,
+}
 
-    zip.finish().map_err(|e| format!("zip finish failed: {e}"))?;
-    Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadProgressEntry {
+    aid: String,
+    updated_at: i64,
+    #[serde(default)]
+    chapter_id: Option<String>,
+    #[serde(default)]
+    chapter_sort: Option<String>,
+    #[serde(default)]
+    chapter_name: Option<String>,
+    #[serde(default)]
+    page_index: Option<i64>,
 }
 
-fn import_read_progress_zip(path: &std::path::Path) -> Result<(), String> {
-    let data_dir = resolve_data_dir()?;
-    let import_dir = data_dir.join("read-progress.import");
-    if import_dir.exists() {
-        std::fs::remove_dir_all(&import_dir)
-            .map_err(|e| format!("remove old import dir failed: {e}"))?;
-    }
-    std::fs::create_dir_all(&import_dir).map_err(|e| format!("mkdir failed: {e}"))?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FollowStateEntry {
+    aid: String,
+    last_known_chapter_id: String,
+    last_known_chapter_sort: Option<String>,
+    updated_at: i64,
+}
 
-    let file = std::fs::File::open(path).map_err(|e| format!("open import file failed: {e}"))?;
-    let mut archive =
-        zip::ZipArchive::new(file).map_err(|e| format!("open zip failed: {e}"))?;
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i).map_err(|e| format!("zip entry failed: {e}"))?;
-        let name = file.name().to_string();
-        if name.is_empty() {
-            continue;
-        }
-        let out_path = import_dir.join(name);
-        if file.name().ends_with('/') {
-            std::fs::create_dir_all(&out_path)
-                .map_err(|e| format!("mkdir failed: {e}"))?;
-        } else {
-            if let Some(parent) = out_path.parent() {
-                std::fs::create_dir_all(parent)
-                    .map_err(|e| format!("mkdir failed: {e}"))?;
-            }
-            let mut out = std::fs::File::create(&out_path)
-                .map_err(|e| format!("create file failed: {e}"))?;
-            std::io::copy(&mut file, &mut out)
-                .map_err(|e| format!("write file failed: {e}"))?;
-        }
-    }
-    Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FollowUpdateEntry {
+    aid: String,
+    latest_chapter_id: String,
+    latest_chapter_sort: Option<String>,
+    updated_at: i64,
 }
 
-fn load_config_from_disk() -> AppConfig {
-    let path = match config_path() {
-        Ok(p) => p,
-        Err(_) => return AppConfig::default(),
-    };
-    let bytes = match std::fs::read(&path) {
-        Ok(b) => b,
-        Err(_) => return AppConfig::default(),
-    };
-    serde_json::from_slice(&bytes).unwrap_or_default()
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalFavoriteView {
+    aid: String,
+    title: String,
+    author: String,
+    cover_url: String,
+    added_at: i64,
+    updated_at: i64,
+    latest_chapter_sort: Option<String>,
 }
 
-fn config_state() -> &'static Mutex<AppConfig> {
-    APP_CONFIG.get_or_init(|| Mutex::new(load_config_from_disk()))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalFavoritesListResponse {
+    total: u64,
+    filtered: u64,
+    list: Vec<LocalFavoriteView>,
 }
 
-fn save_config_to_disk(cfg: &AppConfig) -> Result<(), String> {
-    let path = config_path()?;
-    let tmp = path.with_extension("json.tmp");
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
-    }
-    let bytes = serde_json::to_vec_pretty(cfg).map_err(|e| format!("serialize config failed: {e}"))?;
-    std::fs::write(&tmp, &bytes).map_err(|e| format!("write config tmp failed: {e}"))?;
-    std::fs::rename(&tmp, &path).map_err(|e| format!("commit config failed: {e}"))?;
-    Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestChapterEntry {
+    aid: String,
+    latest_chapter_id: String,
+    latest_chapter_sort: Option<String>,
+    updated_at: i64,
 }
 
-fn current_socks_proxy() -> Option<String> {
-    config_state().lock().ok().and_then(|c| c.socks_proxy.clone())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestScanEntry {
+    aid: String,
+    scanned_at: i64,
 }
 
-fn http_client() -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder();
-    if let Some(p) = current_socks_proxy() {
-        if !p.trim().is_empty() {
-            builder = builder
-                .proxy(reqwest::Proxy::all(&p).map_err(|e| format!("invalid proxy url: {e}"))?);
-        }
-    }
-    builder.build().map_err(|e| format!("create http client failed: {e}"))
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ComicExtraEntry {
+    id: String,
+    #[serde(default)]
+    page_count: u64,
+    #[serde(default)]
+    updated_at: i64,
 }
 
-fn extract_build_tag(version: &str) -> Option<String> {
-    let trimmed = version.trim();
-    let (_, suffix) = trimmed.split_once('+')?;
-    let suffix = suffix.trim();
-    if suffix.is_empty() {
-        None
-    } else {
-        Some(suffix.to_string())
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageMetaEntry {
+    chapter_id: String,
+    image_name: String,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    file_size: u64,
+    // Descrambled file relative to the cache dir, e.g. `read/<aid>/<key>.jpg`.
+    #[serde(default)]
+    cache_file: Option<String>,
+    updated_at: i64,
 }
 
-fn parse_base_version(raw: &str) -> Option<(u64, u64, u64)> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-    let main = trimmed
-        .split(|c| c == '+' || c == '-')
-        .next()
-        .unwrap_or("")
-        .trim();
-    let parts: Vec<&str> = main.split('.').collect();
-    if parts.len() < 3 {
-        return None;
-    }
-    let major = parts[0].parse().ok()?;
-    let minor = parts[1].parse().ok()?;
-    let patch = parts[2].parse().ok()?;
-    Some((major, minor, patch))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PageDimension {
+    index: usize,
+    image_name: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    file_size: Option<u64>,
 }
 
-fn parse_version_from_tag(tag: &str) -> Option<(u64, u64, u64)> {
-    let trimmed = tag.trim().trim_start_matches('v');
-    if trimmed.is_empty() {
-        return None;
-    }
-    let mut buf = String::new();
-    let mut dots = 0usize;
-    for ch in trimmed.chars() {
-        if ch.is_ascii_digit() {
-            buf.push(ch);
-            continue;
-        }
-        if ch == '.' {
-            buf.push(ch);
-            dots += 1;
-            continue;
-        }
-        if !buf.is_empty() {
-            break;
-        }
-    }
-    if dots < 2 {
-        return None;
-    }
-    parse_base_version(&buf)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportResult {
+    path: String,
 }
 
-fn pick_update_asset(assets: &[UpdateAssetInfo], os: &str) -> Option<UpdateAssetInfo> {
-    let normalized_os = os.to_lowercase();
-    let find_by_name = |name: &str| {
-        assets
-            .iter()
-            .find(|a| a.name.eq_ignore_ascii_case(name))
-            .cloned()
-    };
-    match normalized_os.as_str() {
-        "windows" => find_by_name("jm-windows.zip"),
-        "macos" => find_by_name("jm-macos.dmg"),
-        "android" => find_by_name("jm.apk"),
-        "linux" => find_by_name("jm-linux.AppImage").or_else(|| find_by_name("jm-linux.deb")),
-        _ => None,
+struct LocalFavoritesStore {
+    tree: Option<sled::Tree>,
+    init_error: Option<String>,
+}
+
+impl LocalFavoritesStore {
+    fn open() -> Self {
+        let base_dir = match resolve_data_dir() {
+            Ok(p) => p,
+            Err(e) => {
+                logl!("[tauri][localfav] resolve data dir failed: {e}");
+                return Self {
+                    tree: None,
+                    init_error: Some(e),
+                };
+            }
+        };
+
+        let db_dir = base_dir.join("local-favorites.sled");
+        match sled::open(&db_dir)
+            .and_then(|db| db.open_tree("local_favorites").map(|t| (db, t)))
+        {
+            Ok((_db, tree)) => {
+                logl!("[tauri][localfav] sled opened at {:?}", db_dir);
+                Self {
+                    tree: Some(tree),
+                    init_error: None,
+                }
+            }
+            Err(e) => {
+                let msg = format!("open sled failed at {:?}: {e}", db_dir);
+                logl!("[tauri][localfav] {msg}");
+                Self {
+                    tree: None,
+                    init_error: Some(msg),
+                }
+            }
+        }
+    }
+
+    fn tree(&self) -> Result<&sled::Tree, String> {
+        self.tree
+            .as_ref()
+            .ok_or_else(|| self.init_error.clone().unwrap_or_else(|| "local favorites store unavailable".into()))
     }
 }
 
-fn register_cookie_jar() -> Arc<reqwest::cookie::Jar> {
-    REGISTER_COOKIE_JAR
-        .get_or_init(|| Arc::new(reqwest::cookie::Jar::default()))
-        .clone()
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiLoginResponse {
+    code: i64,
+    #[serde(default)]
+    error_msg: String,
+    #[serde(default)]
+    message: String,
+    data: Option<String>,
 }
 
-fn register_client() -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder().cookie_provider(register_cookie_jar());
-    if let Some(p) = current_socks_proxy() {
-        if !p.trim().is_empty() {
-            builder = builder
-                .proxy(reqwest::Proxy::all(&p).map_err(|e| format!("invalid proxy url: {e}"))?);
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiEnvelope {
+    code: i64,
+    #[serde(default)]
+    error_msg: String,
+    #[serde(default)]
+    message: String,
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ApiUserInfo {
+    uid: serde_json::Value,
+    username: String,
+    #[serde(rename = "level_name")]
+    level_name: String,
+    #[serde(deserialize_with = "de_i64")]
+    level: i64,
+    #[serde(deserialize_with = "de_i64")]
+    coin: i64,
+    #[serde(default)]
+    gender: String,
+    #[serde(rename = "album_favorites")]
+    #[serde(deserialize_with = "de_i64")]
+    favorites: i64,
+    #[serde(rename = "album_favorites_max")]
+    #[serde(deserialize_with = "de_i64")]
+    can_favorites: i64,
+    #[serde(default, deserialize_with = "de_i64_default")]
+    exp: i64,
+    #[serde(rename = "nextLevelExp", default, deserialize_with = "de_i64_default")]
+    next_level_exp: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LoginResult {
+    user: ApiUserInfo,
+    cookies: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiBaseLatency {
+    base: String,
+    ms: i64,
+    ok: bool,
+    status: Option<u16>,
+}
+
+#[derive(Default)]
+struct CancelRegistry {
+    map: Mutex<HashMap<String, (Arc<AtomicBool>, i64)>>,
+}
+
+impl CancelRegistry {
+    fn cleanup_locked(map: &mut HashMap<String, (Arc<AtomicBool>, i64)>, now: i64) {
+        // Keep memory bounded: drop tokens not touched for ~10 minutes.
+        const TTL_SECS: i64 = 10 * 60;
+        map.retain(|_, (_, ts)| now.saturating_sub(*ts) <= TTL_SECS);
+
+        // Hard cap as a safety net.
+        const MAX: usize = 512;
+        if map.len() <= MAX {
+            return;
+        }
+        // Remove oldest entries first.
+        let mut keys: Vec<(String, i64)> = map.iter().map(|(k, (_, ts))| (k.clone(), *ts)).collect();
+        keys.sort_by_key(|(_, ts)| *ts);
+        for (k, _) in keys.into_iter().take(map.len().saturating_sub(MAX)) {
+            map.remove(&k);
         }
     }
-    builder.build().map_err(|e| format!("create http client failed: {e}"))
+
+    fn token_for(&self, key: &str) -> Arc<AtomicBool> {
+        let mut map = self.map.lock().unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self::cleanup_locked(&mut map, now);
+        map.entry(key.to_string())
+            .and_modify(|(_, ts)| *ts = now)
+            .or_insert_with(|| (Arc::new(AtomicBool::new(false)), now))
+            .0
+            .clone()
+    }
+
+    fn cancel(&self, key: &str) {
+        let mut map = self.map.lock().unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self::cleanup_locked(&mut map, now);
+        let token = map
+            .entry(key.to_string())
+            .and_modify(|(t, ts)| {
+                t.store(true, Ordering::Relaxed);
+                *ts = now;
+            })
+            .or_insert_with(|| {
+                let t = Arc::new(AtomicBool::new(true));
+                (t, now)
+            })
+            .0
+            .clone();
+        token.store(true, Ordering::Relaxed);
+    }
 }
 
-fn web_base_from_opt(web_base: Option<String>) -> String {
-    if let Some(raw) = web_base {
-        let trimmed = raw.trim();
-        if !trimmed.is_empty() && (trimmed.starts_with("http://") || trimmed.starts_with("https://"))
-        {
-            return trimmed.trim_end_matches('/').to_string();
-        }
+// Matches `identifier` in tauri.conf.json, so these dirs line up with `$APPDATA`/`$APPCACHE`.
+const APP_IDENTIFIER: &str = "com.aa.jm";
+// A file with this name next to the executable keeps everything beside it (portable mode).
+const PORTABLE_MARKER: &str = "jm.portable";
+
+fn exe_dir() -> Option<std::path::PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|x| x.to_path_buf()))
+}
+
+// The pre-XDG layout: `data/`, `jmcomic-cache/` and `jmcomic-logs/` beside the executable.
+fn legacy_app_dirs(portable: bool) -> AppDirs {
+    let base = exe_dir()
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")));
+    AppDirs {
+        config: base.join("data"),
+        data: base.join("data"),
+        cache: base.join("jmcomic-cache"),
+        logs: base.join("jmcomic-logs"),
+        portable,
     }
-    std::env::var("JM_WEB_BASE").unwrap_or_else(|_| JM_WEB_BASE_DEFAULT.to_string())
 }
 
-fn parse_toastr_message(html: &str) -> (bool, String) {
-    let find_msg = |needle: &str| -> Option<String> {
-        let start = html.find(needle)? + needle.len();
-        let tail = &html[start..];
-        let end = tail.find("\")")?;
-        Some(tail[..end].to_string())
+fn platform_app_dirs() -> Option<AppDirs> {
+    // Mobile builds point `JM_*_DIR` at the app sandbox in `run()`.
+    if cfg!(any(target_os = "android", target_os = "ios")) {
+        return None;
+    }
+    let logs = if cfg!(target_os = "macos") {
+        dirs::home_dir()?.join("Library").join("Logs").join(APP_IDENTIFIER)
+    } else {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)?
+            .join(APP_IDENTIFIER)
+            .join("logs")
     };
+    Some(AppDirs {
+        config: dirs::config_dir()?.join(APP_IDENTIFIER),
+        data: dirs::data_dir()?.join(APP_IDENTIFIER),
+        cache: dirs::cache_dir()?.join(APP_IDENTIFIER).join("jmcomic-cache"),
+        logs,
+        portable: false,
+    })
+}
 
-    if let Some(msg) = find_msg("toastr['success'](\"") {
-        return (true, msg);
+fn detect_app_dirs() -> AppDirs {
+    let portable = exe_dir().is_some_and(|d| d.join(PORTABLE_MARKER).exists());
+    if portable {
+        return legacy_app_dirs(true);
     }
-    if let Some(msg) = find_msg("toastr['error'](\"") {
-        return (false, msg);
+    platform_app_dirs().unwrap_or_else(|| legacy_app_dirs(false))
+}
+
+// Fixed by `migrate_legacy_dirs` at startup, which may keep some legacy dirs in use.
+fn app_dirs() -> &'static AppDirs {
+    APP_DIRS.get_or_init(detect_app_dirs)
+}
+
+fn log_file_path() -> std::path::PathBuf {
+    if let Ok(dir) = std::env::var("JM_LOG_DIR") {
+        return std::path::PathBuf::from(dir).join("jm.log");
     }
-    (false, String::new())
+    app_dirs().logs.join("jm.log")
+}
+
+fn dir_is_empty(path: &std::path::Path) -> bool {
+    std::fs::read_dir(path)
+        .map(|mut it| it.next().is_none())
+        .unwrap_or(true)
+}
+
+// Copies a tree, skipping files that already exist at the target with the same size.
+// `on_file` gets the target path and source size of every file copied.
+fn copy_dir_recursive(
+    src: &std::path::Path,
+    dst: &std::path::Path,
+    on_file: &mut dyn FnMut(&std::path::Path, u64),
+) -> std::io::Result<(u64, u64)> {
+    let (mut files, mut bytes) = (0u64, 0u64);
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            let (f, b) = copy_dir_recursive(&entry.path(), &target, on_file)?;
+            files += f;
+            bytes += b;
+        } else if meta.is_file() {
+            let same = std::fs::metadata(&target).is_ok_and(|m| m.len() == meta.len());
+            if !same {
+                let n = std::fs::copy(entry.path(), &target)?;
+                if n != meta.len() {
+                    return Err(std::io::Error::other(format!(
+                        "short copy {:?}: {n} of {} bytes",
+                        target,
+                        meta.len()
+                    )));
+                }
+            }
+            on_file(&target, meta.len());
+            bytes += meta.len();
+            files += 1;
+        }
+    }
+    Ok((files, bytes))
+}
+
+// Checks every copied file against the size its source had; totals alone would let a
+// short file be hidden by a stray extra one.
+fn verify_copied_files(copied: &[(std::path::PathBuf, u64)]) -> Result<(), String> {
+    for (path, len) in copied {
+        match std::fs::metadata(path) {
+            Ok(m) if m.len() == *len => {}
+            Ok(m) => return Err(format!("{:?} has {} bytes, expected {len}", path, m.len())),
+            Err(e) => return Err(format!("{:?} missing: {e}", path)),
+        }
+    }
+    Ok(())
 }
 
-fn scan_dir_bytes(path: &std::path::Path) -> (u64, u64) {
-    let mut files = 0u64;
-    let mut bytes = 0u64;
+fn count_dir_files(path: &std::path::Path) -> (u64, u64) {
+    let (mut files, mut bytes) = (0u64, 0u64);
     let mut stack = vec![path.to_path_buf()];
     while let Some(dir) = stack.pop() {
-        let entries = match std::fs::read_dir(&dir) {
-            Ok(v) => v,
-            Err(_) => continue,
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
         };
         for entry in entries.flatten() {
-            let p = entry.path();
-            if let Ok(meta) = entry.metadata() {
-                if meta.is_dir() {
-                    stack.push(p);
-                } else if meta.is_file() {
-                    files += 1;
-                    bytes += meta.len();
-                }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                stack.push(entry.path());
+            } else if meta.is_file() {
+                files += 1;
+                bytes += meta.len();
             }
         }
     }
     (files, bytes)
 }
 
-fn scan_read_cache_dirs(read_dir: &std::path::Path) -> Vec<ReadCacheDirStats> {
-    let mut out = Vec::new();
-    let entries = match std::fs::read_dir(read_dir) {
-        Ok(v) => v,
-        Err(_) => return out,
-    };
+// Moves `src` to `dst`: a rename when possible, otherwise copy into a staging dir,
+// verify each file's size, then swap it in. The source is only removed after
+// the copy checks out; an unremovable (read-only) source is left in place.
+fn migrate_dir(src: &std::path::Path, dst: &std::path::Path) -> Result<String, String> {
+    if !dst.exists() || dir_is_empty(dst) {
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+        }
+        let _ = std::fs::remove_dir(dst);
+        if std::fs::rename(src, dst).is_ok() {
+            return Ok(format!("moved {:?} -> {:?}", src, dst));
+        }
+    } else {
+        return Ok(format!("skip {:?}: target {:?} is not empty", src, dst));
+    }
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if !path.is_dir() {
+    let staging = dst.with_extension("migrating");
+    let _ = std::fs::remove_dir_all(&staging);
+    let mut files = Vec::new();
+    let copied = copy_dir_recursive(src, &staging, &mut |path, len| {
+        files.push((path.to_path_buf(), len))
+    })
+    .map_err(|e| {
+        let _ = std::fs::remove_dir_all(&staging);
+        format!("copy {:?} failed: {e}", src)
+    })?;
+    let expected = count_dir_files(src);
+    if copied != expected {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(format!(
+            "verify {:?} failed: copied {:?}, expected {:?}",
+            src, copied, expected
+        ));
+    }
+    if let Err(e) = verify_copied_files(&files) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(format!("verify {:?} failed: {e}", src));
+    }
+    std::fs::rename(&staging, dst).map_err(|e| format!("commit {:?} failed: {e}", dst))?;
+    match std::fs::remove_dir_all(src) {
+        Ok(()) => Ok(format!("copied {:?} -> {:?} ({} files)", src, dst, copied.0)),
+        Err(e) => Ok(format!(
+            "copied {:?} -> {:?} ({} files), source kept: {e}",
+            src, dst, copied.0
+        )),
+    }
+}
+
+// One-time move of the exe-adjacent layout into the platform dirs. Runs before
+// anything opens the logs or the sled databases; returns notes to log afterwards.
+// A dir that fails to move stays in use from its legacy location (config.json with the
+// data), and its migration is tried again on the next launch.
+fn migrate_legacy_dirs() -> Vec<String> {
+    let mut dirs = detect_app_dirs();
+    if dirs.portable {
+        return Vec::new();
+    }
+    let legacy = legacy_app_dirs(false);
+    let mut notes = Vec::new();
+    let mut data_failed = false;
+    let pairs = [
+        ("JM_LOG_DIR", &legacy.logs, &mut dirs.logs),
+        ("JM_DATA_DIR", &legacy.data, &mut dirs.data),
+        ("JM_CACHE_DIR", &legacy.cache, &mut dirs.cache),
+    ];
+    for (env, src, dst) in pairs {
+        if std::env::var_os(env).is_some() || src == dst || !src.is_dir() {
             continue;
         }
-        let aid = entry.file_name().to_string_lossy().to_string();
-        let mut bytes = 0u64;
-        let mut newest_ms = 0i64;
-        let mut stack = vec![path];
-        while let Some(dir) = stack.pop() {
-            let sub_entries = match std::fs::read_dir(&dir) {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-            for sub in sub_entries.flatten() {
-                let p = sub.path();
-                if let Ok(meta) = sub.metadata() {
-                    if meta.is_dir() {
-                        stack.push(p);
-                    } else if meta.is_file() {
-                        bytes += meta.len();
-                        if let Ok(modified) = meta.modified() {
-                            if let Ok(ms) = modified.duration_since(std::time::UNIX_EPOCH) {
-                                let ms = ms.as_millis() as i64;
-                                if ms > newest_ms {
-                                    newest_ms = ms;
-                                }
-                            }
-                        }
-                    }
+        match migrate_dir(src, dst) {
+            Ok(note) => notes.push(note),
+            Err(e) => {
+                notes.push(format!("migration failed, using {:?} for now: {e}", src));
+                *dst = src.clone();
+                data_failed |= env == "JM_DATA_DIR";
+            }
+        }
+    }
+    if data_failed {
+        dirs.config = legacy.config.clone();
+    }
+
+    // config.json used to sit in `data/`; it now has its own dir.
+    if std::env::var_os("JM_DATA_DIR").is_none() && dirs.config != dirs.data {
+        let old = dirs.data.join("config.json");
+        let new = dirs.config.join("config.json");
+        if old.exists() && !new.exists() {
+            let moved = std::fs::create_dir_all(&dirs.config)
+                .and_then(|_| std::fs::rename(&old, &new).or_else(|_| {
+                    std::fs::copy(&old, &new)?;
+                    std::fs::remove_file(&old)
+                }));
+            match moved {
+                Ok(()) => notes.push(format!("moved {:?} -> {:?}", old, new)),
+                Err(e) => {
+                    notes.push(format!("move config failed, reading it from {:?}: {e}", old));
+                    dirs.config = dirs.data.clone();
                 }
             }
         }
-
-        out.push(ReadCacheDirStats { aid, bytes, newest_ms });
     }
-    out
-}
-
-fn update_read_cache_stats(app: tauri::AppHandle) -> Result<(), String> {
-    let started = Instant::now();
-    let base = resolve_read_cache_dir(&app)?;
-    let read_dir = base.join("read");
-    if !read_dir.exists() {
-        return Ok(());
+    if APP_DIRS.set(dirs).is_err() {
+        notes.push("app dirs were resolved before migration".to_string());
     }
+    notes
+}
 
-    let mut total_files = 0u64;
-    let mut total_bytes = 0u64;
-    let mut total_comics = 0u64;
-    let updated_at = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_millis() as i64;
+fn get_log_writer() -> Option<&'static Mutex<std::io::BufWriter<std::fs::File>>> {
+    LOG_WRITER.get_or_init(|| {
+        let primary = log_file_path();
 
-    let mut per_comic: Vec<ReadCacheComicStats> = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(&read_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-            let aid = entry.file_name().to_string_lossy().to_string();
-            let (files, bytes) = scan_dir_bytes(&path);
-            if files == 0 {
-                continue;
+        let try_open = |path: &std::path::Path| -> std::io::Result<std::io::BufWriter<std::fs::File>> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
             }
-            total_comics += 1;
-            total_files += files;
-            total_bytes += bytes;
-            per_comic.push(ReadCacheComicStats {
-                aid,
-                files,
-                bytes,
-                updated_at,
-            });
-        }
-    }
-
-    let summary = ReadCacheStats {
-        total_bytes,
-        total_files,
-        total_comics,
-        updated_at,
-        elapsed_ms: started.elapsed().as_millis() as u64,
-    };
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            Ok(std::io::BufWriter::new(file))
+        };
 
-    let data_dir = resolve_data_dir()?;
-    let db = sled::open(data_dir.join("cache-stats.sled"))
-        .map_err(|e| format!("open cache stats db failed: {e}"))?;
-    let summary_tree = db
-        .open_tree("read_cache_summary")
-        .map_err(|e| format!("open cache summary tree failed: {e}"))?;
-    let comics_tree = db
-        .open_tree("read_cache_comics")
-        .map_err(|e| format!("open cache comics tree failed: {e}"))?;
+        let writer = try_open(&primary).or_else(|_| {
+            let fallback = std::env::temp_dir().join("jmcomic-logs").join("jm.log");
+            try_open(&fallback)
+        });
 
-    summary_tree
-        .insert("summary", serde_json::to_vec(&summary).map_err(|e| format!("encode summary failed: {e}"))?)
-        .map_err(|e| format!("write summary failed: {e}"))?;
+        Mutex::new(writer.unwrap_or_else(|_| {
+            // Last resort: /dev/null style sink, so logging doesn't panic.
+            let file = if cfg!(windows) {
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open("NUL")
+                    .unwrap_or_else(|_| std::fs::File::create(std::env::temp_dir().join("jm.log")).unwrap())
+            } else {
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open("/dev/null")
+                    .unwrap_or_else(|_| std::fs::File::create(std::env::temp_dir().join("jm.log")).unwrap())
+            };
+            std::io::BufWriter::new(file)
+        }))
+    });
+    Some(LOG_WRITER.get().expect("just initialized"))
+}
 
-    for item in per_comic {
-        let key = item.aid.as_bytes();
-        let val = serde_json::to_vec(&item).map_err(|e| format!("encode comic stat failed: {e}"))?;
-        let _ = comics_tree.insert(key, val);
+fn config_path() -> Result<std::path::PathBuf, String> {
+    // With a data dir override (mobile, tests) config stays next to the data.
+    if std::env::var_os("JM_DATA_DIR").is_some() {
+        return Ok(resolve_data_dir()?.join("config.json"));
     }
-
-    let _ = summary_tree.flush();
-    let _ = comics_tree.flush();
-    Ok(())
+    Ok(app_dirs().config.join("config.json"))
 }
 
-fn normalize_verify_url(input: &str, base_override: Option<String>) -> String {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return String::new();
-    }
-    let base = web_base_from_opt(base_override);
-    let base_host = base
-        .split("://")
-        .nth(1)
-        .and_then(|s| s.split('/').next())
-        .unwrap_or("");
-    if base_host.is_empty() {
-        return trimmed.to_string();
-    }
+fn api_domain_cache_path() -> Result<std::path::PathBuf, String> {
+    Ok(resolve_data_dir()?.join("api-domain-list.json"))
+}
 
-    if let Some(host) = trimmed
-        .split("://")
-        .nth(1)
-        .and_then(|s| s.split('/').next())
-    {
-        if !host.is_empty() && host != base_host {
-            return trimmed.replace(host, base_host);
+fn load_api_domain_cache() -> Vec<String> {
+    let path = match api_domain_cache_path() {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+    let bytes = match std::fs::read(&path) {
+        Ok(b) => b,
+        Err(_) => return Vec::new(),
+    };
+    let raw: Vec<String> = match serde_json::from_slice(&bytes) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+    let mut out = Vec::new();
+    for item in raw {
+        if let Some(v) = normalize_api_base_root(&item) {
+            if !out.contains(&v) {
+                out.push(v);
+            }
         }
     }
-    trimmed.to_string()
+    out
 }
 
-fn log_line(file: &str, line: u32, msg: std::fmt::Arguments<'_>) {
-    // dev: print to console
-    if cfg!(debug_assertions) || std::env::var("JM_LOG_STDERR").ok().as_deref() == Some("1") {
-        eprintln!("{file}:{line} {msg}");
-    }
-
-    // release: always write to file (best-effort)
-    if cfg!(debug_assertions) {
-        return;
+fn save_api_domain_cache(list: &[String]) -> Result<(), String> {
+    let path = api_domain_cache_path()?;
+    let tmp = path.with_extension("json.tmp");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
     }
+    let bytes =
+        serde_json::to_vec_pretty(list).map_err(|e| format!("serialize api domain cache failed: {e}"))?;
+    std::fs::write(&tmp, &bytes).map_err(|e| format!("write api domain cache tmp failed: {e}"))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("commit api domain cache failed: {e}"))?;
+    Ok(())
+}
 
-    let now_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_millis())
-        .unwrap_or(0);
+fn read_progress_db() -> Result<sled::Db, String> {
+    let res = READ_PROGRESS_DB.get_or_init(|| {
+        let data_dir = resolve_data_dir()?;
+        let path = data_dir.join("read-progress.sled");
+        let import_dir = data_dir.join("read-progress.import");
+        if import_dir.exists() {
+            let ts = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let backup = data_dir.join(format!("read-progress.sled.backup.{ts}"));
+            if path.exists() {
+                let _ = std::fs::rename(&path, &backup);
+            }
+            if let Err(e) = std::fs::rename(&import_dir, &path) {
+                return Err(format!("apply read progress import failed: {e}"));
+            }
+            logl!("[tauri][read] applied imported read progress db");
+        }
+        match sled::open(&path) {
+            Ok(db) => Ok(db),
+            Err(e) => {
+                let msg = e.to_string();
+                let lower = msg.to_ascii_lowercase();
+                let should_repair = lower.contains("corrupt")
+                    || lower.contains("checksum")
+                    || lower.contains("invalid")
+                    || lower.contains("incompatible");
+                if !should_repair {
+                    return Err(format!("open read progress db failed: {e}"));
+                }
 
-    if let Some(m) = get_log_writer() {
-        if let Ok(mut w) = m.lock() {
-            let _ = writeln!(w, "{now_ms} {file}:{line} {msg}");
-            // Reduce overhead: flush every 50 lines.
-            let n = LOG_LINE_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
-            if n % 50 == 0 {
-                let _ = w.flush();
+                let ts = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let backup = data_dir.join(format!("read-progress.sled.broken.{ts}"));
+                if let Err(rename_err) = std::fs::rename(&path, &backup) {
+                    return Err(format!(
+                        "open read progress db failed: {e}; rename failed: {rename_err}"
+                    ));
+                }
+                logl!(
+                    "[tauri][read] read progress db corrupted, moved to {:?}",
+                    backup
+                );
+                sled::open(&path)
+                    .map_err(|e| format!("open read progress db failed after repair: {e}"))
             }
         }
+    });
+    match res {
+        Ok(db) => Ok(db.clone()),
+        Err(e) => Err(e.clone()),
     }
 }
 
-#[tauri::command]
-async fn api_config_get() -> Result<AppConfig, String> {
-    Ok(config_state()
-        .lock()
-        .map_err(|_| "config lock poisoned".to_string())?
-        .clone())
+fn read_progress_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("read_progress")
+        .map_err(|e| format!("open read progress tree failed: {e}"))
 }
 
-#[tauri::command]
-async fn api_config_set_socks_proxy(proxy: Option<String>) -> Result<(), String> {
-    let proxy = proxy
-        .and_then(|s| {
-            let t = s.trim().to_string();
-            if t.is_empty() { None } else { Some(t) }
-        });
+fn read_follow_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("read_follow_state")
+        .map_err(|e| format!("open read follow tree failed: {e}"))
+}
 
-    if let Some(p) = &proxy {
-        reqwest::Proxy::all(p).map_err(|e| format!("invalid proxy url: {e}"))?;
-    }
+fn read_update_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("read_updates")
+        .map_err(|e| format!("open read updates tree failed: {e}"))
+}
 
-    let mut cfg = config_state()
-        .lock()
-        .map_err(|_| "config lock poisoned".to_string())?;
-    cfg.socks_proxy = proxy;
-    save_config_to_disk(&cfg)?;
-    Ok(())
+fn read_latest_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("read_latest")
+        .map_err(|e| format!("open read latest tree failed: {e}"))
 }
 
-#[tauri::command]
-async fn app_update_check(app: tauri::AppHandle) -> Result<UpdateCheckInfo, String> {
-    let current_version = app.package_info().version.to_string();
-    let current_tag = extract_build_tag(&current_version);
-    let is_release = current_tag.as_deref().map(|t| t.starts_with("jm-")).unwrap_or(false);
-    let client = http_client()?;
-    let resp = client
-        .get("https://api.github.com/repos/alexsunxl/jm-tauri/releases/latest")
-        .header(reqwest::header::USER_AGENT, "jm-tauri")
-        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
-        .send()
-        .await
-        .map_err(|e| format!("fetch release failed: {e}"))?;
-    let status = resp.status();
-    if !status.is_success() {
-        return Err(format!("release http status {}", status.as_u16()));
-    }
-    let json: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| format!("parse release json failed: {e}"))?;
-    let latest_tag = json
-        .get("tag_name")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let release_url = json
-        .get("html_url")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let notes = json
-        .get("body")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let assets = json
-        .get("assets")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|item| {
-                    let name = item.get("name")?.as_str()?.to_string();
-                    let url = item.get("browser_download_url")?.as_str()?.to_string();
-                    let size = item.get("size")?.as_u64().unwrap_or(0);
-                    Some(UpdateAssetInfo { name, url, size })
-                })
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-    let asset = pick_update_asset(&assets, std::env::consts::OS);
-
-    let mut has_update = false;
-    let mut compare_mode = None;
-    if let Some(latest) = latest_tag.as_deref() {
-        if is_release {
-            has_update = current_tag.as_deref().map(|t| t != latest).unwrap_or(true);
-            compare_mode = Some("tag".to_string());
-        } else if let (Some(cur), Some(lat)) =
-            (parse_base_version(&current_version), parse_version_from_tag(latest))
-        {
-            has_update = lat > cur;
-            compare_mode = Some("version".to_string());
-        } else {
-            has_update = true;
-            compare_mode = Some("unknown".to_string());
-        }
-    }
+fn read_latest_seen_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("read_latest_seen")
+        .map_err(|e| format!("open read latest seen tree failed: {e}"))
+}
 
-    Ok(UpdateCheckInfo {
-        current_version,
-        current_tag,
-        latest_tag,
-        release_url,
-        notes,
-        has_update,
-        asset,
-        is_dev: !is_release,
-        compare_mode,
-    })
+fn read_comic_extra_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("comic_extra")
+        .map_err(|e| format!("open comic extra tree failed: {e}"))
 }
 
-#[tauri::command]
-async fn app_update_download(
-    app: tauri::AppHandle,
-    url: String,
-    name: Option<String>,
-) -> Result<UpdateDownloadInfo, String> {
-    let trimmed = url.trim();
-    if trimmed.is_empty() {
-        return Err("download url is empty".to_string());
-    }
-    if !trimmed.starts_with("https://github.com/alexsunxl/jm-tauri/releases/download/") {
-        return Err("unsupported download url".to_string());
-    }
+fn read_page_meta_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("page_meta")
+        .map_err(|e| format!("open page meta tree failed: {e}"))
+}
 
-    let raw_name = name
-        .and_then(|n| {
-            let t = n.trim().to_string();
-            if t.is_empty() { None } else { Some(t) }
-        })
-        .or_else(|| trimmed.rsplit('/').next().map(|s| s.to_string()))
-        .unwrap_or_else(|| "update.bin".to_string());
-    let safe_name = sanitize_path_component(&raw_name);
+fn read_offline_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("offline_snapshots")
+        .map_err(|e| format!("open offline snapshots tree failed: {e}"))
+}
 
-    let base_dir = app
-        .path()
-        .download_dir()
-        .or_else(|_| app.path().app_data_dir())
-        .map_err(|e| format!("resolve download dir failed: {e}"))?;
-    let target_dir = base_dir.join("jm-updates");
-    std::fs::create_dir_all(&target_dir).map_err(|e| format!("mkdir failed: {e}"))?;
-    let dest = target_dir.join(&safe_name);
+fn read_download_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("download_jobs")
+        .map_err(|e| format!("open download jobs tree failed: {e}"))
+}
 
-    let client = http_client()?;
-    let mut resp = client
-        .get(trimmed)
-        .header(reqwest::header::USER_AGENT, "jm-tauri")
-        .send()
-        .await
-        .map_err(|e| format!("download failed: {e}"))?;
-    let status = resp.status();
-    if !status.is_success() {
-        return Err(format!("download http status {}", status.as_u16()));
-    }
+fn read_auto_download_rule_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("auto_download_rules")
+        .map_err(|e| format!("open auto download rules tree failed: {e}"))
+}
 
-    let mut file = std::fs::File::create(&dest).map_err(|e| format!("create file failed: {e}"))?;
-    while let Some(chunk) = resp
-        .chunk()
-        .await
-        .map_err(|e| format!("read update chunk failed: {e}"))?
-    {
-        file.write_all(&chunk).map_err(|e| format!("write update failed: {e}"))?;
-    }
+fn read_auto_download_pending_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("auto_download_pending")
+        .map_err(|e| format!("open auto download pending tree failed: {e}"))
+}
 
-    Ok(UpdateDownloadInfo {
-        path: dest.to_string_lossy().to_string(),
-        name: safe_name,
-    })
+fn read_auto_download_log_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("auto_download_log")
+        .map_err(|e| format!("open auto download log tree failed: {e}"))
 }
 
-#[tauri::command]
-async fn api_read_progress_upsert(entry: ReadProgressEntry) -> Result<(), String> {
-    let tree = read_progress_tree()?;
-    let key = entry.aid.as_bytes();
-    let val =
-        serde_json::to_vec(&entry).map_err(|e| format!("encode read progress failed: {e}"))?;
-    tree.insert(key, val)
-        .map_err(|e| format!("write read progress failed: {e}"))?;
-    let _ = tree.flush();
-    Ok(())
+fn read_manifest_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("download_manifests")
+        .map_err(|e| format!("open download manifests tree failed: {e}"))
 }
 
-#[tauri::command]
-async fn api_read_progress_clear(aid: String) -> Result<(), String> {
-    let tree = read_progress_tree()?;
-    tree.remove(aid.as_bytes())
-        .map_err(|e| format!("delete read progress failed: {e}"))?;
-    let _ = tree.flush();
-    Ok(())
+fn read_local_comic_tree() -> Result<sled::Tree, String> {
+    read_progress_db()?
+        .open_tree("local_comics")
+        .map_err(|e| format!("open local comics tree failed: {e}"))
 }
 
-#[tauri::command]
-async fn api_read_progress_export(path: Option<String>) -> Result<ExportResult, String> {
+fn export_read_progress_zip(path: &std::path::Path) -> Result<(), String> {
     let data_dir = resolve_data_dir()?;
-    let export_path = if let Some(p) = path {
-        let trimmed = p.trim();
-        if trimmed.is_empty() {
-            data_dir.join(format!(
-                "read-progress-export-{}.zip",
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0)
-            ))
-        } else {
-            std::path::PathBuf::from(trimmed)
-        }
-    } else {
-        data_dir.join(format!(
-            "read-progress-export-{}.zip",
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0)
-        ))
-    };
-    if let Some(parent) = export_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+    let db_dir = data_dir.join("read-progress.sled");
+    if !db_dir.exists() {
+        return Err("read progress db not found".to_string());
     }
-    export_read_progress_zip(&export_path)?;
-    Ok(ExportResult {
-        path: export_path.to_string_lossy().to_string(),
-    })
-}
 
-#[tauri::command]
-async fn api_read_progress_import(path: String) -> Result<(), String> {
-    let trimmed = path.trim();
-    if trimmed.is_empty() {
-        return Err("导入路径不能为空".to_string());
+    if let Some(Ok(db)) = READ_PROGRESS_DB.get() {
+        let _ = db.flush();
     }
-    let p = std::path::PathBuf::from(trimmed);
-    import_read_progress_zip(&p)?;
-    Ok(())
-}
 
-#[tauri::command]
-async fn api_follow_state_list() -> Result<Vec<FollowStateEntry>, String> {
-    let tree = read_follow_tree()?;
-    let mut out = Vec::new();
-    for item in tree.iter() {
-        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
-        if let Ok(entry) = serde_json::from_slice::<FollowStateEntry>(&val) {
-            out.push(entry);
+    let file = std::fs::File::create(path).map_err(|e| format!("create export file failed: {e}"))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    let mut stack = vec![db_dir.clone()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))? {
+            let entry = entry.map_err(|e| format!("read dir entry failed: {e}"))?;
+            let path = entry.path();
+            let rel = path.strip_prefix(&data_dir).unwrap_or(&path);
+            let name = rel.to_string_lossy().replace('\\', "/");
+            if path.is_dir() {
+                zip.add_directory(format!("{name}/"), options)
+                    .map_err(|e| format!("zip add dir failed: {e}"))?;
+                stack.push(path);
+            } else {
+                zip.start_file(name, options)
+                    .map_err(|e| format!("zip start file failed: {e}"))?;
+                let mut f = std::fs::File::open(&path)
+                    .map_err(|e| format!("zip open file failed: {e}"))?;
+                std::io::copy(&mut f, &mut zip)
+                    .map_err(|e| format!("zip write file failed: {e}"))?;
+            }
         }
     }
-    Ok(out)
-}
 
-#[tauri::command]
-async fn api_proxy_check(proxy: Option<String>) -> Result<String, String> {
-    let proxy = proxy
-        .and_then(|s| {
-            let t = s.trim().to_string();
-            if t.is_empty() { None } else { Some(t) }
-        })
-        .or_else(current_socks_proxy);
-
-    let Some(proxy) = proxy else {
-        return Err("未配置SOCKS代理".to_string());
-    };
+    zip.finish().map_err(|e| format!("zip finish failed: {e}"))?;
+    Ok(())
+}
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(8))
-        .proxy(reqwest::Proxy::all(&proxy).map_err(|e| format!("invalid proxy url: {e}"))?)
-        .build()
-        .map_err(|e| format!("create http client failed: {e}"))?;
+fn import_read_progress_zip(path: &std::path::Path) -> Result<(), String> {
+    let data_dir = resolve_data_dir()?;
+    let import_dir = data_dir.join("read-progress.import");
+    if import_dir.exists() {
+        std::fs::remove_dir_all(&import_dir)
+            .map_err(|e| format!("remove old import dir failed: {e}"))?;
+    }
+    std::fs::create_dir_all(&import_dir).map_err(|e| format!("mkdir failed: {e}"))?;
 
-    let resp = client
-        .get(JM_API_BASE_DEFAULT)
-        .send()
-        .await
-        .map_err(|e| format!("proxy request failed: {e}"))?;
-
-    let status = resp.status();
-    if status.is_success() || status.is_redirection() {
-        Ok(format!("代理连接成功（HTTP {}）", status.as_u16()))
-    } else {
-        Err(format!("代理连接失败（HTTP {}）", status.as_u16()))
+    let file = std::fs::File::open(path).map_err(|e| format!("open import file failed: {e}"))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("open zip failed: {e}"))?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| format!("zip entry failed: {e}"))?;
+        let name = file.name().to_string();
+        if name.is_empty() {
+            continue;
+        }
+        let out_path = import_dir.join(name);
+        if file.name().ends_with('/') {
+            std::fs::create_dir_all(&out_path)
+                .map_err(|e| format!("mkdir failed: {e}"))?;
+        } else {
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("mkdir failed: {e}"))?;
+            }
+            let mut out = std::fs::File::create(&out_path)
+                .map_err(|e| format!("create file failed: {e}"))?;
+            std::io::copy(&mut file, &mut out)
+                .map_err(|e| format!("write file failed: {e}"))?;
+        }
     }
+    Ok(())
 }
 
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+fn load_config_from_disk() -> AppConfig {
+    let path = match config_path() {
+        Ok(p) => p,
+        Err(_) => return AppConfig::default(),
+    };
+    let bytes = match std::fs::read(&path) {
+        Ok(b) => b,
+        Err(_) => return AppConfig::default(),
+    };
+    serde_json::from_slice(&bytes).unwrap_or_default()
 }
 
-fn md5_hex(input: &str) -> String {
-    format!("{:x}", md5::compute(input.as_bytes()))
+fn config_state() -> &'static Mutex<AppConfig> {
+    APP_CONFIG.get_or_init(|| Mutex::new(load_config_from_disk()))
 }
 
-fn de_i64<'de, D>(deserializer: D) -> Result<i64, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let v = serde_json::Value::deserialize(deserializer)?;
-    match v {
-        serde_json::Value::Number(n) => n
-            .as_i64()
-            .ok_or_else(|| serde::de::Error::custom("number out of range for i64")),
-        serde_json::Value::String(s) => s
-            .parse::<i64>()
-            .map_err(|_| serde::de::Error::custom("invalid i64 string")),
-        serde_json::Value::Null => Err(serde::de::Error::custom("null where i64 expected")),
-        other => Err(serde::de::Error::custom(format!(
-            "invalid type for i64: {other}"
-        ))),
+fn save_config_to_disk(cfg: &AppConfig) -> Result<(), String> {
+    let path = config_path()?;
+    let tmp = path.with_extension("json.tmp");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
     }
+    let bytes = serde_json::to_vec_pretty(cfg).map_err(|e| format!("serialize config failed: {e}"))?;
+    std::fs::write(&tmp, &bytes).map_err(|e| format!("write config tmp failed: {e}"))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("commit config failed: {e}"))?;
+    Ok(())
 }
 
-fn de_i64_default<'de, D>(deserializer: D) -> Result<i64, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let v = serde_json::Value::deserialize(deserializer)?;
-    match v {
-        serde_json::Value::Null => Ok(0),
-        serde_json::Value::Number(n) => n
-            .as_i64()
-            .ok_or_else(|| serde::de::Error::custom("number out of range for i64")),
-        serde_json::Value::String(s) => s
-            .parse::<i64>()
-            .map_err(|_| serde::de::Error::custom("invalid i64 string")),
-        other => Err(serde::de::Error::custom(format!(
-            "invalid type for i64: {other}"
-        ))),
-    }
+fn current_socks_proxy() -> Option<String> {
+    config_state().lock().ok().and_then(|c| c.socks_proxy.clone())
 }
 
-fn unpad_like_python(mut data: Vec<u8>) -> Vec<u8> {
-    if data.is_empty() {
-        return data;
+fn http_client() -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(p) = current_socks_proxy() {
+        if !p.trim().is_empty() {
+            builder = builder
+                .proxy(reqwest::Proxy::all(&p).map_err(|e| format!("invalid proxy url: {e}"))?);
+        }
     }
-    let pad = *data.last().unwrap() as usize;
-    let new_len = data.len().saturating_sub(pad);
-    data.truncate(new_len);
-    data
+    builder.build().map_err(|e| format!("create http client failed: {e}"))
 }
 
-fn preview_bytes_hex(bytes: &[u8], limit: usize) -> String {
-    let mut out = String::new();
-    let n = bytes.len().min(limit);
-    for (i, b) in bytes[..n].iter().enumerate() {
-        if i > 0 {
-            out.push(' ');
-        }
-        out.push_str(&format!("{b:02x}"));
+fn extract_build_tag(version: &str) -> Option<String> {
+    let trimmed = version.trim();
+    let (_, suffix) = trimmed.split_once('+')?;
+    let suffix = suffix.trim();
+    if suffix.is_empty() {
+        None
+    } else {
+        Some(suffix.to_string())
     }
-    out
 }
 
-fn looks_like_json_utf8(s: &str) -> bool {
-    let trimmed = s.trim_start_matches(['\u{0000}', ' ', '\n', '\r', '\t']);
-    trimmed.starts_with('{') || trimmed.starts_with('[')
+fn parse_base_version(raw: &str) -> Option<(u64, u64, u64)> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let main = trimmed
+        .split(|c| c == '+' || c == '-')
+        .next()
+        .unwrap_or("")
+        .trim();
+    let parts: Vec<&str> = main.split('.').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    let major = parts[0].parse().ok()?;
+    let minor = parts[1].parse().ok()?;
+    let patch = parts[2].parse().ok()?;
+    Some((major, minor, patch))
 }
 
-fn decode_resp_data(data_b64: &str, ts: i64) -> Result<String, String> {
-    let debug = std::env::var("JM_DEBUG_LOGIN").ok().as_deref() == Some("1");
-
-    let secret =
-        std::env::var("JM_APP_DATA_SECRET").unwrap_or_else(|_| JM_APP_DATA_SECRET_DEFAULT.into());
-
-    let key = md5_hex(&format!("{ts}{secret}")).into_bytes(); // jmcomic: md5hex(...).encode('utf-8')
-
-    let mut ciphertext = base64::engine::general_purpose::STANDARD
-        .decode(data_b64)
-        .map_err(|e| format!("base64 decode failed: {e}"))?;
-
-    type Aes256EcbDec = ecb::Decryptor<Aes256>;
-    let cipher =
-        Aes256EcbDec::new_from_slice(&key).map_err(|e| format!("cipher init failed: {e}"))?;
-
-    let decrypted_padded = cipher
-        .decrypt_padded_mut::<NoPadding>(&mut ciphertext)
-        .map_err(|e| format!("decrypt failed: {e}"))?
-        .to_vec();
-
-    let decrypted = unpad_like_python(decrypted_padded);
-    if debug {
-        logl!(
-            "[tauri][login][decrypt] secret={:?} key_len={} decrypted_len={} hex_preview={}",
-            secret,
-            key.len(),
-            decrypted.len(),
-            preview_bytes_hex(&decrypted, 32)
-        );
+fn parse_version_from_tag(tag: &str) -> Option<(u64, u64, u64)> {
+    let trimmed = tag.trim().trim_start_matches('v');
+    if trimmed.is_empty() {
+        return None;
     }
-
-    let s = String::from_utf8(decrypted).map_err(|e| format!("utf8 decode failed: {e}"))?;
-    if !looks_like_json_utf8(&s) {
-        return Err("decoded text is not json-like".into());
+    let mut buf = String::new();
+    let mut dots = 0usize;
+    for ch in trimmed.chars() {
+        if ch.is_ascii_digit() {
+            buf.push(ch);
+            continue;
+        }
+        if ch == '.' {
+            buf.push(ch);
+            dots += 1;
+            continue;
+        }
+        if !buf.is_empty() {
+            break;
+        }
     }
-    Ok(s)
+    if dots < 2 {
+        return None;
+    }
+    parse_base_version(&buf)
 }
 
-fn cookie_header(cookies: &HashMap<String, String>) -> String {
-    cookies
-        .iter()
-        .map(|(k, v)| format!("{k}={v}"))
-        .collect::<Vec<_>>()
-        .join("; ")
+fn pick_update_asset(assets: &[UpdateAssetInfo], os: &str) -> Option<UpdateAssetInfo> {
+    let normalized_os = os.to_lowercase();
+    let find_by_name = |name: &str| {
+        assets
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(name))
+            .cloned()
+    };
+    match normalized_os.as_str() {
+        "windows" => find_by_name("jm-windows.zip"),
+        "macos" => find_by_name("jm-macos.dmg"),
+        "android" => find_by_name("jm.apk"),
+        "linux" => find_by_name("jm-linux.AppImage").or_else(|| find_by_name("jm-linux.deb")),
+        _ => None,
+    }
 }
 
-fn normalize_api_base(input: &str) -> Option<String> {
-    let raw = input.trim();
-    if raw.is_empty() {
-        return None;
-    }
-    let with_scheme = if raw.starts_with("http://") || raw.starts_with("https://") {
-        raw.to_string()
-    } else {
-        format!("https://{raw}")
-    };
-    Some(with_scheme.trim_end_matches('/').to_string())
+fn register_cookie_jar() -> Arc<reqwest::cookie::Jar> {
+    REGISTER_COOKIE_JAR
+        .get_or_init(|| Arc::new(reqwest::cookie::Jar::default()))
+        .clone()
 }
 
-fn normalize_api_base_root(input: &str) -> Option<String> {
-    let normalized = normalize_api_base(input)?;
-    if let Some(pos) = normalized.find("://") {
-        let rest = &normalized[(pos + 3)..];
-        if let Some(slash) = rest.find('/') {
-            let prefix = &normalized[..pos];
-            let host = &rest[..slash];
-            return Some(format!("{prefix}://{host}"));
+fn register_client() -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().cookie_provider(register_cookie_jar());
+    if let Some(p) = current_socks_proxy() {
+        if !p.trim().is_empty() {
+            builder = builder
+                .proxy(reqwest::Proxy::all(&p).map_err(|e| format!("invalid proxy url: {e}"))?);
         }
     }
-    Some(normalized)
+    builder.build().map_err(|e| format!("create http client failed: {e}"))
 }
 
-fn parse_api_domain_list(text: &str) -> Vec<String> {
-    let mut out: Vec<String> = Vec::new();
-    for line in text.lines() {
-        for token in line.split(|c: char| c == ',' || c.is_whitespace()) {
-            if let Some(v) = normalize_api_base_root(token) {
-                if !out.contains(&v) {
-                    out.push(v);
-                }
-            }
+fn web_base_from_opt(web_base: Option<String>) -> String {
+    if let Some(raw) = web_base {
+        let trimmed = raw.trim();
+        if !trimmed.is_empty() && (trimmed.starts_with("http://") || trimmed.starts_with("https://"))
+        {
+            return trimmed.trim_end_matches('/').to_string();
         }
     }
-    out
+    std::env::var("JM_WEB_BASE").unwrap_or_else(|_| JM_WEB_BASE_DEFAULT.to_string())
 }
 
-fn parse_api_domain_payload(payload: &str) -> Vec<String> {
-    let value: serde_json::Value = match serde_json::from_str(payload) {
-        Ok(v) => v,
-        Err(_) => return Vec::new(),
-    };
-    let Some(server) = value.get("Server") else {
-        return Vec::new();
+fn parse_toastr_message(html: &str) -> (bool, String) {
+    let find_msg = |needle: &str| -> Option<String> {
+        let start = html.find(needle)? + needle.len();
+        let tail = &html[start..];
+        let end = tail.find("\")")?;
+        Some(tail[..end].to_string())
     };
-    if let Some(list) = server.as_array() {
-        let mut out = Vec::new();
-        for item in list {
-            if let Some(s) = item.as_str() {
-                if let Some(v) = normalize_api_base_root(s) {
-                    if !out.contains(&v) {
-                        out.push(v);
-                    }
-                }
-            }
-        }
-        return out;
+
+    if let Some(msg) = find_msg("toastr['success'](\"") {
+        return (true, msg);
     }
-    if let Some(s) = server.as_str() {
-        return parse_api_domain_list(s);
+    if let Some(msg) = find_msg("toastr['error'](\"") {
+        return (false, msg);
     }
-    Vec::new()
+    (false, String::new())
 }
 
-fn strip_leading_non_ascii(text: &str) -> &str {
-    for (pos, ch) in text.char_indices() {
-        if ch.is_ascii() {
-            return &text[pos..];
-        }
+fn cache_stats_db() -> Result<sled::Db, String> {
+    let res = CACHE_STATS_DB.get_or_init(|| {
+        let data_dir = resolve_data_dir()?;
+        sled::open(data_dir.join("cache-stats.sled"))
+            .map_err(|e| format!("open cache stats db failed: {e}"))
+    });
+    match res {
+        Ok(db) => Ok(db.clone()),
+        Err(e) => Err(e.clone()),
     }
-    text
 }
 
-fn decode_domain_server_payload(data_b64: &str) -> Result<String, String> {
-    let key = md5_hex(JM_API_DOMAIN_SERVER_SECRET).into_bytes();
-    let mut trimmed = data_b64.trim().to_string();
-    let pad = trimmed.len() % 4;
-    if pad != 0 {
-        trimmed.push_str(&"=".repeat(4 - pad));
-    }
-    let mut ciphertext = base64::engine::general_purpose::STANDARD
-        .decode(trimmed.as_bytes())
-        .or_else(|_| base64::engine::general_purpose::URL_SAFE.decode(trimmed.as_bytes()))
-        .map_err(|e| format!("base64 decode failed: {e}"))?;
-
-    type Aes256EcbDec = ecb::Decryptor<Aes256>;
-    let cipher =
-        Aes256EcbDec::new_from_slice(&key).map_err(|e| format!("cipher init failed: {e}"))?;
-
-    let decrypted_padded = cipher
-        .decrypt_padded_mut::<NoPadding>(&mut ciphertext)
-        .map_err(|e| format!("decrypt failed: {e}"))?
-        .to_vec();
-
-    let decrypted = unpad_like_python(decrypted_padded);
-    String::from_utf8(decrypted).map_err(|e| format!("utf8 decode failed: {e}"))
+fn cache_summary_tree() -> Result<sled::Tree, String> {
+    cache_stats_db()?
+        .open_tree("read_cache_summary")
+        .map_err(|e| format!("open cache summary tree failed: {e}"))
 }
 
-fn load_api_base_list() -> Vec<String> {
-    let mut out: Vec<String> = Vec::new();
-    let add = |out: &mut Vec<String>, s: &str| {
-        if let Some(v) = normalize_api_base_root(s) {
-            if !out.contains(&v) {
-                out.push(v);
-            }
-        }
-    };
+fn cache_comics_tree() -> Result<sled::Tree, String> {
+    cache_stats_db()?
+        .open_tree("read_cache_comics")
+        .map_err(|e| format!("open cache comics tree failed: {e}"))
+}
 
-    for item in load_api_domain_cache() {
-        add(&mut out, &item);
-    }
+fn cache_index_tree() -> Result<sled::Tree, String> {
+    cache_stats_db()?
+        .open_tree("cache_index")
+        .map_err(|e| format!("open cache index tree failed: {e}"))
+}
 
-    if let Ok(cfg) = config_state().lock() {
-        for item in &cfg.api_base_list {
-            add(&mut out, item);
-        }
-    }
+// Bumped when the index layout changes; a mismatch triggers one full rebuild.
+const CACHE_INDEX_VERSION: &[u8] = b"2";
+// Hits refresh `accessed_at` at most this often per file.
+const CACHE_TOUCH_INTERVAL_MS: i64 = 60_000;
 
-    if let Ok(list) = std::env::var("JM_API_BASE_LIST") {
-        for item in list.split(|c: char| c == ',' || c.is_whitespace()) {
-            add(&mut out, item);
-        }
-    }
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
 
-    if out.is_empty() {
-        if let Ok(single) = std::env::var("JM_API_BASE") {
-            add(&mut out, &single);
-        }
+fn add_signed(v: u64, delta: i64) -> u64 {
+    if delta >= 0 {
+        v.saturating_add(delta as u64)
+    } else {
+        v.saturating_sub(delta.unsigned_abs())
     }
+}
 
-    if out.is_empty() {
-        for item in JM_API_BASE_LIST_DEFAULT {
-            add(&mut out, item);
-        }
+// `(relative path, area, aid dir, url key)` for an indexed file: `<area>/<aid>/<key>[_w<n>].<ext>`,
+// or `cover/<key>.<ext>` with an empty aid.
+fn cache_index_location(path: &std::path::Path) -> Option<(String, String, String, String)> {
+    let file_name = path.file_name()?.to_str()?;
+    if file_name.starts_with('.') || file_name.ends_with(".tmp") {
+        return None;
     }
-
-    if out.is_empty() {
-        add(&mut out, JM_API_BASE_DEFAULT);
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    let aid_dir = path.parent()?;
+    let aid = aid_dir.file_name()?.to_str()?;
+    let area = aid_dir.parent()?.file_name()?.to_str()?;
+    if aid == "cover" && area != "read" && area != "thumb" {
+        return Some((
+            format!("cover/{file_name}"),
+            "cover".to_string(),
+            String::new(),
+            stem.to_string(),
+        ));
+    }
+    if area != "read" && area != "thumb" {
+        return None;
     }
-
-    out
-}
-
-fn api_bases_state() -> &'static Mutex<Vec<String>> {
-    JM_API_BASES.get_or_init(|| Mutex::new(load_api_base_list()))
+    let key = stem.split("_w").next().unwrap_or(stem);
+    Some((
+        format!("{area}/{aid}/{file_name}"),
+        area.to_string(),
+        aid.to_string(),
+        key.to_string(),
+    ))
 }
 
-fn get_api_bases() -> Vec<String> {
-    api_bases_state()
-        .lock()
-        .map(|v| v.clone())
+fn load_cache_summary(tree: &sled::Tree) -> ReadCacheStats {
+    tree.get("summary")
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
         .unwrap_or_default()
 }
 
-fn set_api_bases(list: Vec<String>) {
-    if list.is_empty() {
-        return;
+// Folds one file add/remove into the per-comic and summary aggregates.
+fn apply_cache_index_delta(
+    area: &str,
+    aid: &str,
+    files: i64,
+    bytes: i64,
+    accessed_at: i64,
+) -> Result<(), String> {
+    let summary_tree = cache_summary_tree()?;
+    let now = now_millis();
+    if area == "cover" {
+        let mut summary = load_cache_summary(&summary_tree);
+        summary.total_files = add_signed(summary.total_files, files);
+        summary.total_bytes = add_signed(summary.total_bytes, bytes);
+        summary.cover_files = add_signed(summary.cover_files, files);
+        summary.cover_bytes = add_signed(summary.cover_bytes, bytes);
+        summary.updated_at = now;
+        summary_tree
+            .insert(
+                "summary",
+                serde_json::to_vec(&summary).map_err(|e| format!("encode summary failed: {e}"))?,
+            )
+            .map_err(|e| format!("write summary failed: {e}"))?;
+        return Ok(());
     }
-    if let Ok(mut bases) = api_bases_state().lock() {
-        *bases = list;
-        JM_API_BASE_INDEX.store(0, Ordering::Relaxed);
+    let comics_tree = cache_comics_tree()?;
+
+    let mut comic: ReadCacheComicStats = comics_tree
+        .get(aid.as_bytes())
+        .map_err(|e| format!("read comic stat failed: {e}"))?
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_else(|| ReadCacheComicStats {
+            aid: aid.to_string(),
+            ..Default::default()
+        });
+    let had_pages = comic.files > 0;
+    if area == "thumb" {
+        comic.thumb_files = add_signed(comic.thumb_files, files);
+        comic.thumb_bytes = add_signed(comic.thumb_bytes, bytes);
+    } else {
+        comic.files = add_signed(comic.files, files);
+        comic.bytes = add_signed(comic.bytes, bytes);
+    }
+    comic.accessed_at = comic.accessed_at.max(accessed_at);
+    comic.updated_at = now;
+    if comic.files == 0 && comic.thumb_files == 0 {
+        comics_tree
+            .remove(aid.as_bytes())
+            .map_err(|e| format!("remove comic stat failed: {e}"))?;
+    } else {
+        let val = serde_json::to_vec(&comic).map_err(|e| format!("encode comic stat failed: {e}"))?;
+        comics_tree
+            .insert(aid.as_bytes(), val)
+            .map_err(|e| format!("write comic stat failed: {e}"))?;
     }
-}
 
-#[allow(dead_code)]
-fn update_api_base_list(list: Vec<String>) -> Result<(), String> {
-    if list.is_empty() {
-        return Ok(());
+    let mut summary = load_cache_summary(&summary_tree);
+    summary.total_files = add_signed(summary.total_files, files);
+    summary.total_bytes = add_signed(summary.total_bytes, bytes);
+    if area == "thumb" {
+        summary.thumb_files = add_signed(summary.thumb_files, files);
+        summary.thumb_bytes = add_signed(summary.thumb_bytes, bytes);
     }
-    {
-        let mut cfg = config_state()
-            .lock()
-            .map_err(|_| "config lock poisoned".to_string())?;
-        cfg.api_base_list = list.clone();
-        save_config_to_disk(&cfg)?;
+    match (had_pages, comic.files > 0) {
+        (false, true) => summary.total_comics += 1,
+        (true, false) => summary.total_comics = summary.total_comics.saturating_sub(1),
+        _ => {}
     }
-    set_api_bases(list);
+    summary.updated_at = now;
+    summary_tree
+        .insert(
+            "summary",
+            serde_json::to_vec(&summary).map_err(|e| format!("encode summary failed: {e}"))?,
+        )
+        .map_err(|e| format!("write summary failed: {e}"))?;
     Ok(())
 }
 
-fn update_api_domain_cache(list: Vec<String>) -> Result<(), String> {
-    if list.is_empty() {
-        return Ok(());
+// Called after a file lands in `read/` or `thumb/`.
+fn cache_index_record(path: &std::path::Path, chapter_id: Option<&str>) {
+    let Some((rel, area, aid, key)) = cache_index_location(path) else {
+        return;
+    };
+    let Ok(meta) = std::fs::metadata(path) else {
+        return;
+    };
+    let res = (|| -> Result<(), String> {
+        let _guard = CACHE_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tree = cache_index_tree()?;
+        let old: Option<CacheIndexEntry> = tree
+            .get(rel.as_bytes())
+            .map_err(|e| format!("read cache index failed: {e}"))?
+            .and_then(|v| serde_json::from_slice(&v).ok());
+        let now = now_millis();
+        let entry = CacheIndexEntry {
+            area: area.clone(),
+            aid: aid.clone(),
+            chapter_id: chapter_id
+                .map(|c| c.to_string())
+                .or_else(|| old.as_ref().and_then(|o| o.chapter_id.clone())),
+            key,
+            bytes: meta.len(),
+            created_at: old.as_ref().map(|o| o.created_at).unwrap_or(now),
+            accessed_at: now,
+        };
+        let val = serde_json::to_vec(&entry).map_err(|e| format!("encode cache index failed: {e}"))?;
+        tree.insert(rel.as_bytes(), val)
+            .map_err(|e| format!("write cache index failed: {e}"))?;
+        let (files, old_bytes) = match &old {
+            Some(o) => (0, o.bytes as i64),
+            None => (1, 0),
+        };
+        apply_cache_index_delta(&area, &aid, files, entry.bytes as i64 - old_bytes, now)
+    })();
+    if let Err(e) = res {
+        logl!("[tauri][cache] index record {:?} failed: {}", rel, e);
     }
-    save_api_domain_cache(&list)?;
-    set_api_bases(list);
-    Ok(())
 }
 
-fn api_base_candidates() -> Vec<(usize, String)> {
-    let bases = get_api_bases();
-    if bases.is_empty() {
-        return Vec::new();
-    }
-    let start = JM_API_BASE_INDEX.load(Ordering::Relaxed) % bases.len();
-    let mut out = Vec::with_capacity(bases.len());
-    for (offset, _base) in bases.iter().enumerate() {
-        let idx = (start + offset) % bases.len();
-        out.push((idx, bases[idx].clone()));
+// Called on cache hits; unknown files (cached before the index existed) get recorded.
+fn cache_index_touch(path: &std::path::Path) {
+    let Some((rel, area, aid, _)) = cache_index_location(path) else {
+        return;
+    };
+    let res = (|| -> Result<bool, String> {
+        let _guard = CACHE_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tree = cache_index_tree()?;
+        let Some(mut entry) = tree
+            .get(rel.as_bytes())
+            .map_err(|e| format!("read cache index failed: {e}"))?
+            .and_then(|v| serde_json::from_slice::<CacheIndexEntry>(&v).ok())
+        else {
+            return Ok(false);
+        };
+        let now = now_millis();
+        if now - entry.accessed_at < CACHE_TOUCH_INTERVAL_MS {
+            return Ok(true);
+        }
+        entry.accessed_at = now;
+        let val = serde_json::to_vec(&entry).map_err(|e| format!("encode cache index failed: {e}"))?;
+        tree.insert(rel.as_bytes(), val)
+            .map_err(|e| format!("write cache index failed: {e}"))?;
+        apply_cache_index_delta(&area, &aid, 0, 0, now)?;
+        Ok(true)
+    })();
+    match res {
+        Ok(true) => {}
+        Ok(false) => cache_index_record(path, None),
+        Err(e) => {
+            logl!("[tauri][cache] index touch {:?} failed: {}", rel, e);
+        }
     }
-    out
 }
 
-fn current_api_base() -> Option<String> {
-    let bases = get_api_bases();
-    if bases.is_empty() {
-        return None;
-    }
-    let idx = JM_API_BASE_INDEX.load(Ordering::Relaxed) % bases.len();
-    let base = bases.get(idx).cloned();
-    if let Some(b) = &base {
-        logl!("[tauri][api] current api base: {}", b);
+// Called after a cached file is deleted.
+fn cache_index_remove(path: &std::path::Path) {
+    let Some((rel, area, aid, _)) = cache_index_location(path) else {
+        return;
+    };
+    let res = (|| -> Result<(), String> {
+        let _guard = CACHE_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tree = cache_index_tree()?;
+        let Some(old) = tree
+            .remove(rel.as_bytes())
+            .map_err(|e| format!("remove cache index failed: {e}"))?
+            .and_then(|v| serde_json::from_slice::<CacheIndexEntry>(&v).ok())
+        else {
+            return Ok(());
+        };
+        apply_cache_index_delta(&area, &aid, -1, -(old.bytes as i64), 0)
+    })();
+    if let Err(e) = res {
+        logl!("[tauri][cache] index remove {:?} failed: {}", rel, e);
+    }
+}
+
+// Drops every indexed file of one comic dir (pages, variants and thumbnails).
+fn cache_index_remove_comic(aid: &str) -> Result<(), String> {
+    let _guard = CACHE_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let tree = cache_index_tree()?;
+    for area in ["read", "thumb"] {
+        let prefix = format!("{area}/{aid}/");
+        let mut files = 0i64;
+        let mut bytes = 0i64;
+        for res in tree.scan_prefix(prefix.as_bytes()) {
+            let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+            if let Ok(entry) = serde_json::from_slice::<CacheIndexEntry>(&v) {
+                files += 1;
+                bytes += entry.bytes as i64;
+            }
+            tree.remove(k).map_err(|e| format!("remove cache index failed: {e}"))?;
+        }
+        if files > 0 {
+            apply_cache_index_delta(area, aid, -files, -bytes, 0)?;
+        }
     }
-    base
+    Ok(())
 }
 
-fn should_retry_status(status: reqwest::StatusCode) -> bool {
-    let code = status.as_u16();
-    matches!(code, 403 | 500 | 502 | 503 | 504 | 520 | 524) || status.is_server_error()
+// Index entries of one comic as `(relative path, entry)`, pages first then thumbnails.
+fn cache_index_comic_entries(aid: &str) -> Result<Vec<(String, CacheIndexEntry)>, String> {
+    let tree = cache_index_tree()?;
+    let mut out = Vec::new();
+    for area in ["read", "thumb"] {
+        for res in tree.scan_prefix(format!("{area}/{aid}/").as_bytes()) {
+            let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+            if let Ok(entry) = serde_json::from_slice::<CacheIndexEntry>(&v) {
+                out.push((String::from_utf8_lossy(&k).into_owned(), entry));
+            }
+        }
+    }
+    Ok(out)
 }
 
-fn should_retry_error(err: &reqwest::Error) -> bool {
-    err.is_timeout() || err.is_connect()
+fn cache_index_ready() -> bool {
+    cache_summary_tree()
+        .ok()
+        .and_then(|t| t.get("index_version").ok().flatten())
+        .is_some_and(|v| v.as_ref() == CACHE_INDEX_VERSION)
 }
 
-fn should_retry_body(text: &str) -> bool {
-    let lower = text.to_ascii_lowercase();
-    lower.contains("404 not found")
-        || lower.contains("apache server")
-        || lower.contains("cloudflare")
-}
+// Full walk of `read/`, `thumb/` and `cover/`. Only needed once for caches that predate the
+// index, after external changes, or on explicit refresh; keeps known access times.
+fn rebuild_read_cache_index(base: &std::path::Path) -> Result<ReadCacheStats, String> {
+    let started = Instant::now();
+    let _guard = CACHE_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let index_tree = cache_index_tree()?;
+    let comics_tree = cache_comics_tree()?;
+    let summary_tree = cache_summary_tree()?;
 
-#[tauri::command]
-fn api_api_base_current() -> Result<String, String> {
-    current_api_base().ok_or_else(|| "api base unavailable".to_string())
-}
+    let mut previous: HashMap<String, CacheIndexEntry> = HashMap::new();
+    for res in index_tree.iter() {
+        let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(entry) = serde_json::from_slice::<CacheIndexEntry>(&v) {
+            previous.insert(String::from_utf8_lossy(&k).into_owned(), entry);
+        }
+    }
 
-#[tauri::command]
-fn api_api_base_list() -> Result<Vec<String>, String> {
-    Ok(get_api_bases())
-}
+    // Files cached before the index existed get their chapter from the page meta store.
+    let mut meta_chapters: HashMap<String, String> = HashMap::new();
+    if let Ok(tree) = read_page_meta_tree() {
+        for (_, v) in tree.iter().flatten() {
+            if let Ok(meta) = serde_json::from_slice::<PageMetaEntry>(&v) {
+                if let Some(file) = meta.cache_file {
+                    meta_chapters.insert(file, meta.chapter_id);
+                }
+            }
+        }
+    }
 
-#[tauri::command]
-async fn api_api_base_latency() -> Result<Vec<ApiBaseLatency>, String> {
-    let bases = get_api_bases();
-    let client = http_client()?;
-    let mut out = Vec::with_capacity(bases.len());
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
-    for base in bases {
-        let url = format!("{}/latest/?page=0", base.trim_end_matches('/'));
-        let started = Instant::now();
-        let resp = tokio::time::timeout(
-            Duration::from_secs(5),
-            client
-                .get(&url)
-                .header("tokenparam", tokenparam.clone())
-                .header("token", token.clone())
-                .header(
-                    "user-agent",
-                    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-                )
-                .header("accept-encoding", "identity")
-                .header("version", JM_APP_VERSION)
-                .send(),
-        )
-        .await;
-        let mut ok = false;
-        let mut status = None;
-        match resp {
-            Ok(Ok(r)) => {
-                status = Some(r.status().as_u16());
-                ok = r.status().is_success();
+    let now = now_millis();
+    let mut entries: Vec<(String, CacheIndexEntry)> = Vec::new();
+    let mut dirs = vec![base.join("cover")];
+    for area in ["read", "thumb"] {
+        if let Ok(aid_dirs) = std::fs::read_dir(base.join(area)) {
+            dirs.extend(aid_dirs.flatten().map(|d| d.path()).filter(|p| p.is_dir()));
+        }
+    }
+    for dir in dirs {
+        let Ok(files) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            let Ok(meta) = file.metadata() else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
             }
-            Ok(Err(_)) => {}
-            Err(_) => {}
+            let Some((rel, area, aid, key)) = cache_index_location(&path) else {
+                continue;
+            };
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(now);
+            let old = previous.get(&rel);
+            let entry = CacheIndexEntry {
+                area,
+                aid,
+                chapter_id: old
+                    .and_then(|o| o.chapter_id.clone())
+                    .or_else(|| meta_chapters.get(&rel).cloned()),
+                key,
+                bytes: meta.len(),
+                created_at: old.map(|o| o.created_at).unwrap_or(mtime),
+                accessed_at: old.map(|o| o.accessed_at).unwrap_or(mtime),
+            };
+            entries.push((rel, entry));
+        }
+    }
+
+    let mut per_comic: HashMap<String, ReadCacheComicStats> = HashMap::new();
+    let mut summary = ReadCacheStats::default();
+    for (_, entry) in &entries {
+        summary.total_files += 1;
+        summary.total_bytes += entry.bytes;
+        if entry.area == "cover" {
+            summary.cover_files += 1;
+            summary.cover_bytes += entry.bytes;
+            continue;
+        }
+        let comic = per_comic
+            .entry(entry.aid.clone())
+            .or_insert_with(|| ReadCacheComicStats {
+                aid: entry.aid.clone(),
+                updated_at: now,
+                ..Default::default()
+            });
+        if entry.area == "thumb" {
+            comic.thumb_files += 1;
+            comic.thumb_bytes += entry.bytes;
+            summary.thumb_files += 1;
+            summary.thumb_bytes += entry.bytes;
+        } else {
+            comic.files += 1;
+            comic.bytes += entry.bytes;
+        }
+        comic.accessed_at = comic.accessed_at.max(entry.accessed_at);
+    }
+    summary.total_comics = per_comic.values().filter(|c| c.files > 0).count() as u64;
+    summary.updated_at = now;
+    summary.elapsed_ms = started.elapsed().as_millis() as u64;
+
+    index_tree.clear().map_err(|e| format!("clear cache index failed: {e}"))?;
+    comics_tree.clear().map_err(|e| format!("clear cache comics failed: {e}"))?;
+    let mut batch = sled::Batch::default();
+    for (rel, entry) in &entries {
+        let val = serde_json::to_vec(entry).map_err(|e| format!("encode cache index failed: {e}"))?;
+        batch.insert(rel.as_bytes(), val);
+    }
+    index_tree
+        .apply_batch(batch)
+        .map_err(|e| format!("write cache index failed: {e}"))?;
+    for (aid, comic) in &per_comic {
+        let val = serde_json::to_vec(comic).map_err(|e| format!("encode comic stat failed: {e}"))?;
+        comics_tree
+            .insert(aid.as_bytes(), val)
+            .map_err(|e| format!("write comic stat failed: {e}"))?;
+    }
+    summary_tree
+        .insert(
+            "summary",
+            serde_json::to_vec(&summary).map_err(|e| format!("encode summary failed: {e}"))?,
+        )
+        .map_err(|e| format!("write summary failed: {e}"))?;
+    summary_tree
+        .insert("index_version", CACHE_INDEX_VERSION)
+        .map_err(|e| format!("write index version failed: {e}"))?;
+
+    let _ = index_tree.flush();
+    let _ = comics_tree.flush();
+    let _ = summary_tree.flush();
+    logl!(
+        "[tauri][cache] index rebuilt files={} bytes={} comics={} cost_ms={}",
+        summary.total_files,
+        summary.total_bytes,
+        summary.total_comics,
+        summary.elapsed_ms
+    );
+    Ok(summary)
+}
+
+fn ensure_read_cache_index(base: &std::path::Path) -> Result<(), String> {
+    if !cache_index_ready() {
+        rebuild_read_cache_index(base)?;
+    }
+    Ok(())
+}
+
+fn cache_policy_tree() -> Result<sled::Tree, String> {
+    cache_stats_db()?
+        .open_tree("cache_policy")
+        .map_err(|e| format!("open cache policy tree failed: {e}"))
+}
+
+fn load_cache_policies() -> Result<HashMap<String, CacheComicPolicy>, String> {
+    let mut out = HashMap::new();
+    for res in cache_policy_tree()?.iter() {
+        let (_, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(policy) = serde_json::from_slice::<CacheComicPolicy>(&v) {
+            out.insert(policy.aid.clone(), policy);
         }
-        out.push(ApiBaseLatency {
-            base,
-            ms: started.elapsed().as_millis() as i64,
-            ok,
-            status,
-        });
     }
     Ok(out)
 }
 
-fn parse_series_latest(series: &[serde_json::Value]) -> Option<(String, Option<String>)> {
-    let mut items: Vec<(usize, Option<f64>, String, Option<String>)> = Vec::new();
-    for (idx, item) in series.iter().enumerate() {
-        let id = item
-            .get("id")
-            .map(|v| v.to_string().trim_matches('"').to_string())
-            .unwrap_or_default();
-        if id.is_empty() {
+// Everything cached for one page: the original, its display variants and thumbnail.
+struct CachePageGroup {
+    aid: String,
+    files: Vec<String>,
+    bytes: u64,
+    accessed_at: i64,
+}
+
+fn load_cache_page_groups() -> Result<Vec<CachePageGroup>, String> {
+    let mut groups: HashMap<(String, String), CachePageGroup> = HashMap::new();
+    for res in cache_index_tree()?.iter() {
+        let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        let Ok(entry) = serde_json::from_slice::<CacheIndexEntry>(&v) else {
+            continue;
+        };
+        if entry.area == "cover" {
             continue;
         }
-        let sort_raw = item.get("sort");
-        let sort_str = sort_raw
-            .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .or_else(|| sort_raw.and_then(|v| v.as_i64().map(|n| n.to_string())))
-            .or_else(|| sort_raw.and_then(|v| v.as_f64().map(|n| n.to_string())));
-        let sort_num = sort_raw
-            .and_then(|v| v.as_f64())
-            .or_else(|| sort_raw.and_then(|v| v.as_i64().map(|n| n as f64)))
-            .or_else(|| sort_raw.and_then(|v| v.as_str().and_then(|s| s.parse::<f64>().ok())));
-        items.push((idx, sort_num, id, sort_str));
+        let group = groups
+            .entry((entry.aid.clone(), entry.key.clone()))
+            .or_insert_with(|| CachePageGroup {
+                aid: entry.aid.clone(),
+                files: Vec::new(),
+                bytes: 0,
+                accessed_at: 0,
+            });
+        group.files.push(String::from_utf8_lossy(&k).into_owned());
+        group.bytes += entry.bytes;
+        group.accessed_at = group.accessed_at.max(entry.accessed_at);
+    }
+    Ok(groups.into_values().collect())
+}
+
+// Page-level LRU: first trims comics over their own quota, then evicts the least
+// recently read pages of unpinned comics until the cache fits `max_bytes`. Comics with a
+// download job are never touched; cancelling the job releases them.
+fn evict_read_cache(
+    base: &std::path::Path,
+    options: &CacheEvictionOptions,
+    favorites: &std::collections::HashSet<String>,
+) -> Result<CacheEvictionReport, String> {
+    ensure_read_cache_index(base)?;
+    let policies = load_cache_policies()?;
+    let downloaded = downloaded_aids()?;
+    let mut groups = load_cache_page_groups()?;
+    groups.sort_by_key(|g| g.accessed_at);
+
+    let mut evict = vec![false; groups.len()];
+    let mut per_comic: HashMap<String, CacheEvictionComic> = HashMap::new();
+
+    let mut comic_bytes: HashMap<&str, u64> = HashMap::new();
+    for g in &groups {
+        *comic_bytes.entry(g.aid.as_str()).or_default() += g.bytes;
+    }
+    for (i, g) in groups.iter().enumerate() {
+        if downloaded.contains(&g.aid) {
+            continue;
+        }
+        let Some(quota) = policies.get(&g.aid).and_then(|p| p.quota_bytes) else {
+            continue;
+        };
+        let used = comic_bytes.entry(g.aid.as_str()).or_default();
+        if *used <= quota {
+            continue;
+        }
+        *used -= g.bytes;
+        evict[i] = true;
+        per_comic
+            .entry(g.aid.clone())
+            .or_insert_with(|| CacheEvictionComic {
+                aid: g.aid.clone(),
+                ..Default::default()
+            })
+            .quota_pages += 1;
     }
-    if items.len() <= 1 {
-        return None;
+
+    if let Some(max_bytes) = options.max_bytes {
+        let protect_favorites = options.protect_favorites.unwrap_or(true);
+        let mut total: u64 = comic_bytes.values().sum();
+        for (i, g) in groups.iter().enumerate() {
+            if total <= max_bytes {
+                break;
+            }
+            if evict[i]
+                || downloaded.contains(&g.aid)
+                || policies.get(&g.aid).is_some_and(|p| p.pinned)
+                || (protect_favorites && favorites.contains(&g.aid))
+            {
+                continue;
+            }
+            evict[i] = true;
+            total = total.saturating_sub(g.bytes);
+        }
     }
-    items.sort_by(|a, b| {
-        match (a.1, b.1) {
-            (Some(sa), Some(sb)) => sa
-                .partial_cmp(&sb)
-                .unwrap_or(std::cmp::Ordering::Equal),
-            (Some(_), None) => std::cmp::Ordering::Greater,
-            (None, Some(_)) => std::cmp::Ordering::Less,
-            (None, None) => a.0.cmp(&b.0),
+
+    let mut report = CacheEvictionReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    for (g, _) in groups.iter().zip(&evict).filter(|(_, e)| **e) {
+        let comic = per_comic
+            .entry(g.aid.clone())
+            .or_insert_with(|| CacheEvictionComic {
+                aid: g.aid.clone(),
+                ..Default::default()
+            });
+        comic.pages += 1;
+        comic.bytes += g.bytes;
+        report.evicted_pages += 1;
+        report.evicted_files += g.files.len() as u64;
+        report.evicted_bytes += g.bytes;
+        if options.dry_run {
+            continue;
         }
-    });
-    let last = items.pop()?;
-    Some((last.2, last.3))
+        for rel in &g.files {
+            let path = base.join(rel);
+            match std::fs::remove_file(&path) {
+                Ok(()) => cache_index_remove(&path),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => cache_index_remove(&path),
+                Err(e) => {
+                    logl!("[tauri][cache] evict {:?} failed: {}", path, e);
+                }
+            }
+        }
+    }
+
+    if !options.dry_run {
+        for aid in per_comic.keys() {
+            for area in ["read", "thumb"] {
+                // Only succeeds once the comic dir is empty.
+                let _ = std::fs::remove_dir(base.join(area).join(aid));
+            }
+        }
+        let _ = cache_stats_db()?.flush();
+        logl!(
+            "[tauri][cache] evicted pages={} files={} bytes={} comics={}",
+            report.evicted_pages,
+            report.evicted_files,
+            report.evicted_bytes,
+            per_comic.len()
+        );
+    }
+    report.comics = per_comic.into_values().collect();
+    report.comics.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+    report.stats = load_cache_summary(&cache_summary_tree()?);
+    Ok(report)
 }
 
-async fn scan_latest_chapters(app: tauri::AppHandle) -> Result<(), String> {
-    let store = app.state::<LocalFavoritesStore>();
-    let tree = store.tree()?.clone();
-    let latest_tree = read_latest_tree()?;
-    let seen_tree = read_latest_seen_tree()?;
+const DEFAULT_COVER_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
 
-    for item in tree.iter() {
-        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
-        let fav: LocalFavoriteItem =
-            bincode::deserialize(&val).map_err(|e| format!("decode favorite failed: {e}"))?;
-        let aid = fav.aid.clone();
+fn cover_cache_max_bytes() -> u64 {
+    config_state()
+        .lock()
+        .ok()
+        .and_then(|c| c.cover_cache_max_bytes)
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_COVER_CACHE_MAX_BYTES)
+}
 
-        if seen_tree
-            .get(aid.as_bytes())
-            .map_err(|e| format!("read latest seen failed: {e}"))?
-            .is_some()
-        {
-            continue;
+// Cover cache keys (md5 of the cover URL) of local favorites; these are never evicted.
+fn favorite_cover_keys<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+) -> std::collections::HashSet<String> {
+    let mut out = std::collections::HashSet::new();
+    let Some(store) = app.try_state::<LocalFavoritesStore>() else {
+        return out;
+    };
+    if let Ok(tree) = store.tree() {
+        for (_, v) in tree.iter().flatten() {
+            if let Ok(item) = bincode::deserialize::<LocalFavoriteItem>(&v) {
+                let url = item.cover_url.trim();
+                if !url.is_empty() {
+                    out.insert(md5_hex(url));
+                }
+            }
         }
+    }
+    out
+}
 
-        let album = match api_album(aid.clone(), HashMap::new()).await {
-            Ok(v) => v,
+// LRU over `cover/` until it fits `max_bytes`; returns `(files, bytes)` removed.
+fn evict_cover_cache(
+    base: &std::path::Path,
+    max_bytes: u64,
+    keep: &std::collections::HashSet<String>,
+) -> Result<(u64, u64), String> {
+    ensure_read_cache_index(base)?;
+    let mut covers = Vec::new();
+    for res in cache_index_tree()?.scan_prefix(b"cover/") {
+        let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(entry) = serde_json::from_slice::<CacheIndexEntry>(&v) {
+            covers.push((String::from_utf8_lossy(&k).into_owned(), entry));
+        }
+    }
+    let mut total: u64 = covers.iter().map(|(_, e)| e.bytes).sum();
+    if total <= max_bytes {
+        return Ok((0, 0));
+    }
+    covers.sort_by_key(|(_, e)| e.accessed_at);
+    let (mut files, mut bytes) = (0u64, 0u64);
+    for (rel, entry) in covers {
+        if total <= max_bytes {
+            break;
+        }
+        if keep.contains(&entry.key) {
+            continue;
+        }
+        let path = base.join(&rel);
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
             Err(e) => {
-                logl!("[tauri][latest] album fetch failed aid={} err={}", aid, e);
+                logl!("[tauri][cover] evict {:?} failed: {}", path, e);
                 continue;
             }
-        };
-        let series = album
-            .get("series")
-            .and_then(|v| v.as_array())
-            .cloned()
-            .unwrap_or_default();
+        }
+        cache_index_remove(&path);
+        total = total.saturating_sub(entry.bytes);
+        files += 1;
+        bytes += entry.bytes;
+    }
+    if files > 0 {
+        logl!("[tauri][cover] evicted files={} bytes={}", files, bytes);
+    }
+    Ok((files, bytes))
+}
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
+const DEFAULT_MIN_FREE_BYTES: u64 = 512 * 1024 * 1024;
+// Headroom required by essential writes on top of their own size.
+const ESSENTIAL_WRITE_MARGIN: u64 = 4 * 1024 * 1024;
+// Prefix of the error returned when a cache write is refused; the UI matches on it.
+const DISK_SPACE_LOW_ERROR: &str = "disk space low";
+const CACHE_MOVING_ERROR: &str = "cache move in progress";
 
-        if let Some((latest_id, latest_sort)) = parse_series_latest(&series) {
-            let latest_entry = LatestChapterEntry {
-                aid: aid.clone(),
-                latest_chapter_id: latest_id,
-                latest_chapter_sort: latest_sort,
-                updated_at: now,
-            };
-            latest_tree
-                .insert(
-                    aid.as_bytes(),
-                    serde_json::to_vec(&latest_entry)
-                        .map_err(|e| format!("encode latest failed: {e}"))?,
-                )
-                .map_err(|e| format!("write latest failed: {e}"))?;
-            let _ = latest_tree.flush();
-        } else {
-            let _ = latest_tree.remove(aid.as_bytes());
-            let _ = latest_tree.flush();
-        }
+// The cache dir is frozen while `api_cache_dir_move` copies it, so the copy needs no
+// catch-up pass and nothing is lost when the old dir is removed.
+fn ensure_cache_not_moving() -> Result<(), String> {
+    if CACHE_MOVE_RUNNING.load(Ordering::Acquire) {
+        return Err(CACHE_MOVING_ERROR.to_string());
+    }
+    Ok(())
+}
 
-        let seen_entry = LatestScanEntry {
-            aid: aid.clone(),
-            scanned_at: now,
+fn min_free_bytes() -> u64 {
+    config_state()
+        .lock()
+        .ok()
+        .and_then(|c| c.min_free_bytes)
+        .unwrap_or(DEFAULT_MIN_FREE_BYTES)
+}
+
+fn dir_size_bytes(path: &std::path::Path) -> u64 {
+    count_dir_files(path).1
+}
+
+// Frees at least `deficit` bytes: least recently read pages of unpinned, non-favorite
+// comics first, then covers outside the favorites.
+fn emergency_evict(base: &std::path::Path, deficit: u64) {
+    if CACHE_MOVE_RUNNING.load(Ordering::Acquire)
+        || EMERGENCY_EVICTION_RUNNING.swap(true, Ordering::AcqRel)
+    {
+        return;
+    }
+    let (favorites, favorite_covers) = match APP_HANDLE.get() {
+        Some(app) => (local_favorite_aids(app), favorite_cover_keys(app)),
+        None => Default::default(),
+    };
+    let res = (|| -> Result<u64, String> {
+        ensure_read_cache_index(base)?;
+        let summary = load_cache_summary(&cache_summary_tree()?);
+        let page_bytes = summary.total_bytes.saturating_sub(summary.cover_bytes);
+        let options = CacheEvictionOptions {
+            max_bytes: Some(page_bytes.saturating_sub(deficit)),
+            ..Default::default()
         };
-        seen_tree
-            .insert(
-                aid.as_bytes(),
-                serde_json::to_vec(&seen_entry).map_err(|e| format!("encode seen failed: {e}"))?,
-            )
-            .map_err(|e| format!("write seen failed: {e}"))?;
-        let _ = seen_tree.flush();
+        let mut freed = evict_read_cache(base, &options, &favorites)?.evicted_bytes;
+        if freed < deficit {
+            let cover_target = summary.cover_bytes.saturating_sub(deficit - freed);
+            freed += evict_cover_cache(base, cover_target, &favorite_covers)?.1;
+        }
+        Ok(freed)
+    })();
+    match res {
+        Ok(freed) => {
+            logl!("[tauri][disk] emergency eviction deficit={} freed={}", deficit, freed);
+        }
+        Err(e) => {
+            logl!("[tauri][disk] emergency eviction failed: {e}");
+        }
+    }
+    EMERGENCY_EVICTION_RUNNING.store(false, Ordering::Release);
+}
+
+// Guard for non-essential cache writes of `need` bytes: below the free-space
+// threshold it evicts first and refuses the write if that wasn't enough.
+fn ensure_cache_write_space(base: &std::path::Path, need: u64) -> Result<(), String> {
+    ensure_cache_not_moving()?;
+    let Ok(free) = fs2::available_space(base) else {
+        return Ok(());
+    };
+    let min = min_free_bytes();
+    let wanted = min.saturating_add(need);
+    if free >= wanted {
+        return Ok(());
+    }
+    emergency_evict(base, wanted - free);
+    let free = fs2::available_space(base).unwrap_or(free);
+    if free >= wanted {
+        return Ok(());
+    }
+    Err(format!("{DISK_SPACE_LOW_ERROR}: {free} bytes free, keeping {min}"))
+}
+
+// For async callers: the guard may scan the index and delete files, so it runs on the
+// blocking pool.
+async fn ensure_cache_write_space_async(base: &std::path::Path, need: u64) -> Result<(), String> {
+    let base = base.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || ensure_cache_write_space(&base, need))
+        .await
+        .map_err(|e| format!("disk space check failed: {e}"))?
+}
+
+// Guard for user-initiated writes: they only need to fit.
+fn ensure_write_space(dir: &std::path::Path, need: u64) -> Result<(), String> {
+    let Ok(free) = fs2::available_space(dir) else {
+        return Ok(());
+    };
+    if free < need.saturating_add(ESSENTIAL_WRITE_MARGIN) {
+        return Err(format!("{DISK_SPACE_LOW_ERROR}: {free} bytes free, need {need}"));
+    }
+    Ok(())
+}
+
+fn is_disk_space_low(err: &str) -> bool {
+    err.starts_with(DISK_SPACE_LOW_ERROR)
+}
+
+fn disk_guard_tick(base: &std::path::Path) {
+    let Ok(free) = fs2::available_space(base) else {
+        return;
+    };
+    let min = min_free_bytes();
+    if free < min {
+        logl!("[tauri][disk] free space low free={} min={}", free, min);
+        emergency_evict(base, min - free);
+    }
+}
+
+fn local_favorite_aids<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> std::collections::HashSet<String> {
+    let mut out = std::collections::HashSet::new();
+    let Some(store) = app.try_state::<LocalFavoritesStore>() else {
+        return out;
+    };
+    if let Ok(tree) = store.tree() {
+        for (k, _) in tree.iter().flatten() {
+            out.insert(sanitize_path_component(&String::from_utf8_lossy(&k)));
+        }
+    }
+    out
+}
+
+fn normalize_verify_url(input: &str, base_override: Option<String>) -> String {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return String::new();
+    }
+    let base = web_base_from_opt(base_override);
+    let base_host = base
+        .split("://")
+        .nth(1)
+        .and_then(|s| s.split('/').next())
+        .unwrap_or("");
+    if base_host.is_empty() {
+        return trimmed.to_string();
+    }
+
+    if let Some(host) = trimmed
+        .split("://")
+        .nth(1)
+        .and_then(|s| s.split('/').next())
+    {
+        if !host.is_empty() && host != base_host {
+            return trimmed.replace(host, base_host);
+        }
+    }
+    trimmed.to_string()
+}
+
+fn log_line(file: &str, line: u32, msg: std::fmt::Arguments<'_>) {
+    // dev: print to console
+    if cfg!(debug_assertions) || std::env::var("JM_LOG_STDERR").ok().as_deref() == Some("1") {
+        eprintln!("{file}:{line} {msg}");
+    }
+
+    // release: always write to file (best-effort)
+    if cfg!(debug_assertions) {
+        return;
+    }
+
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    if let Some(m) = get_log_writer() {
+        if let Ok(mut w) = m.lock() {
+            let _ = writeln!(w, "{now_ms} {file}:{line} {msg}");
+            // Reduce overhead: flush every 50 lines.
+            let n = LOG_LINE_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+            if n % 50 == 0 {
+                let _ = w.flush();
+            }
+        }
+    }
+}
+
+#[tauri::command]
+async fn api_config_get() -> Result<AppConfig, String> {
+    Ok(config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?
+        .clone())
+}
+
+#[tauri::command]
+async fn api_config_set_socks_proxy(proxy: Option<String>) -> Result<(), String> {
+    let proxy = proxy
+        .and_then(|s| {
+            let t = s.trim().to_string();
+            if t.is_empty() { None } else { Some(t) }
+        });
+
+    if let Some(p) = &proxy {
+        reqwest::Proxy::all(p).map_err(|e| format!("invalid proxy url: {e}"))?;
+    }
+
+    let mut cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    cfg.socks_proxy = proxy;
+    save_config_to_disk(&cfg)?;
+    Ok(())
+}
+
+// `None` restores the default budget; 0 turns the memory cache off.
+#[tauri::command]
+async fn api_config_set_memory_cache_max_bytes(max_bytes: Option<u64>) -> Result<(), String> {
+    {
+        let mut cfg = config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?;
+        cfg.memory_cache_max_bytes = max_bytes;
+        save_config_to_disk(&cfg)?;
+    }
+    let mut cache = page_memory_cache()
+        .lock()
+        .map_err(|_| "memory cache lock poisoned".to_string())?;
+    cache.budget_bytes = max_bytes.unwrap_or(DEFAULT_MEMORY_CACHE_MAX_BYTES);
+    let budget = cache.budget_bytes;
+    cache.shrink_to(budget);
+    Ok(())
+}
+
+// `None` restores the default threshold; 0 disables the guard.
+#[tauri::command]
+async fn api_config_set_min_free_bytes(min_free_bytes: Option<u64>) -> Result<(), String> {
+    let mut cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    cfg.min_free_bytes = min_free_bytes;
+    save_config_to_disk(&cfg)?;
+    Ok(())
+}
+
+// Setting a dir mirrors everything downloaded so far in the background.
+#[tauri::command]
+async fn api_config_set_library_dir(app: tauri::AppHandle, dir: Option<String>) -> Result<(), String> {
+    let dir = dir.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    if let Some(d) = &dir {
+        std::fs::create_dir_all(d).map_err(|e| format!("mkdir failed: {e}"))?;
+    }
+    {
+        let mut cfg = config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?;
+        cfg.library_dir = dir;
+        save_config_to_disk(&cfg)?;
+    }
+    spawn_library_sync(&app, Vec::new());
+    Ok(())
+}
+
+// `None` or 0 restores the default budget.
+#[tauri::command]
+async fn api_config_set_cover_cache_max_bytes(max_bytes: Option<u64>) -> Result<(), String> {
+    let mut cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    cfg.cover_cache_max_bytes = max_bytes.filter(|n| *n > 0);
+    save_config_to_disk(&cfg)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn app_update_check(app: tauri::AppHandle) -> Result<UpdateCheckInfo, String> {
+    let current_version = app.package_info().version.to_string();
+    let current_tag = extract_build_tag(&current_version);
+    let is_release = current_tag.as_deref().map(|t| t.starts_with("jm-")).unwrap_or(false);
+    let client = http_client()?;
+    let resp = client
+        .get("https://api.github.com/repos/alexsunxl/jm-tauri/releases/latest")
+        .header(reqwest::header::USER_AGENT, "jm-tauri")
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("fetch release failed: {e}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("release http status {}", status.as_u16()));
+    }
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("parse release json failed: {e}"))?;
+    let latest_tag = json
+        .get("tag_name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let release_url = json
+        .get("html_url")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let notes = json
+        .get("body")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let assets = json
+        .get("assets")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|item| {
+                    let name = item.get("name")?.as_str()?.to_string();
+                    let url = item.get("browser_download_url")?.as_str()?.to_string();
+                    let size = item.get("size")?.as_u64().unwrap_or(0);
+                    Some(UpdateAssetInfo { name, url, size })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let asset = pick_update_asset(&assets, std::env::consts::OS);
+
+    let mut has_update = false;
+    let mut compare_mode = None;
+    if let Some(latest) = latest_tag.as_deref() {
+        if is_release {
+            has_update = current_tag.as_deref().map(|t| t != latest).unwrap_or(true);
+            compare_mode = Some("tag".to_string());
+        } else if let (Some(cur), Some(lat)) =
+            (parse_base_version(&current_version), parse_version_from_tag(latest))
+        {
+            has_update = lat > cur;
+            compare_mode = Some("version".to_string());
+        } else {
+            has_update = true;
+            compare_mode = Some("unknown".to_string());
+        }
+    }
+
+    Ok(UpdateCheckInfo {
+        current_version,
+        current_tag,
+        latest_tag,
+        release_url,
+        notes,
+        has_update,
+        asset,
+        is_dev: !is_release,
+        compare_mode,
+    })
+}
+
+#[tauri::command]
+async fn app_update_download(
+    app: tauri::AppHandle,
+    url: String,
+    name: Option<String>,
+) -> Result<UpdateDownloadInfo, String> {
+    let trimmed = url.trim();
+    if trimmed.is_empty() {
+        return Err("download url is empty".to_string());
+    }
+    if !trimmed.starts_with("https://github.com/alexsunxl/jm-tauri/releases/download/") {
+        return Err("unsupported download url".to_string());
+    }
+
+    let raw_name = name
+        .and_then(|n| {
+            let t = n.trim().to_string();
+            if t.is_empty() { None } else { Some(t) }
+        })
+        .or_else(|| trimmed.rsplit('/').next().map(|s| s.to_string()))
+        .unwrap_or_else(|| "update.bin".to_string());
+    let safe_name = sanitize_path_component(&raw_name);
+
+    let base_dir = app
+        .path()
+        .download_dir()
+        .or_else(|_| app.path().app_data_dir())
+        .map_err(|e| format!("resolve download dir failed: {e}"))?;
+    let target_dir = base_dir.join("jm-updates");
+    std::fs::create_dir_all(&target_dir).map_err(|e| format!("mkdir failed: {e}"))?;
+    let dest = target_dir.join(&safe_name);
+
+    let client = http_client()?;
+    let mut resp = client
+        .get(trimmed)
+        .header(reqwest::header::USER_AGENT, "jm-tauri")
+        .send()
+        .await
+        .map_err(|e| format!("download failed: {e}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("download http status {}", status.as_u16()));
+    }
+    ensure_write_space(&target_dir, resp.content_length().unwrap_or(0))?;
+
+    let mut file = std::fs::File::create(&dest).map_err(|e| format!("create file failed: {e}"))?;
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("read update chunk failed: {e}"))?
+    {
+        file.write_all(&chunk).map_err(|e| format!("write update failed: {e}"))?;
+    }
+
+    Ok(UpdateDownloadInfo {
+        path: dest.to_string_lossy().to_string(),
+        name: safe_name,
+    })
+}
+
+#[tauri::command]
+async fn api_read_progress_upsert(entry: ReadProgressEntry) -> Result<(), String> {
+    let tree = read_progress_tree()?;
+    let key = entry.aid.as_bytes();
+    let val =
+        serde_json::to_vec(&entry).map_err(|e| format!("encode read progress failed: {e}"))?;
+    tree.insert(key, val)
+        .map_err(|e| format!("write read progress failed: {e}"))?;
+    let _ = tree.flush();
+    Ok(())
+}
+
+#[tauri::command]
+async fn api_read_progress_clear(aid: String) -> Result<(), String> {
+    let tree = read_progress_tree()?;
+    tree.remove(aid.as_bytes())
+        .map_err(|e| format!("delete read progress failed: {e}"))?;
+    let _ = tree.flush();
+    Ok(())
+}
+
+#[tauri::command]
+async fn api_read_progress_export(path: Option<String>) -> Result<ExportResult, String> {
+    let data_dir = resolve_data_dir()?;
+    let export_path = if let Some(p) = path {
+        let trimmed = p.trim();
+        if trimmed.is_empty() {
+            data_dir.join(format!(
+                "read-progress-export-{}.zip",
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0)
+            ))
+        } else {
+            std::path::PathBuf::from(trimmed)
+        }
+    } else {
+        data_dir.join(format!(
+            "read-progress-export-{}.zip",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        ))
+    };
+    if let Some(parent) = export_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+        ensure_write_space(parent, dir_size_bytes(&data_dir.join("read-progress.sled")))?;
+    }
+    export_read_progress_zip(&export_path)?;
+    Ok(ExportResult {
+        path: export_path.to_string_lossy().to_string(),
+    })
+}
+
+#[tauri::command]
+async fn api_read_progress_import(path: String) -> Result<(), String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("导入路径不能为空".to_string());
+    }
+    let p = std::path::PathBuf::from(trimmed);
+    import_read_progress_zip(&p)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn api_follow_state_list() -> Result<Vec<FollowStateEntry>, String> {
+    let tree = read_follow_tree()?;
+    let mut out = Vec::new();
+    for item in tree.iter() {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(entry) = serde_json::from_slice::<FollowStateEntry>(&val) {
+            out.push(entry);
+        }
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+async fn api_proxy_check(proxy: Option<String>) -> Result<String, String> {
+    let proxy = proxy
+        .and_then(|s| {
+            let t = s.trim().to_string();
+            if t.is_empty() { None } else { Some(t) }
+        })
+        .or_else(current_socks_proxy);
+
+    let Some(proxy) = proxy else {
+        return Err("未配置SOCKS代理".to_string());
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(8))
+        .proxy(reqwest::Proxy::all(&proxy).map_err(|e| format!("invalid proxy url: {e}"))?)
+        .build()
+        .map_err(|e| format!("create http client failed: {e}"))?;
+
+    let resp = client
+        .get(JM_API_BASE_DEFAULT)
+        .send()
+        .await
+        .map_err(|e| format!("proxy request failed: {e}"))?;
+
+    let status = resp.status();
+    if status.is_success() || status.is_redirection() {
+        Ok(format!("代理连接成功（HTTP {}）", status.as_u16()))
+    } else {
+        Err(format!("代理连接失败（HTTP {}）", status.as_u16()))
+    }
+}
+
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+fn md5_hex(input: &str) -> String {
+    format!("{:x}", md5::compute(input.as_bytes()))
+}
+
+fn de_i64<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v = serde_json::Value::deserialize(deserializer)?;
+    match v {
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| serde::de::Error::custom("number out of range for i64")),
+        serde_json::Value::String(s) => s
+            .parse::<i64>()
+            .map_err(|_| serde::de::Error::custom("invalid i64 string")),
+        serde_json::Value::Null => Err(serde::de::Error::custom("null where i64 expected")),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid type for i64: {other}"
+        ))),
+    }
+}
+
+fn de_i64_default<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v = serde_json::Value::deserialize(deserializer)?;
+    match v {
+        serde_json::Value::Null => Ok(0),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| serde::de::Error::custom("number out of range for i64")),
+        serde_json::Value::String(s) => s
+            .parse::<i64>()
+            .map_err(|_| serde::de::Error::custom("invalid i64 string")),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid type for i64: {other}"
+        ))),
+    }
+}
+
+fn unpad_like_python(mut data: Vec<u8>) -> Vec<u8> {
+    if data.is_empty() {
+        return data;
+    }
+    let pad = *data.last().unwrap() as usize;
+    let new_len = data.len().saturating_sub(pad);
+    data.truncate(new_len);
+    data
+}
+
+fn preview_bytes_hex(bytes: &[u8], limit: usize) -> String {
+    let mut out = String::new();
+    let n = bytes.len().min(limit);
+    for (i, b) in bytes[..n].iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        out.push_str(&format!("{b:02x}"));
+    }
+    out
+}
+
+fn looks_like_json_utf8(s: &str) -> bool {
+    let trimmed = s.trim_start_matches(['\u{0000}', ' ', '\n', '\r', '\t']);
+    trimmed.starts_with('{') || trimmed.starts_with('[')
+}
+
+fn decode_resp_data(data_b64: &str, ts: i64) -> Result<String, String> {
+    let debug = std::env::var("JM_DEBUG_LOGIN").ok().as_deref() == Some("1");
+
+    let secret =
+        std::env::var("JM_APP_DATA_SECRET").unwrap_or_else(|_| JM_APP_DATA_SECRET_DEFAULT.into());
+
+    let key = md5_hex(&format!("{ts}{secret}")).into_bytes(); // jmcomic: md5hex(...).encode('utf-8')
+
+    let mut ciphertext = base64::engine::general_purpose::STANDARD
+        .decode(data_b64)
+        .map_err(|e| format!("base64 decode failed: {e}"))?;
+
+    type Aes256EcbDec = ecb::Decryptor<Aes256>;
+    let cipher =
+        Aes256EcbDec::new_from_slice(&key).map_err(|e| format!("cipher init failed: {e}"))?;
+
+    let decrypted_padded = cipher
+        .decrypt_padded_mut::<NoPadding>(&mut ciphertext)
+        .map_err(|e| format!("decrypt failed: {e}"))?
+        .to_vec();
+
+    let decrypted = unpad_like_python(decrypted_padded);
+    if debug {
+        logl!(
+            "[tauri][login][decrypt] secret={:?} key_len={} decrypted_len={} hex_preview={}",
+            secret,
+            key.len(),
+            decrypted.len(),
+            preview_bytes_hex(&decrypted, 32)
+        );
+    }
+
+    let s = String::from_utf8(decrypted).map_err(|e| format!("utf8 decode failed: {e}"))?;
+    if !looks_like_json_utf8(&s) {
+        return Err("decoded text is not json-like".into());
+    }
+    Ok(s)
+}
+
+fn cookie_header(cookies: &HashMap<String, String>) -> String {
+    cookies
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn normalize_api_base(input: &str) -> Option<String> {
+    let raw = input.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    let with_scheme = if raw.starts_with("http://") || raw.starts_with("https://") {
+        raw.to_string()
+    } else {
+        format!("https://{raw}")
+    };
+    Some(with_scheme.trim_end_matches('/').to_string())
+}
+
+fn normalize_api_base_root(input: &str) -> Option<String> {
+    let normalized = normalize_api_base(input)?;
+    if let Some(pos) = normalized.find("://") {
+        let rest = &normalized[(pos + 3)..];
+        if let Some(slash) = rest.find('/') {
+            let prefix = &normalized[..pos];
+            let host = &rest[..slash];
+            return Some(format!("{prefix}://{host}"));
+        }
+    }
+    Some(normalized)
+}
+
+fn parse_api_domain_list(text: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in text.lines() {
+        for token in line.split(|c: char| c == ',' || c.is_whitespace()) {
+            if let Some(v) = normalize_api_base_root(token) {
+                if !out.contains(&v) {
+                    out.push(v);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn parse_api_domain_payload(payload: &str) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str(payload) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+    let Some(server) = value.get("Server") else {
+        return Vec::new();
+    };
+    if let Some(list) = server.as_array() {
+        let mut out = Vec::new();
+        for item in list {
+            if let Some(s) = item.as_str() {
+                if let Some(v) = normalize_api_base_root(s) {
+                    if !out.contains(&v) {
+                        out.push(v);
+                    }
+                }
+            }
+        }
+        return out;
+    }
+    if let Some(s) = server.as_str() {
+        return parse_api_domain_list(s);
+    }
+    Vec::new()
+}
+
+fn strip_leading_non_ascii(text: &str) -> &str {
+    for (pos, ch) in text.char_indices() {
+        if ch.is_ascii() {
+            return &text[pos..];
+        }
+    }
+    text
+}
+
+fn decode_domain_server_payload(data_b64: &str) -> Result<String, String> {
+    let key = md5_hex(JM_API_DOMAIN_SERVER_SECRET).into_bytes();
+    let mut trimmed = data_b64.trim().to_string();
+    let pad = trimmed.len() % 4;
+    if pad != 0 {
+        trimmed.push_str(&"=".repeat(4 - pad));
+    }
+    let mut ciphertext = base64::engine::general_purpose::STANDARD
+        .decode(trimmed.as_bytes())
+        .or_else(|_| base64::engine::general_purpose::URL_SAFE.decode(trimmed.as_bytes()))
+        .map_err(|e| format!("base64 decode failed: {e}"))?;
+
+    type Aes256EcbDec = ecb::Decryptor<Aes256>;
+    let cipher =
+        Aes256EcbDec::new_from_slice(&key).map_err(|e| format!("cipher init failed: {e}"))?;
+
+    let decrypted_padded = cipher
+        .decrypt_padded_mut::<NoPadding>(&mut ciphertext)
+        .map_err(|e| format!("decrypt failed: {e}"))?
+        .to_vec();
+
+    let decrypted = unpad_like_python(decrypted_padded);
+    String::from_utf8(decrypted).map_err(|e| format!("utf8 decode failed: {e}"))
+}
+
+fn load_api_base_list() -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let add = |out: &mut Vec<String>, s: &str| {
+        if let Some(v) = normalize_api_base_root(s) {
+            if !out.contains(&v) {
+                out.push(v);
+            }
+        }
+    };
+
+    for item in load_api_domain_cache() {
+        add(&mut out, &item);
+    }
+
+    if let Ok(cfg) = config_state().lock() {
+        for item in &cfg.api_base_list {
+            add(&mut out, item);
+        }
+    }
+
+    if let Ok(list) = std::env::var("JM_API_BASE_LIST") {
+        for item in list.split(|c: char| c == ',' || c.is_whitespace()) {
+            add(&mut out, item);
+        }
+    }
+
+    if out.is_empty() {
+        if let Ok(single) = std::env::var("JM_API_BASE") {
+            add(&mut out, &single);
+        }
+    }
+
+    if out.is_empty() {
+        for item in JM_API_BASE_LIST_DEFAULT {
+            add(&mut out, item);
+        }
+    }
+
+    if out.is_empty() {
+        add(&mut out, JM_API_BASE_DEFAULT);
+    }
+
+    out
+}
+
+fn api_bases_state() -> &'static Mutex<Vec<String>> {
+    JM_API_BASES.get_or_init(|| Mutex::new(load_api_base_list()))
+}
+
+fn get_api_bases() -> Vec<String> {
+    api_bases_state()
+        .lock()
+        .map(|v| v.clone())
+        .unwrap_or_default()
+}
+
+fn set_api_bases(list: Vec<String>) {
+    if list.is_empty() {
+        return;
+    }
+    if let Ok(mut bases) = api_bases_state().lock() {
+        *bases = list;
+        JM_API_BASE_INDEX.store(0, Ordering::Relaxed);
+    }
+}
+
+#[allow(dead_code)]
+fn update_api_base_list(list: Vec<String>) -> Result<(), String> {
+    if list.is_empty() {
+        return Ok(());
+    }
+    {
+        let mut cfg = config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?;
+        cfg.api_base_list = list.clone();
+        save_config_to_disk(&cfg)?;
+    }
+    set_api_bases(list);
+    Ok(())
+}
+
+fn update_api_domain_cache(list: Vec<String>) -> Result<(), String> {
+    if list.is_empty() {
+        return Ok(());
+    }
+    save_api_domain_cache(&list)?;
+    set_api_bases(list);
+    Ok(())
+}
+
+fn api_base_candidates() -> Vec<(usize, String)> {
+    let bases = get_api_bases();
+    if bases.is_empty() {
+        return Vec::new();
+    }
+    let start = JM_API_BASE_INDEX.load(Ordering::Relaxed) % bases.len();
+    let mut out = Vec::with_capacity(bases.len());
+    for (offset, _base) in bases.iter().enumerate() {
+        let idx = (start + offset) % bases.len();
+        out.push((idx, bases[idx].clone()));
+    }
+    out
+}
+
+fn current_api_base() -> Option<String> {
+    let bases = get_api_bases();
+    if bases.is_empty() {
+        return None;
+    }
+    let idx = JM_API_BASE_INDEX.load(Ordering::Relaxed) % bases.len();
+    let base = bases.get(idx).cloned();
+    if let Some(b) = &base {
+        logl!("[tauri][api] current api base: {}", b);
+    }
+    base
+}
+
+fn should_retry_status(status: reqwest::StatusCode) -> bool {
+    let code = status.as_u16();
+    matches!(code, 403 | 500 | 502 | 503 | 504 | 520 | 524) || status.is_server_error()
+}
+
+fn should_retry_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+fn should_retry_body(text: &str) -> bool {
+    let lower = text.to_ascii_lowercase();
+    lower.contains("404 not found")
+        || lower.contains("apache server")
+        || lower.contains("cloudflare")
+}
+
+#[tauri::command]
+fn api_api_base_current() -> Result<String, String> {
+    current_api_base().ok_or_else(|| "api base unavailable".to_string())
+}
+
+#[tauri::command]
+fn api_api_base_list() -> Result<Vec<String>, String> {
+    Ok(get_api_bases())
+}
+
+#[tauri::command]
+async fn api_api_base_latency() -> Result<Vec<ApiBaseLatency>, String> {
+    let bases = get_api_bases();
+    let client = http_client()?;
+    let mut out = Vec::with_capacity(bases.len());
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+    for base in bases {
+        let url = format!("{}/latest/?page=0", base.trim_end_matches('/'));
+        let started = Instant::now();
+        let resp = tokio::time::timeout(
+            Duration::from_secs(5),
+            client
+                .get(&url)
+                .header("tokenparam", tokenparam.clone())
+                .header("token", token.clone())
+                .header(
+                    "user-agent",
+                    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+                )
+                .header("accept-encoding", "identity")
+                .header("version", JM_APP_VERSION)
+                .send(),
+        )
+        .await;
+        let mut ok = false;
+        let mut status = None;
+        match resp {
+            Ok(Ok(r)) => {
+                status = Some(r.status().as_u16());
+                ok = r.status().is_success();
+            }
+            Ok(Err(_)) => {}
+            Err(_) => {}
+        }
+        out.push(ApiBaseLatency {
+            base,
+            ms: started.elapsed().as_millis() as i64,
+            ok,
+            status,
+        });
+    }
+    Ok(out)
+}
+
+fn parse_series_latest(series: &[serde_json::Value]) -> Option<(String, Option<String>)> {
+    let mut items: Vec<(usize, Option<f64>, String, Option<String>)> = Vec::new();
+    for (idx, item) in series.iter().enumerate() {
+        let id = item
+            .get("id")
+            .map(|v| v.to_string().trim_matches('"').to_string())
+            .unwrap_or_default();
+        if id.is_empty() {
+            continue;
+        }
+        let sort_raw = item.get("sort");
+        let sort_str = sort_raw
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .or_else(|| sort_raw.and_then(|v| v.as_i64().map(|n| n.to_string())))
+            .or_else(|| sort_raw.and_then(|v| v.as_f64().map(|n| n.to_string())));
+        let sort_num = sort_raw
+            .and_then(|v| v.as_f64())
+            .or_else(|| sort_raw.and_then(|v| v.as_i64().map(|n| n as f64)))
+            .or_else(|| sort_raw.and_then(|v| v.as_str().and_then(|s| s.parse::<f64>().ok())));
+        items.push((idx, sort_num, id, sort_str));
+    }
+    if items.len() <= 1 {
+        return None;
+    }
+    items.sort_by(|a, b| {
+        match (a.1, b.1) {
+            (Some(sa), Some(sb)) => sa
+                .partial_cmp(&sb)
+                .unwrap_or(std::cmp::Ordering::Equal),
+            (Some(_), None) => std::cmp::Ordering::Greater,
+            (None, Some(_)) => std::cmp::Ordering::Less,
+            (None, None) => a.0.cmp(&b.0),
+        }
+    });
+    let last = items.pop()?;
+    Some((last.2, last.3))
+}
+
+async fn scan_latest_chapters(app: tauri::AppHandle) -> Result<(), String> {
+    let store = app.state::<LocalFavoritesStore>();
+    let tree = store.tree()?.clone();
+    let latest_tree = read_latest_tree()?;
+    let seen_tree = read_latest_seen_tree()?;
+
+    for item in tree.iter() {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        let fav: LocalFavoriteItem =
+            bincode::deserialize(&val).map_err(|e| format!("decode favorite failed: {e}"))?;
+        let aid = fav.aid.clone();
+        // Imported comics have no remote album to check.
+        if aid.starts_with(LOCAL_COMIC_PREFIX) {
+            continue;
+        }
+
+        let seen = seen_tree
+            .get(aid.as_bytes())
+            .map_err(|e| format!("read latest seen failed: {e}"))?
+            .and_then(|bytes| serde_json::from_slice::<LatestScanEntry>(&bytes).ok());
+        let pending = load_auto_download_pending(&aid)?;
+        if let Some(seen) = seen {
+            let age = unix_secs(std::time::SystemTime::now()) as i64 - seen.scanned_at;
+            let interval = if pending.is_some() {
+                AUTO_DOWNLOAD_RETRY_SECS
+            } else {
+                AUTO_DOWNLOAD_RESCAN_SECS
+            };
+            if age < interval || !auto_download_enabled(&aid)? {
+                continue;
+            }
+        }
+
+        let album = match api_album(aid.clone(), HashMap::new()).await {
+            Ok(v) => v,
+            Err(e) => {
+                logl!("[tauri][latest] album fetch failed aid={} err={}", aid, e);
+                continue;
+            }
+        };
+        let series = album
+            .get("series")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let latest = parse_series_latest(&series);
+        let previous = latest_tree
+            .get(aid.as_bytes())
+            .map_err(|e| format!("read latest failed: {e}"))?
+            .and_then(|bytes| serde_json::from_slice::<LatestChapterEntry>(&bytes).ok());
+        // A deferred batch is remembered in `auto_download_pending` and retried from where
+        // it started; the latest entry always moves on so update notices are not held back.
+        let from = pending.map(|p| p.from_chapter_id).or_else(|| {
+            previous
+                .as_ref()
+                .filter(|prev| latest.as_ref().is_some_and(|(id, _)| prev.latest_chapter_id != *id))
+                .map(|prev| prev.latest_chapter_id.clone())
+        });
+        if let Some(from) = from {
+            if auto_download_enabled(&aid)? {
+                match auto_download_new_chapters(&app, &fav, &album, &from) {
+                    Ok(true) => clear_auto_download_pending(&aid),
+                    Ok(false) => save_auto_download_pending(&aid, &from)?,
+                    Err(e) => {
+                        logl!("[tauri][autodl] queue failed aid={} err={}", aid, e);
+                    }
+                }
+            } else {
+                clear_auto_download_pending(&aid);
+            }
+        }
+
+        if let Some((latest_id, latest_sort)) = latest {
+            let latest_entry = LatestChapterEntry {
+                aid: aid.clone(),
+                latest_chapter_id: latest_id,
+                latest_chapter_sort: latest_sort,
+                updated_at: now,
+            };
+            latest_tree
+                .insert(
+                    aid.as_bytes(),
+                    serde_json::to_vec(&latest_entry)
+                        .map_err(|e| format!("encode latest failed: {e}"))?,
+                )
+                .map_err(|e| format!("write latest failed: {e}"))?;
+            let _ = latest_tree.flush();
+        } else {
+            let _ = latest_tree.remove(aid.as_bytes());
+            let _ = latest_tree.flush();
+        }
+
+        let seen_entry = LatestScanEntry {
+            aid: aid.clone(),
+            scanned_at: now,
+        };
+        seen_tree
+            .insert(
+                aid.as_bytes(),
+                serde_json::to_vec(&seen_entry).map_err(|e| format!("encode seen failed: {e}"))?,
+            )
+            .map_err(|e| format!("write seen failed: {e}"))?;
+        let _ = seen_tree.flush();
     }
 
     Ok(())
@@ -1839,2189 +3571,7554 @@ async fn scan_follow_updates(app: tauri::AppHandle) -> Result<(), String> {
     let updates_tree = read_update_tree()?;
     let latest_tree = read_latest_tree()?;
 
-    for item in tree.iter() {
-        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
-        let fav: LocalFavoriteItem =
-            bincode::deserialize(&val).map_err(|e| format!("decode favorite failed: {e}"))?;
-        let aid = fav.aid.clone();
-        let Some(progress_bytes) = progress_tree
-            .get(aid.as_bytes())
-            .map_err(|e| format!("read progress failed: {e}"))?
-        else {
-            continue;
-        };
-        let progress: ReadProgressEntry = serde_json::from_slice(&progress_bytes)
-            .map_err(|e| format!("decode read progress failed: {e}"))?;
-        let Some(progress_chapter_id) = progress.chapter_id.clone() else {
-            continue;
-        };
+    for item in tree.iter() {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        let fav: LocalFavoriteItem =
+            bincode::deserialize(&val).map_err(|e| format!("decode favorite failed: {e}"))?;
+        let aid = fav.aid.clone();
+        let Some(progress_bytes) = progress_tree
+            .get(aid.as_bytes())
+            .map_err(|e| format!("read progress failed: {e}"))?
+        else {
+            continue;
+        };
+        let progress: ReadProgressEntry = serde_json::from_slice(&progress_bytes)
+            .map_err(|e| format!("decode read progress failed: {e}"))?;
+        let Some(progress_chapter_id) = progress.chapter_id.clone() else {
+            continue;
+        };
+
+        let latest_entry = latest_tree
+            .get(aid.as_bytes())
+            .map_err(|e| format!("read latest failed: {e}"))?
+            .and_then(|bytes| serde_json::from_slice::<LatestChapterEntry>(&bytes).ok());
+        let Some(latest_entry) = latest_entry else {
+            continue;
+        };
+        let latest_id = latest_entry.latest_chapter_id.clone();
+        let latest_sort = latest_entry.latest_chapter_sort.clone();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let prev_state = follow_tree
+            .get(aid.as_bytes())
+            .map_err(|e| format!("read follow state failed: {e}"))?
+            .and_then(|bytes| serde_json::from_slice::<FollowStateEntry>(&bytes).ok());
+
+        if let Some(state) = prev_state {
+            if state.last_known_chapter_id != latest_id {
+                let update = FollowUpdateEntry {
+                    aid: aid.clone(),
+                    latest_chapter_id: latest_id.clone(),
+                    latest_chapter_sort: latest_sort.clone(),
+                    updated_at: now,
+                };
+                updates_tree
+                    .insert(
+                        aid.as_bytes(),
+                        serde_json::to_vec(&update)
+                            .map_err(|e| format!("encode update failed: {e}"))?,
+                    )
+                    .map_err(|e| format!("write update failed: {e}"))?;
+                let _ = updates_tree.flush();
+                logl!(
+                    "[tauri][follow] update found aid={} latest={}",
+                    aid,
+                    latest_id
+                );
+            }
+        }
+
+        if progress_chapter_id == latest_id {
+            let state = FollowStateEntry {
+                aid: aid.clone(),
+                last_known_chapter_id: latest_id.clone(),
+                last_known_chapter_sort: latest_sort.clone(),
+                updated_at: now,
+            };
+            follow_tree
+                .insert(
+                    aid.as_bytes(),
+                    serde_json::to_vec(&state).map_err(|e| format!("encode follow failed: {e}"))?,
+                )
+                .map_err(|e| format!("write follow failed: {e}"))?;
+            let _ = follow_tree.flush();
+        } else {
+            let _ = follow_tree.remove(aid.as_bytes());
+            let _ = follow_tree.flush();
+        }
+    }
+
+    Ok(())
+}
+
+async fn fetch_api_domain_list() -> Result<Vec<String>, String> {
+    let client = http_client()?;
+    let mut last_err: Option<String> = None;
+    for url in JM_API_DOMAIN_SERVER_LIST {
+        logl!("[tauri][api] domain fetch start url={}", url);
+        let resp = match client.get(*url).send().await {
+            Ok(r) => r,
+            Err(e) => {
+                last_err = Some(format!("fetch failed: {e}"));
+                logl!("[tauri][api] domain fetch error url={} err={}", url, e);
+                continue;
+            }
+        };
+        if !resp.status().is_success() {
+            last_err = Some(format!("http status {} from {}", resp.status(), url));
+            logl!(
+                "[tauri][api] domain fetch status url={} status={}",
+                url,
+                resp.status()
+            );
+            continue;
+        }
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| format!("read response body failed: {e}"))?;
+        let cleaned = strip_leading_non_ascii(&body);
+        let mut list = Vec::new();
+        match decode_domain_server_payload(cleaned) {
+            Ok(decoded) => {
+                list = parse_api_domain_payload(&decoded);
+                if list.is_empty() {
+                    let preview = decoded.chars().take(200).collect::<String>();
+                    logl!(
+                        "[tauri][api] domain decode payload empty: {}",
+                        preview
+                    );
+                }
+            }
+            Err(e) => {
+                let preview = cleaned.chars().take(200).collect::<String>();
+                logl!(
+                    "[tauri][api] domain decode failed: {} raw_preview={}",
+                    e,
+                    preview
+                );
+            }
+        }
+        if list.is_empty() {
+            list = parse_api_domain_list(cleaned);
+        }
+        if !list.is_empty() {
+            logl!(
+                "[tauri][api] domain fetch success url={} count={}",
+                url,
+                list.len()
+            );
+            logl!("[tauri][api] domain fetch list: {}", list.join(", "));
+            return Ok(list);
+        }
+        last_err = Some(format!("empty domain list from {}", url));
+        logl!(
+            "[tauri][api] domain fetch empty url={}",
+            url
+        );
+    }
+    Err(last_err.unwrap_or_else(|| "api domain list unavailable".to_string()))
+}
+
+#[tauri::command]
+async fn api_api_domain_fetch() -> Result<Vec<String>, String> {
+    let list = fetch_api_domain_list().await?;
+    update_api_domain_cache(list.clone())?;
+    Ok(list)
+}
+
+fn api_error(status: reqwest::StatusCode, api_code: i64, msg: impl AsRef<str>) -> String {
+    format!(
+        "HTTP_STATUS={};API_CODE={};{}",
+        status.as_u16(),
+        api_code,
+        msg.as_ref()
+    )
+}
+
+async fn api_get_encrypted(
+    path: &str,
+    page: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let url = format!(
+            "{}/{}/?page={}",
+            base.trim_end_matches('/'),
+            path.trim_start_matches('/'),
+            page
+        );
+        let resp = match client
+            .get(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+            )
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .header(reqwest::header::COOKIE, cookie_header(&cookies))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][api] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][api] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(300)
+            .collect::<String>();
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][api] retry base={} body_hint", base);
+            continue;
+        }
+
+        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if env.code != 200 {
+            let msg = if !env.error_msg.is_empty() {
+                env.error_msg
+            } else if !env.message.is_empty() {
+                env.message
+            } else {
+                format!("request failed, code={}, http_status={}", env.code, status)
+            };
+            logl!(
+                "[tauri][api] error path={} http_status={} api_code={} base={:?} msg={:?}",
+                path, status, env.code, base, msg
+            );
+            return Err(api_error(status, env.code, msg));
+        }
+
+        let encrypted = env
+            .data
+            .as_str()
+            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
+        let decrypted = decode_resp_data(encrypted, ts)?;
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        return serde_json::from_str(&decrypted)
+            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+async fn api_get_encrypted_with_query(
+    path: &str,
+    query: Option<String>,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let mut url = format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+
+        if let Some(q) = query.clone() {
+            if !q.is_empty() {
+                if !url.ends_with('/') {
+                    url.push('/');
+                }
+                url.push('?');
+                url.push_str(&q);
+            }
+        }
+
+        let resp = match client
+            .get(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+            )
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .header(reqwest::header::COOKIE, cookie_header(&cookies))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][api] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][api] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(300)
+            .collect::<String>();
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][api] retry base={} body_hint", base);
+            continue;
+        }
+
+        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if env.code != 200 {
+            let msg = if !env.error_msg.is_empty() {
+                env.error_msg
+            } else if !env.message.is_empty() {
+                env.message
+            } else {
+                format!("request failed, code={}, http_status={}", env.code, status)
+            };
+            logl!(
+                "[tauri][api] error path={} http_status={} api_code={} base={:?} msg={:?}",
+                path, status, env.code, base, msg
+            );
+            return Err(api_error(status, env.code, msg));
+        }
+
+        let encrypted = env
+            .data
+            .as_str()
+            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
+        let decrypted = decode_resp_data(encrypted, ts)?;
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        return serde_json::from_str(&decrypted)
+            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+async fn api_post_encrypted(
+    path: &str,
+    form: &[(&str, &str)],
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let url = format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+
+        let resp = match client
+            .post(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+            )
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .header(reqwest::header::COOKIE, cookie_header(&cookies))
+            .form(form)
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][api] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][api] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(300)
+            .collect::<String>();
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][api] retry base={} body_hint", base);
+            continue;
+        }
+
+        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if env.code != 200 {
+            let msg = if !env.error_msg.is_empty() {
+                env.error_msg
+            } else if !env.message.is_empty() {
+                env.message
+            } else {
+                format!("request failed, code={}, http_status={}", env.code, status)
+            };
+            logl!(
+                "[tauri][api] error path={} http_status={} api_code={} base={:?} msg={:?}",
+                path, status, env.code, base, msg
+            );
+            return Err(api_error(status, env.code, msg));
+        }
+
+        let encrypted = env
+            .data
+            .as_str()
+            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
+        let decrypted = decode_resp_data(encrypted, ts)?;
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        return serde_json::from_str(&decrypted)
+            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+#[tauri::command]
+async fn login(username: String, password: String) -> Result<LoginResult, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let url = format!("{}/login", base.trim_end_matches('/'));
+
+        let resp = match client
+            .post(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .form(&[("username", username.as_str()), ("password", password.as_str())])
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][login] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        let headers = resp.headers().clone();
+
+        let mut cookies = HashMap::new();
+        for c in resp.cookies() {
+            cookies.insert(c.name().to_string(), c.value().to_string());
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(500)
+            .collect::<String>();
+
+        logl!(
+            "[tauri][login] status={} url={} content-type={:?} content-encoding={:?} body_len={} body_preview={:?}",
+            status,
+            url,
+            headers.get(reqwest::header::CONTENT_TYPE),
+            headers.get(reqwest::header::CONTENT_ENCODING),
+            body_bytes.len(),
+            body_preview
+        );
+
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][login] retry base={} status={}", base, status);
+            continue;
+        }
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][login] retry base={} body_hint", base);
+            continue;
+        }
+
+        let body: ApiLoginResponse = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if body.code != 200 {
+            let msg = if !body.error_msg.is_empty() {
+                body.error_msg
+            } else if !body.message.is_empty() {
+                body.message
+            } else {
+                format!("login failed, code={}", body.code)
+            };
+            return Err(msg);
+        }
+
+        let data_b64 = body
+            .data
+            .ok_or_else(|| "missing response data".to_string())?;
+        let decrypted = decode_resp_data(&data_b64, ts)?;
+        let user: ApiUserInfo =
+            serde_json::from_str(&decrypted).map_err(|e| format!("parse user data failed: {e}"))?;
+
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        if let Ok(mut cfg) = config_state().lock() {
+            cfg.session_cookies = cookies.clone();
+            if let Err(e) = save_config_to_disk(&cfg) {
+                logl!("[tauri][login] save cookies failed: {e}");
+            }
+        }
+        return Ok(LoginResult { user, cookies });
+    }
+
+    Err(last_err.unwrap_or_else(|| "login failed".to_string()))
+}
+
+#[tauri::command]
+async fn api_latest(
+    page: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    api_get_encrypted("/latest", page, cookies).await
+}
+
+#[tauri::command]
+async fn api_promote(
+    page: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    api_get_encrypted("/promote", page, cookies).await
+}
+
+#[tauri::command]
+async fn api_history(page: String, cookies: HashMap<String, String>) -> Result<serde_json::Value, String> {
+    api_get_encrypted("/watch_list", page, cookies).await
+}
+
+#[tauri::command]
+async fn api_daily(user_id: String, cookies: HashMap<String, String>) -> Result<serde_json::Value, String> {
+    let query = format!("user_id={}", user_id);
+    api_get_encrypted_with_query("/daily", Some(query), cookies).await
+}
+
+#[tauri::command]
+async fn api_daily_check(
+    user_id: String,
+    daily_id: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    api_post_encrypted(
+        "/daily_chk",
+        &[("user_id", user_id.as_str()), ("daily_id", daily_id.as_str())],
+        cookies,
+    )
+    .await
+}
+
+#[tauri::command]
+async fn api_comments(
+    aid: String,
+    page: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let aid_trim = aid.trim();
+    let page_trim = page.trim();
+    let page_val = if page_trim.is_empty() { "1" } else { page_trim };
+    let query = format!(
+        "mode=manhua&aid={}&page={}",
+        urlencoding::encode(aid_trim),
+        urlencoding::encode(page_val)
+    );
+    api_get_encrypted_with_query("/forum", Some(query), cookies).await
+}
+
+#[tauri::command]
+async fn api_comment_send(
+    aid: String,
+    comment: String,
+    comment_id: Option<String>,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let cid = comment_id.unwrap_or_default();
+    let mut form = vec![("comment", comment.as_str()), ("aid", aid.as_str())];
+    if !cid.trim().is_empty() {
+        form.push(("comment_id", cid.as_str()));
+    }
+    api_post_encrypted("/comment", &form, cookies).await
+}
+
+#[tauri::command]
+async fn api_categories(cookies: HashMap<String, String>) -> Result<serde_json::Value, String> {
+    api_get_encrypted_with_query("/categories", None, cookies).await
+}
+
+#[tauri::command]
+fn api_read_cache_stats(app: tauri::AppHandle) -> Result<ReadCacheStats, String> {
+    ensure_read_cache_index(&resolve_read_cache_dir(&app)?)?;
+    Ok(load_cache_summary(&cache_summary_tree()?))
+}
+
+#[tauri::command]
+fn api_read_cache_refresh(app: tauri::AppHandle) -> Result<(), String> {
+    rebuild_read_cache_index(&resolve_read_cache_dir(&app)?).map(|_| ())
+}
+
+#[tauri::command]
+fn api_read_cache_cleanup(app: tauri::AppHandle, max_bytes: u64) -> Result<ReadCacheStats, String> {
+    ensure_cache_not_moving()?;
+    let base = resolve_read_cache_dir(&app)?;
+    let options = CacheEvictionOptions {
+        max_bytes: Some(max_bytes),
+        ..Default::default()
+    };
+    evict_read_cache(&base, &options, &local_favorite_aids(&app)).map(|r| r.stats)
+}
+
+#[tauri::command]
+async fn api_read_cache_evict(
+    app: tauri::AppHandle,
+    options: Option<CacheEvictionOptions>,
+) -> Result<CacheEvictionReport, String> {
+    ensure_cache_not_moving()?;
+    let base = resolve_read_cache_dir(&app)?;
+    let favorites = local_favorite_aids(&app);
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || evict_read_cache(&base, &options, &favorites))
+        .await
+        .map_err(|e| format!("cache evict task failed: {e}"))?
+}
+
+#[tauri::command]
+fn api_read_cache_policy_list() -> Result<Vec<CacheComicPolicy>, String> {
+    let mut list: Vec<CacheComicPolicy> = load_cache_policies()?.into_values().collect();
+    list.sort_by_key(|p| std::cmp::Reverse(p.updated_at));
+    Ok(list)
+}
+
+// `quota_bytes: 0` clears the quota; a policy with nothing set is removed.
+#[tauri::command]
+fn api_read_cache_policy_set(
+    aid: String,
+    pinned: Option<bool>,
+    quota_bytes: Option<u64>,
+) -> Result<CacheComicPolicy, String> {
+    let aid = sanitize_path_component(aid.trim());
+    if aid.is_empty() {
+        return Err("aid is empty".to_string());
+    }
+    set_cache_policy(&aid, pinned, quota_bytes)
+}
+
+fn set_cache_policy(
+    aid: &str,
+    pinned: Option<bool>,
+    quota_bytes: Option<u64>,
+) -> Result<CacheComicPolicy, String> {
+    let tree = cache_policy_tree()?;
+    let mut policy: CacheComicPolicy = tree
+        .get(aid.as_bytes())
+        .map_err(|e| format!("read cache policy failed: {e}"))?
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_else(|| CacheComicPolicy {
+            aid: aid.to_string(),
+            ..Default::default()
+        });
+    if let Some(pinned) = pinned {
+        policy.pinned = pinned;
+    }
+    if let Some(quota) = quota_bytes {
+        policy.quota_bytes = (quota > 0).then_some(quota);
+    }
+    policy.updated_at = now_millis();
+    if !policy.pinned && policy.quota_bytes.is_none() {
+        tree.remove(aid.as_bytes())
+            .map_err(|e| format!("remove cache policy failed: {e}"))?;
+    } else {
+        let val = serde_json::to_vec(&policy).map_err(|e| format!("encode cache policy failed: {e}"))?;
+        tree.insert(aid.as_bytes(), val)
+            .map_err(|e| format!("write cache policy failed: {e}"))?;
+    }
+    let _ = tree.flush();
+    Ok(policy)
+}
+
+fn emit_cache_move_progress(app: &tauri::AppHandle, progress: &CacheMoveProgress) {
+    let _ = app.emit("cache-move-progress", progress);
+}
+
+fn move_read_cache(
+    app: &tauri::AppHandle,
+    from: &std::path::Path,
+    to: &std::path::Path,
+    new_root: Option<String>,
+) -> Result<CacheMoveResult, String> {
+    let started = Instant::now();
+    if to.exists() && !dir_is_empty(to) {
+        return Err(format!("target {:?} is not empty", to));
+    }
+    let (total_files, total_bytes) = count_dir_files(from);
+    let parent = to
+        .parent()
+        .ok_or_else(|| format!("no parent dir for {:?}", to))?;
+    std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+    ensure_write_space(parent, total_bytes)?;
+
+    let mut progress = CacheMoveProgress {
+        phase: "copy".to_string(),
+        total_files,
+        total_bytes,
+        ..Default::default()
+    };
+    emit_cache_move_progress(app, &progress);
+    let staging = to.with_extension("moving");
+    let mut last_emit = Instant::now();
+    let mut files = Vec::new();
+    let copied = copy_dir_recursive(from, &staging, &mut |path, len| {
+        files.push((path.to_path_buf(), len));
+        progress.done_files += 1;
+        progress.done_bytes += len;
+        if last_emit.elapsed() >= Duration::from_millis(200) {
+            emit_cache_move_progress(app, &progress);
+            last_emit = Instant::now();
+        }
+    })
+    .map_err(|e| format!("copy cache failed: {e}"))?;
+
+    progress.phase = "verify".to_string();
+    emit_cache_move_progress(app, &progress);
+    if let Err(e) = verify_copied_files(&files) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(format!("verify cache copy failed: {e}"));
+    }
+    let _ = std::fs::remove_dir(to);
+    std::fs::rename(&staging, to).map_err(|e| format!("commit cache dir failed: {e}"))?;
+
+    // Switch: new reads and writes go to the new dir from here on.
+    progress.phase = "switch".to_string();
+    emit_cache_move_progress(app, &progress);
+    {
+        let mut cfg = config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?;
+        cfg.cache_root = new_root;
+        save_config_to_disk(&cfg)?;
+    }
+    if let Err(e) = app.asset_protocol_scope().allow_directory(to, true) {
+        logl!("[tauri][cache] allow asset scope {:?} failed: {}", to, e);
+    }
+
+    // Cache writes were refused for the whole move, so the copy is complete.
+    progress.phase = "cleanup".to_string();
+    emit_cache_move_progress(app, &progress);
+    let old_removed = match std::fs::remove_dir_all(from) {
+        Ok(()) => true,
+        Err(e) => {
+            logl!("[tauri][cache] remove old cache {:?} failed: {}", from, e);
+            false
+        }
+    };
+
+    progress.phase = "done".to_string();
+    progress.done_files = copied.0;
+    progress.done_bytes = copied.1;
+    emit_cache_move_progress(app, &progress);
+    // Absolute cache paths handed out before the move (cover paths etc.) now point at the
+    // old dir; the frontend drops them on this event and asks again.
+    let _ = app.emit(
+        "cache-dir-moved",
+        serde_json::json!({ "from": from.to_string_lossy(), "to": to.to_string_lossy() }),
+    );
+    Ok(CacheMoveResult {
+        from: from.to_string_lossy().to_string(),
+        to: to.to_string_lossy().to_string(),
+        files: copied.0,
+        bytes: copied.1,
+        old_removed,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+// Moves the cache under `root` (or back to the default location when empty),
+// emitting `cache-move-progress` events along the way and `cache-dir-moved` once switched.
+#[tauri::command]
+async fn api_cache_dir_move(
+    app: tauri::AppHandle,
+    root: Option<String>,
+) -> Result<CacheMoveResult, String> {
+    let root = root.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+    let from = resolve_read_cache_dir(&app)?;
+    let to = match &root {
+        Some(r) => std::path::PathBuf::from(r).join("jmcomic-cache"),
+        None => default_read_cache_dir(),
+    };
+    if to == from {
+        return Err("cache is already there".to_string());
+    }
+    if to.starts_with(&from) || from.starts_with(&to) {
+        return Err("new cache dir must not be inside the old one or contain it".to_string());
+    }
+    if CACHE_MOVE_RUNNING.swap(true, Ordering::AcqRel) {
+        return Err("cache move already running".to_string());
+    }
+    // A running job keeps writing under the dir it resolved at start.
+    if DOWNLOAD_ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+        CACHE_MOVE_RUNNING.store(false, Ordering::Release);
+        return Err("a download is running; pause it before moving the cache".to_string());
+    }
+    let handle = app.clone();
+    let res = tauri::async_runtime::spawn_blocking(move || move_read_cache(&handle, &from, &to, root))
+        .await
+        .map_err(|e| format!("cache move task failed: {e}"));
+    CACHE_MOVE_RUNNING.store(false, Ordering::Release);
+    let result = res??;
+    logl!(
+        "[tauri][cache] moved {:?} -> {:?} files={} bytes={} cost_ms={}",
+        result.from,
+        result.to,
+        result.files,
+        result.bytes,
+        result.elapsed_ms
+    );
+    Ok(result)
+}
+
+// Walks the data dir and may rebuild the cache index, so the work runs off the main thread.
+#[tauri::command]
+async fn api_disk_space_status(app: tauri::AppHandle) -> Result<DiskSpaceStatus, String> {
+    let cache_dir = resolve_read_cache_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || disk_space_status(cache_dir))
+        .await
+        .map_err(|e| format!("disk space status task failed: {e}"))?
+}
+
+fn disk_space_status(cache_dir: std::path::PathBuf) -> Result<DiskSpaceStatus, String> {
+    let data_dir = resolve_data_dir()?;
+    ensure_read_cache_index(&cache_dir)?;
+    let cache_free_bytes = fs2::available_space(&cache_dir).ok();
+    let min_free_bytes = min_free_bytes();
+    Ok(DiskSpaceStatus {
+        cache_dir: cache_dir.to_string_lossy().to_string(),
+        cache_free_bytes,
+        cache_total_bytes: fs2::total_space(&cache_dir).ok(),
+        cache_used_bytes: load_cache_summary(&cache_summary_tree()?).total_bytes,
+        data_dir: data_dir.to_string_lossy().to_string(),
+        data_free_bytes: fs2::available_space(&data_dir).ok(),
+        data_total_bytes: fs2::total_space(&data_dir).ok(),
+        data_used_bytes: dir_size_bytes(&data_dir),
+        min_free_bytes,
+        low: cache_free_bytes.is_some_and(|f| f < min_free_bytes),
+    })
+}
+
+#[tauri::command]
+async fn api_cover_cache_clear(
+    app: tauri::AppHandle,
+    keep_favorites: Option<bool>,
+) -> Result<ReadCacheStats, String> {
+    ensure_cache_not_moving()?;
+    let base = resolve_read_cache_dir(&app)?;
+    let keep = if keep_favorites.unwrap_or(true) {
+        favorite_cover_keys(&app)
+    } else {
+        std::collections::HashSet::new()
+    };
+    tauri::async_runtime::spawn_blocking(move || evict_cover_cache(&base, 0, &keep))
+        .await
+        .map_err(|e| format!("cover clear task failed: {e}"))??;
+    let _ = cache_stats_db()?.flush();
+    Ok(load_cache_summary(&cache_summary_tree()?))
+}
+
+#[tauri::command]
+fn api_read_cache_comics(
+    app: tauri::AppHandle,
+    sort: Option<String>,
+    store: tauri::State<'_, LocalFavoritesStore>,
+) -> Result<Vec<ReadCacheComicUsage>, String> {
+    let base = resolve_read_cache_dir(&app)?;
+    ensure_read_cache_index(&base)?;
+    let policies = load_cache_policies()?;
+    let favorites = store.tree().ok();
+    let progress = read_progress_tree().ok();
+    let cover_dir = base.join("cover");
+
+    let mut out = Vec::new();
+    for res in cache_comics_tree()?.iter() {
+        let (_, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        let Ok(stats) = serde_json::from_slice::<ReadCacheComicStats>(&v) else {
+            continue;
+        };
+        let policy = policies.get(&stats.aid);
+        let mut item = ReadCacheComicUsage {
+            aid: stats.aid.clone(),
+            files: stats.files,
+            bytes: stats.bytes,
+            thumb_bytes: stats.thumb_bytes,
+            total_bytes: stats.bytes + stats.thumb_bytes,
+            accessed_at: stats.accessed_at,
+            pinned: policy.is_some_and(|p| p.pinned),
+            quota_bytes: policy.and_then(|p| p.quota_bytes),
+            ..Default::default()
+        };
+        let favorite = favorites
+            .and_then(|t| t.get(stats.aid.as_bytes()).ok().flatten())
+            .and_then(|v| bincode::deserialize::<LocalFavoriteItem>(&v).ok());
+        if let Some(fav) = favorite {
+            if !fav.cover_url.trim().is_empty() {
+                item.cover_path = find_cached_image(&cover_dir, &md5_hex(fav.cover_url.trim()))
+                    .map(|p| p.to_string_lossy().to_string());
+                item.cover_url = Some(fav.cover_url);
+            }
+            item.title = Some(fav.title).filter(|t| !t.is_empty());
+            item.author = Some(fav.author).filter(|a| !a.is_empty());
+        }
+        let entry = progress
+            .as_ref()
+            .and_then(|t| t.get(stats.aid.as_bytes()).ok().flatten())
+            .and_then(|v| serde_json::from_slice::<ReadProgressEntry>(&v).ok());
+        if let Some(entry) = entry {
+            item.last_read_at = Some(entry.updated_at);
+            item.last_chapter_name = entry.chapter_name;
+        }
+        out.push(item);
+    }
+
+    match sort.as_deref().unwrap_or("size") {
+        "recent" => out.sort_by_key(|c| std::cmp::Reverse(c.accessed_at.max(c.last_read_at.unwrap_or(0)))),
+        _ => out.sort_by_key(|c| std::cmp::Reverse(c.total_bytes)),
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+fn api_read_cache_comic_chapters(
+    app: tauri::AppHandle,
+    aid: String,
+) -> Result<Vec<ReadCacheChapterUsage>, String> {
+    ensure_read_cache_index(&resolve_read_cache_dir(&app)?)?;
+    let aid = sanitize_path_component(aid.trim());
+    let entries = cache_index_comic_entries(&aid)?;
+
+    // Variants and thumbnails carry no chapter; attribute them through their page key.
+    let key_chapter: HashMap<String, Option<String>> = entries
+        .iter()
+        .filter(|(_, e)| e.area == "read" && e.chapter_id.is_some())
+        .map(|(_, e)| (e.key.clone(), e.chapter_id.clone()))
+        .collect();
+    let mut chapters: HashMap<Option<String>, ReadCacheChapterUsage> = HashMap::new();
+    let mut page_keys: std::collections::HashSet<(Option<String>, String)> =
+        std::collections::HashSet::new();
+    for (_, entry) in &entries {
+        let chapter_id = key_chapter.get(&entry.key).cloned().flatten();
+        let item = chapters
+            .entry(chapter_id.clone())
+            .or_insert_with(|| ReadCacheChapterUsage {
+                chapter_id: chapter_id.clone(),
+                ..Default::default()
+            });
+        item.bytes += entry.bytes;
+        item.accessed_at = item.accessed_at.max(entry.accessed_at);
+        if entry.area == "read" && page_keys.insert((chapter_id, entry.key.clone())) {
+            item.pages += 1;
+        }
+    }
+    let mut out: Vec<ReadCacheChapterUsage> = chapters.into_values().collect();
+    out.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+    Ok(out)
+}
+
+#[tauri::command]
+fn api_read_cache_delete_comics(
+    app: tauri::AppHandle,
+    aids: Vec<String>,
+) -> Result<ReadCacheStats, String> {
+    ensure_cache_not_moving()?;
+    let base = resolve_read_cache_dir(&app)?;
+    ensure_read_cache_index(&base)?;
+    for aid in aids {
+        let aid = sanitize_path_component(aid.trim());
+        if aid.is_empty() {
+            continue;
+        }
+        for area in ["read", "thumb"] {
+            let dir = base.join(area).join(&aid);
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(format!("remove {:?} failed: {e}", dir));
+                }
+            }
+        }
+        cache_index_remove_comic(&aid)?;
+        logl!("[tauri][cache] deleted comic cache aid={}", aid);
+    }
+    let _ = cache_stats_db()?.flush();
+    Ok(load_cache_summary(&cache_summary_tree()?))
+}
+
+#[tauri::command]
+fn api_read_cache_delete_chapters(
+    app: tauri::AppHandle,
+    aid: String,
+    chapter_ids: Vec<String>,
+) -> Result<ReadCacheStats, String> {
+    ensure_cache_not_moving()?;
+    let base = resolve_read_cache_dir(&app)?;
+    ensure_read_cache_index(&base)?;
+    let aid = sanitize_path_component(aid.trim());
+    let wanted: std::collections::HashSet<&str> = chapter_ids.iter().map(|c| c.as_str()).collect();
+    let entries = cache_index_comic_entries(&aid)?;
+    let keys: std::collections::HashSet<&str> = entries
+        .iter()
+        .filter(|(_, e)| e.area == "read")
+        .filter(|(_, e)| e.chapter_id.as_deref().is_some_and(|c| wanted.contains(c)))
+        .map(|(_, e)| e.key.as_str())
+        .collect();
+
+    let mut removed = 0u64;
+    for (rel, entry) in &entries {
+        if !keys.contains(entry.key.as_str()) {
+            continue;
+        }
+        let path = base.join(rel);
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("remove {:?} failed: {e}", path)),
+        }
+        cache_index_remove(&path);
+    }
+    logl!(
+        "[tauri][cache] deleted chapter cache aid={} chapters={} files={}",
+        aid,
+        wanted.len(),
+        removed
+    );
+    let _ = cache_stats_db()?.flush();
+    Ok(load_cache_summary(&cache_summary_tree()?))
+}
+
+#[tauri::command]
+async fn api_read_cache_repair(app: tauri::AppHandle) -> Result<ReadCacheRepairResult, String> {
+    ensure_cache_not_moving()?;
+    let base = resolve_read_cache_dir(&app)?;
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let mut result = ReadCacheRepairResult::default();
+        for sub in ["read", "cover", "thumb"] {
+            let dir = base.join(sub);
+            if dir.exists() {
+                repair_image_cache_dir(&dir, &mut result);
+            }
+        }
+        result.elapsed_ms = started.elapsed().as_millis() as u64;
+        result
+    })
+    .await
+    .map_err(|e| format!("cache repair task failed: {e}"))?;
+
+    logl!(
+        "[tauri][cache] repair done scanned={} removed={} removed_bytes={} cost_ms={}",
+        result.scanned_files,
+        result.removed_files,
+        result.removed_bytes,
+        result.elapsed_ms
+    );
+    Ok(result)
+}
+
+#[tauri::command]
+async fn api_register_captcha(web_base: Option<String>) -> Result<String, String> {
+    let base = web_base_from_opt(web_base);
+    let url = format!("{}/captcha", base.trim_end_matches('/'));
+    let referer = format!("{}/signup", base.trim_end_matches('/'));
+
+    let client = register_client()?;
+    let resp = client
+        .get(&url)
+        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
+        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
+        .header("accept-language", "zh-CN,zh;q=0.9")
+        .header("accept-encoding", "identity")
+        .header(reqwest::header::REFERER, referer)
+        .send()
+        .await
+        .map_err(|e| format!("captcha request failed: {e}"))?;
+
+    let status = resp.status();
+    let bytes = resp.bytes().await.map_err(|e| format!("read captcha failed: {e}"))?;
+    if !status.is_success() {
+        return Err(format!("captcha http status {}", status.as_u16()));
+    }
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+#[tauri::command]
+async fn api_register(
+    username: String,
+    email: String,
+    password: String,
+    verification: String,
+    gender: String,
+    web_base: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let base = web_base_from_opt(web_base);
+    let url = format!("{}/signup", base.trim_end_matches('/'));
+
+    let gender = if gender.eq_ignore_ascii_case("female") {
+        "Female"
+    } else {
+        "Male"
+    };
+
+    let client = register_client()?;
+    let resp = client
+        .post(&url)
+        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
+        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
+        .header("accept-language", "zh-CN,zh;q=0.9")
+        .header("accept-encoding", "identity")
+        .form(&[
+            ("username", username.as_str()),
+            ("password", password.as_str()),
+            ("email", email.as_str()),
+            ("verification", verification.as_str()),
+            ("password_confirm", password.as_str()),
+            ("gender", gender),
+            ("age", "on"),
+            ("terms", "on"),
+            ("submit_signup", ""),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("register request failed: {e}"))?;
+
+    let status = resp.status();
+    let text = resp.text().await.map_err(|e| format!("read register response failed: {e}"))?;
+    let (ok, msg) = parse_toastr_message(&text);
+
+    if ok {
+        return Ok(serde_json::json!({ "ok": true, "message": msg }));
+    }
+
+    if status.is_redirection() && msg.is_empty() {
+        return Ok(serde_json::json!({ "ok": true, "message": "" }));
+    }
+
+    if status != reqwest::StatusCode::OK && msg.is_empty() {
+        return Err(format!("register http status {}", status.as_u16()));
+    }
+
+    Ok(serde_json::json!({ "ok": false, "message": msg }))
+}
+
+#[tauri::command]
+async fn api_register_verify(email: String, web_base: Option<String>) -> Result<serde_json::Value, String> {
+    let base = web_base_from_opt(web_base);
+    let url = format!("{}/confirm", base.trim_end_matches('/'));
+
+    let client = register_client()?;
+    let resp = client
+        .post(&url)
+        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
+        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
+        .header("accept-language", "zh-CN,zh;q=0.9")
+        .header("accept-encoding", "identity")
+        .form(&[("email", email.as_str()), ("submit_confirm", "發送EMAIL")])
+        .send()
+        .await
+        .map_err(|e| format!("verify mail request failed: {e}"))?;
+
+    let status = resp.status();
+    let text = resp.text().await.map_err(|e| format!("read verify mail response failed: {e}"))?;
+    let (ok, msg) = parse_toastr_message(&text);
+
+    if ok {
+        return Ok(serde_json::json!({ "ok": true, "message": msg }));
+    }
+    if status.is_redirection() && msg.is_empty() {
+        return Ok(serde_json::json!({ "ok": true, "message": "" }));
+    }
+    if status != reqwest::StatusCode::OK && msg.is_empty() {
+        return Err(format!("verify mail http status {}", status.as_u16()));
+    }
+    Ok(serde_json::json!({ "ok": false, "message": msg }))
+}
+
+#[tauri::command]
+async fn api_reset_password(email: String, web_base: Option<String>) -> Result<serde_json::Value, String> {
+    let base = web_base_from_opt(web_base);
+    let url = format!("{}/lost", base.trim_end_matches('/'));
+
+    let client = register_client()?;
+    let resp = client
+        .post(&url)
+        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
+        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
+        .header("accept-language", "zh-CN,zh;q=0.9")
+        .header("accept-encoding", "identity")
+        .form(&[("email", email.as_str()), ("submit_lost", "恢復密碼")])
+        .send()
+        .await
+        .map_err(|e| format!("reset password request failed: {e}"))?;
+
+    let status = resp.status();
+    let text = resp.text().await.map_err(|e| format!("read reset response failed: {e}"))?;
+    let (ok, msg) = parse_toastr_message(&text);
+
+    if ok {
+        return Ok(serde_json::json!({ "ok": true, "message": msg }));
+    }
+    if status.is_redirection() && msg.is_empty() {
+        return Ok(serde_json::json!({ "ok": true, "message": "" }));
+    }
+    if status != reqwest::StatusCode::OK && msg.is_empty() {
+        return Err(format!("reset password http status {}", status.as_u16()));
+    }
+    Ok(serde_json::json!({ "ok": false, "message": msg }))
+}
+
+#[tauri::command]
+async fn api_verify_mail(url: String, web_base: Option<String>) -> Result<serde_json::Value, String> {
+    let url = normalize_verify_url(&url, web_base);
+    if url.is_empty() {
+        return Err("empty verify url".to_string());
+    }
+
+    let client = register_client()?;
+    let resp = client
+        .get(&url)
+        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
+        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
+        .header("accept-language", "zh-CN,zh;q=0.9")
+        .header("accept-encoding", "identity")
+        .send()
+        .await
+        .map_err(|e| format!("verify url request failed: {e}"))?;
+
+    let status = resp.status();
+    let text = resp.text().await.map_err(|e| format!("read verify url response failed: {e}"))?;
+    let (ok, msg) = parse_toastr_message(&text);
+
+    if ok {
+        return Ok(serde_json::json!({ "ok": true, "message": msg }));
+    }
+    if status.is_redirection() && msg.is_empty() {
+        return Ok(serde_json::json!({ "ok": true, "message": "" }));
+    }
+    if status != reqwest::StatusCode::OK && msg.is_empty() {
+        return Err(format!("verify url http status {}", status.as_u16()));
+    }
+    Ok(serde_json::json!({ "ok": false, "message": msg }))
+}
+
+#[tauri::command]
+async fn api_category_search(
+    category: String,
+    page: String,
+    sort: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let mut params: Vec<(String, String)> = Vec::new();
+    if let Ok(p) = page.parse::<i64>() {
+        if p > 1 {
+            params.push(("page".to_string(), p.to_string()));
+        }
+    }
+    if !sort.trim().is_empty() {
+        params.push(("o".to_string(), sort));
+    }
+    if !category.trim().is_empty() {
+        params.push(("c".to_string(), category));
+    }
+
+    let query = if params.is_empty() {
+        None
+    } else {
+        Some(
+            params
+                .into_iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join("&"),
+        )
+    };
+
+    api_get_encrypted_with_query("/categories/filter", query, cookies).await
+}
+
+// Trims the cover cache in the background once it outgrows its budget.
+fn enforce_cover_budget(app: &tauri::AppHandle, base: &std::path::Path) {
+    let max_bytes = cover_cache_max_bytes();
+    let over = cache_summary_tree()
+        .map(|t| load_cache_summary(&t).cover_bytes > max_bytes)
+        .unwrap_or(false);
+    if !over || COVER_EVICTION_RUNNING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let keep = favorite_cover_keys(app);
+    let base = base.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        // Evict a little below the budget so every new cover doesn't trigger a pass.
+        if let Err(e) = evict_cover_cache(&base, max_bytes / 10 * 9, &keep) {
+            logl!("[tauri][cover] budget eviction failed: {e}");
+        }
+        COVER_EVICTION_RUNNING.store(false, Ordering::Release);
+    });
+}
+
+#[tauri::command]
+async fn api_cover_cache(
+    url: String,
+    background: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let url = url.trim().to_string();
+    if url.is_empty() {
+        return Err("empty url".to_string());
+    }
+
+    let _permit = cover_semaphore()
+        .acquire()
+        .await
+        .map_err(|_| "cover cache queue closed".to_string())?;
+
+    let base = resolve_read_cache_dir(&app)?;
+    let cover_dir = base.join("cover");
+    std::fs::create_dir_all(&cover_dir).map_err(|e| format!("mkdir failed: {e}"))?;
+
+    let clean_url = url.split('#').next().unwrap_or(&url);
+    let clean_url = clean_url.split('?').next().unwrap_or(clean_url);
+    let ext = std::path::Path::new(clean_url)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| sanitize_path_component(s))
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "jpg".to_string());
+    let hash = md5_hex(&url);
+    let out_path = cover_dir.join(format!("{hash}.{ext}"));
+
+    if out_path.exists() {
+        cache_index_touch(&out_path);
+        return Ok(out_path.to_string_lossy().to_string());
+    }
+    if let Some(cached) = find_cached_image(&cover_dir, &hash) {
+        cache_index_touch(&cached);
+        return Ok(cached.to_string_lossy().to_string());
+    }
+
+    if background == Some(true) && !background_window_open() {
+        return Err(OUTSIDE_WINDOW_ERROR.to_string());
+    }
+
+    let client = http_client()?;
+    let mut last_err = None::<String>;
+    for attempt in 0..3 {
+        let resp = client
+            .get(&url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await;
+        match resp {
+            Ok(resp) => {
+                let status = resp.status();
+                if !status.is_success() {
+                    last_err = Some(format!("http status {}", status.as_u16()));
+                } else {
+                    let content_len = resp.content_length();
+                    let bytes = read_body_paced(resp, Transfer::from_background(background))
+                        .await
+                        .map_err(|e| format!("read body failed: {e}"))?;
+                    match check_image_header(&bytes, content_len) {
+                        Ok(fmt) => {
+                            // Name the file after the sniffed format so jmcache serves the right MIME type.
+                            let out_path = cover_dir.join(format!("{hash}.{}", ext_from_format(fmt)));
+                            ensure_cache_write_space_async(&base, bytes.len() as u64).await?;
+                            write_file_atomic(&out_path, &bytes)?;
+                            cache_index_record(&out_path, None);
+                            enforce_cover_budget(&app, &base);
+                            return Ok(out_path.to_string_lossy().to_string());
+                        }
+                        Err(e) => {
+                            last_err = Some(e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                last_err = Some(format!("request failed: {e}"));
+            }
+        }
+        if attempt < 2 {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+#[tauri::command]
+async fn api_search(
+    search_query: String,
+    sort: String,
+    page: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let mut url = format!(
+            "{}/search/?search_query={}&o={}",
+            base.trim_end_matches('/'),
+            urlencoding::encode(&search_query),
+            urlencoding::encode(&sort)
+        );
+        if page.trim() != "1" && !page.trim().is_empty() {
+            url.push_str(&format!("&page={}", urlencoding::encode(page.trim())));
+        }
+
+        let resp = match client
+            .get(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+            )
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .header(reqwest::header::COOKIE, cookie_header(&cookies))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][search] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][search] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(300)
+            .collect::<String>();
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][search] retry base={} body_hint", base);
+            continue;
+        }
+
+        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if env.code != 200 {
+            let msg = if !env.error_msg.is_empty() {
+                env.error_msg
+            } else if !env.message.is_empty() {
+                env.message
+            } else {
+                format!("request failed, code={}, http_status={}", env.code, status)
+            };
+            logl!(
+                "[tauri][search] error http_status={} api_code={} base={:?} msg={:?}",
+                status, env.code, base, msg
+            );
+            return Err(api_error(status, env.code, msg));
+        }
+
+        let encrypted = env
+            .data
+            .as_str()
+            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
+        let decrypted = decode_resp_data(encrypted, ts)?;
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        return serde_json::from_str(&decrypted)
+            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+async fn fetch_album(
+    id: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let url = format!(
+            "{}/album/?comicName=&id={}",
+            base.trim_end_matches('/'),
+            id
+        );
+
+        let resp = match client
+            .get(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+            )
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .header(reqwest::header::COOKIE, cookie_header(&cookies))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][album] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][album] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(300)
+            .collect::<String>();
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][album] retry base={} body_hint", base);
+            continue;
+        }
+
+        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if env.code != 200 {
+            let msg = if !env.error_msg.is_empty() {
+                env.error_msg
+            } else if !env.message.is_empty() {
+                env.message
+            } else {
+                format!("request failed, code={}, http_status={}", env.code, status)
+            };
+            return Err(msg);
+        }
+
+        let encrypted = env
+            .data
+            .as_str()
+            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
+        let decrypted = decode_resp_data(encrypted, ts)?;
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        let album: serde_json::Value =
+            serde_json::from_str(&decrypted).map_err(|e| format!("parse decrypted json failed: {e}"))?;
+        if let Some(series) = album.get("series").and_then(|v| v.as_array()) {
+            if let Ok(tree) = read_latest_tree() {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                if let Some((latest_id, latest_sort)) = parse_series_latest(series) {
+                    let entry = LatestChapterEntry {
+                        aid: id.clone(),
+                        latest_chapter_id: latest_id,
+                        latest_chapter_sort: latest_sort,
+                        updated_at: now,
+                    };
+                    if let Ok(val) = serde_json::to_vec(&entry) {
+                        let _ = tree.insert(id.as_bytes(), val);
+                        let _ = tree.flush();
+                    }
+                } else {
+                    let _ = tree.remove(id.as_bytes());
+                    let _ = tree.flush();
+                }
+            }
+        }
+        refresh_download_meta(&id, &album);
+        return Ok(album);
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+async fn fetch_chapter(
+    id: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret =
+        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let url = format!(
+            "{}/chapter/?comicName=&skip=&id={}",
+            base.trim_end_matches('/'),
+            id
+        );
+
+        let resp = match client
+            .get(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
+            )
+            .header("accept-encoding", "identity")
+            .header("version", JM_APP_VERSION)
+            .header(reqwest::header::COOKIE, cookie_header(&cookies))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][chapter] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][chapter] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let body_bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("failed reading response body: {e}"))?;
+
+        let body_preview = String::from_utf8_lossy(&body_bytes)
+            .chars()
+            .take(300)
+            .collect::<String>();
+        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
+            let msg = "body indicates invalid domain".to_string();
+            last_err = Some(msg.clone());
+            logl!("[tauri][chapter] retry base={} body_hint", base);
+            continue;
+        }
+
+        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
+            format!(
+                "invalid json response: status={status}, err={e}, body_preview={:?}",
+                body_preview
+            )
+        })?;
+
+        if env.code != 200 {
+            let msg = if !env.error_msg.is_empty() {
+                env.error_msg
+            } else if !env.message.is_empty() {
+                env.message
+            } else {
+                format!("request failed, code={}, http_status={}", env.code, status)
+            };
+            return Err(msg);
+        }
+
+        let encrypted = env
+            .data
+            .as_str()
+            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
+        let decrypted = decode_resp_data(encrypted, ts)?;
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        return serde_json::from_str(&decrypted)
+            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+const OFFLINE_RETRY_MS: i64 = 60_000;
+
+fn offline_mode() -> String {
+    config_state()
+        .lock()
+        .ok()
+        .and_then(|c| c.offline_mode.clone())
+        .filter(|m| m == "on" || m == "off")
+        .unwrap_or_else(|| "auto".to_string())
+}
+
+fn store_offline_snapshot(key: &str, value: &serde_json::Value) {
+    let res = read_offline_tree().and_then(|tree| {
+        let val = serde_json::to_vec(value).map_err(|e| format!("encode snapshot failed: {e}"))?;
+        tree.insert(key.as_bytes(), val)
+            .map_err(|e| format!("write snapshot failed: {e}"))?;
+        let _ = tree.flush();
+        Ok(())
+    });
+    if let Err(e) = res {
+        logl!("[tauri][offline] store snapshot failed key={} err={}", key, e);
+    }
+}
+
+fn load_offline_snapshot(key: &str) -> Result<Option<serde_json::Value>, String> {
+    let tree = read_offline_tree()?;
+    let Some(val) = tree
+        .get(key.as_bytes())
+        .map_err(|e| format!("read snapshot failed: {e}"))?
+    else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&val)
+        .map(Some)
+        .map_err(|e| format!("decode snapshot failed: {e}"))
+}
+
+// Snapshot to serve without touching the network: always when forced offline, and in
+// auto mode while the API was recently unreachable.
+fn offline_snapshot_first(key: &str) -> Result<Option<serde_json::Value>, String> {
+    match offline_mode().as_str() {
+        "on" => load_offline_snapshot(key)?
+            .map(Some)
+            .ok_or_else(|| format!("offline: {key} not downloaded")),
+        "auto" if now_millis() < NETWORK_DOWN_UNTIL.load(Ordering::Relaxed) => {
+            Ok(load_offline_snapshot(key).ok().flatten())
+        }
+        _ => Ok(None),
+    }
+}
+
+fn offline_fallback(key: &str, err: String) -> Result<serde_json::Value, String> {
+    if offline_mode() == "off" {
+        return Err(err);
+    }
+    // Only transport failures from `send()`; "request failed, code=.." is the API rejecting
+    // this one request and says nothing about the network.
+    if err.starts_with("request failed: ") {
+        NETWORK_DOWN_UNTIL.store(now_millis() + OFFLINE_RETRY_MS, Ordering::Relaxed);
+    }
+    match load_offline_snapshot(key) {
+        Ok(Some(v)) => {
+            logl!("[tauri][offline] serving snapshot key={} err={}", key, err);
+            Ok(v)
+        }
+        _ => Err(err),
+    }
+}
+
+const BANDWIDTH_SAMPLE_MS: u128 = 5000;
+// A limited transfer may run this far ahead of its budget before it is paced.
+const BANDWIDTH_BURST_MS: u64 = 250;
+
+// Image traffic is paced per class: the reader's own requests, and bulk work
+// (downloads, prefetch, cover warming) that should not starve it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    Interactive,
+    Background,
+}
+
+struct TransferMeter {
+    next_free: Mutex<Option<Instant>>,
+    samples: Mutex<std::collections::VecDeque<(Instant, u64)>>,
+    total: AtomicU64,
+}
+
+impl TransferMeter {
+    const fn new() -> Self {
+        Self {
+            next_free: Mutex::new(None),
+            samples: Mutex::new(std::collections::VecDeque::new()),
+            total: AtomicU64::new(0),
+        }
+    }
+
+    // Drops samples outside the averaging window; called on every record so the queue
+    // stays bounded whether or not anyone reads the status.
+    fn prune(samples: &mut std::collections::VecDeque<(Instant, u64)>) {
+        while samples
+            .front()
+            .is_some_and(|(t, _)| t.elapsed().as_millis() > BANDWIDTH_SAMPLE_MS)
+        {
+            samples.pop_front();
+        }
+    }
+
+    fn record(&self, bytes: u64) {
+        self.total.fetch_add(bytes, Ordering::Relaxed);
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        Self::prune(&mut samples);
+        samples.push_back((Instant::now(), bytes));
+    }
+
+    fn bytes_per_sec(&self) -> u64 {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        Self::prune(&mut samples);
+        let sum: u64 = samples.iter().map(|(_, n)| n).sum();
+        (sum as u128 * 1000 / BANDWIDTH_SAMPLE_MS) as u64
+    }
+}
+
+static INTERACTIVE_TRANSFER: TransferMeter = TransferMeter::new();
+static BACKGROUND_TRANSFER: TransferMeter = TransferMeter::new();
+
+impl Transfer {
+    fn from_background(background: Option<bool>) -> Self {
+        if background.unwrap_or(false) {
+            Transfer::Background
+        } else {
+            Transfer::Interactive
+        }
+    }
+
+    fn meter(self) -> &'static TransferMeter {
+        match self {
+            Transfer::Interactive => &INTERACTIVE_TRANSFER,
+            Transfer::Background => &BACKGROUND_TRANSFER,
+        }
+    }
+
+    fn limit(self) -> Option<u64> {
+        config_state().lock().ok().and_then(|cfg| match self {
+            Transfer::Interactive => cfg.bandwidth_interactive_bps,
+            Transfer::Background => cfg.bandwidth_background_bps,
+        })
+    }
+
+    // Records `bytes` just received and sleeps long enough to keep the class under `limit`.
+    async fn account(self, bytes: u64, limit: Option<u64>) {
+        let meter = self.meter();
+        meter.record(bytes);
+        let Some(limit) = limit else {
+            return;
+        };
+        let wait = {
+            let mut next = meter.next_free.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let start = next.filter(|n| *n > now).unwrap_or(now);
+            let end = start + Duration::from_secs_f64(bytes as f64 / limit as f64);
+            *next = Some(end);
+            end.saturating_duration_since(now + Duration::from_millis(BANDWIDTH_BURST_MS))
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn status(self) -> TransferClassStatus {
+        let meter = self.meter();
+        TransferClassStatus {
+            limit: self.limit(),
+            bytes_per_sec: meter.bytes_per_sec(),
+            total_bytes: meter.total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Reads the body chunk by chunk so the class cap applies while it streams in. Windows are
+// checked before the request is sent, never mid-body, so no connection is held open.
+async fn read_body_paced(mut resp: reqwest::Response, class: Transfer) -> reqwest::Result<Vec<u8>> {
+    let limit = class.limit();
+    let mut out = Vec::with_capacity(resp.content_length().unwrap_or(0) as usize);
+    while let Some(chunk) = resp.chunk().await? {
+        out.extend_from_slice(&chunk);
+        class.account(chunk.len() as u64, limit).await;
+    }
+    Ok(out)
+}
+
+// "HH:MM-HH:MM" in local time as minutes of the day; the end may be past midnight.
+fn parse_time_window(s: &str) -> Option<(u32, u32)> {
+    let minutes = |t: &str| -> Option<u32> {
+        let (h, m) = t.trim().split_once(':')?;
+        let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+        (h <= 24 && m < 60 && h * 60 + m <= 1440).then_some(h * 60 + m)
+    };
+    let (start, end) = s.split_once('-')?;
+    Some((minutes(start)?, minutes(end)?))
+}
+
+// Windows are read in the system's current local time, so DST changes are followed.
+fn background_window_open() -> bool {
+    use chrono::Timelike as _;
+
+    let windows = config_state()
+        .lock()
+        .map(|cfg| cfg.background_windows.clone())
+        .unwrap_or_default();
+    if windows.is_empty() {
+        return true;
+    }
+    let now = chrono::Local::now();
+    let minute = now.hour() * 60 + now.minute();
+    windows.iter().filter_map(|w| parse_time_window(w)).any(|(start, end)| {
+        if start <= end {
+            (start..end).contains(&minute)
+        } else {
+            minute >= start || minute < end
+        }
+    })
+}
+
+// Returned by background fetches that have no job to wait in (prefetch, cover warming).
+const OUTSIDE_WINDOW_ERROR: &str = "outside background window";
+
+// Holds a download until a window opens; `Err("cancelled")` if `stop` is set meanwhile.
+async fn wait_background_window(stop: &Arc<AtomicBool>) -> Result<(), String> {
+    let mut logged = false;
+    while !background_window_open() {
+        if !logged {
+            logl!("[tauri][bandwidth] outside background window, download waiting");
+            logged = true;
+        }
+        for _ in 0..30 {
+            if stop.load(Ordering::Relaxed) {
+                return Err("cancelled".to_string());
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+    Ok(())
+}
+
+// Caps are bytes per second (`None` or 0 = unlimited). `windows` are local-time
+// "HH:MM-HH:MM" ranges when background transfers may run (empty = always).
+#[tauri::command]
+async fn api_config_set_bandwidth(
+    background_bps: Option<u64>,
+    interactive_bps: Option<u64>,
+    windows: Option<Vec<String>>,
+) -> Result<(), String> {
+    let windows: Vec<String> = windows
+        .unwrap_or_default()
+        .into_iter()
+        .map(|w| w.trim().to_string())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if let Some(bad) = windows.iter().find(|w| parse_time_window(w).is_none()) {
+        return Err(format!("bad time window: {bad}"));
+    }
+    let mut cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    cfg.bandwidth_background_bps = background_bps.filter(|n| *n > 0);
+    cfg.bandwidth_interactive_bps = interactive_bps.filter(|n| *n > 0);
+    cfg.background_windows = windows;
+    save_config_to_disk(&cfg)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn api_bandwidth_status() -> Result<BandwidthStatus, String> {
+    let windows = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?
+        .background_windows
+        .clone();
+    Ok(BandwidthStatus {
+        interactive: Transfer::Interactive.status(),
+        background: Transfer::Background.status(),
+        windows,
+        background_allowed: background_window_open(),
+    })
+}
+
+#[tauri::command]
+async fn api_config_set_offline_mode(mode: Option<String>) -> Result<(), String> {
+    let mode = mode.map(|m| m.trim().to_ascii_lowercase()).filter(|m| !m.is_empty());
+    if let Some(m) = &mode {
+        if !matches!(m.as_str(), "auto" | "on" | "off") {
+            return Err(format!("unknown offline mode: {m}"));
+        }
+    }
+    let mut cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    cfg.offline_mode = mode;
+    save_config_to_disk(&cfg)?;
+    NETWORK_DOWN_UNTIL.store(0, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+fn api_offline_status() -> Result<OfflineStatus, String> {
+    let mode = offline_mode();
+    let until = NETWORK_DOWN_UNTIL.load(Ordering::Relaxed);
+    let network_down = mode == "auto" && now_millis() < until;
+    Ok(OfflineStatus {
+        mode,
+        network_down,
+        retry_at: network_down.then_some(until),
+    })
+}
+
+// Every comic with downloads, and whether it can be read with no network at all.
+#[tauri::command]
+async fn api_offline_comics(app: tauri::AppHandle) -> Result<Vec<OfflineComicStatus>, String> {
+    let base_dir = resolve_read_cache_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut aids: Vec<String> = load_download_jobs()?.into_iter().map(|j| j.aid).collect();
+        aids.sort();
+        aids.dedup();
+        let mut out = Vec::with_capacity(aids.len());
+        for aid in aids {
+            let Some(source) = downloaded_source(&aid)? else {
+                continue;
+            };
+            let read_dir = page_cache_dir(&base_dir, "read", Some(&aid));
+            let mut status = OfflineComicStatus {
+                aid: aid.clone(),
+                title: source.title.clone(),
+                has_album_snapshot: load_offline_snapshot(&format!("album:{aid}"))?.is_some(),
+                chapters: source.chapters.len() as u64,
+                ..Default::default()
+            };
+            for chapter in &source.chapters {
+                let cached = chapter
+                    .pages
+                    .iter()
+                    .filter(|p| find_cached_image(&read_dir, &page_cache_key(&p.url, p.num)).is_some())
+                    .count() as u64;
+                status.total_pages += chapter.pages.len() as u64;
+                status.cached_pages += cached;
+                let snapshot = load_offline_snapshot(&format!("chapter:{}", chapter.chapter_id))?.is_some()
+                    && load_offline_snapshot(&format!("scramble:{}", chapter.chapter_id))?.is_some();
+                if chapter.resolved && snapshot && cached == chapter.pages.len() as u64 {
+                    status.complete_chapters += 1;
+                }
+            }
+            status.full = status.has_album_snapshot
+                && status.chapters > 0
+                && status.complete_chapters == status.chapters;
+            out.push(status);
+        }
+        Ok(out)
+    })
+    .await
+    .map_err(|e| format!("offline scan task failed: {e}"))?
+}
+
+#[tauri::command]
+async fn api_album(
+    id: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let key = format!("album:{id}");
+    if let Some(snapshot) = offline_snapshot_first(&key)? {
+        return Ok(snapshot);
+    }
+    match fetch_album(id, cookies).await {
+        Ok(v) => {
+            NETWORK_DOWN_UNTIL.store(0, Ordering::Relaxed);
+            Ok(v)
+        }
+        Err(e) => offline_fallback(&key, e),
+    }
+}
+
+#[tauri::command]
+async fn api_chapter(
+    id: String,
+    cookies: HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let key = format!("chapter:{id}");
+    if let Some(snapshot) = offline_snapshot_first(&key)? {
+        return Ok(snapshot);
+    }
+    match fetch_chapter(id, cookies).await {
+        Ok(v) => {
+            NETWORK_DOWN_UNTIL.store(0, Ordering::Relaxed);
+            Ok(v)
+        }
+        Err(e) => offline_fallback(&key, e),
+    }
+}
+
+// Scramble ids never change, so a stored one is used in every mode.
+#[tauri::command]
+async fn api_chapter_scramble_id(id: String) -> Result<i64, String> {
+    let key = format!("scramble:{id}");
+    if let Some(v) = load_offline_snapshot(&key)?.and_then(|v| v.as_i64()) {
+        return Ok(v);
+    }
+    if offline_mode() == "on" {
+        return Err(format!("offline: {key} not downloaded"));
+    }
+    fetch_chapter_scramble_id(id).await
+}
+
+#[tauri::command]
+async fn api_comic_page_count(
+    id: String,
+    chapter_id: Option<String>,
+    cookies: HashMap<String, String>,
+) -> Result<u64, String> {
+    let fetch_id = chapter_id.unwrap_or_else(|| id.clone());
+    let chapter = api_chapter(fetch_id, cookies).await?;
+    let page_count = chapter
+        .get("images")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.len() as u64)
+        .unwrap_or(0);
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let entry = ComicExtraEntry {
+        id,
+        page_count,
+        updated_at: now,
+    };
+    let tree = read_comic_extra_tree()?;
+    let val =
+        serde_json::to_vec(&entry).map_err(|e| format!("encode comic extra failed: {e}"))?;
+    tree.insert(entry.id.as_bytes(), val)
+        .map_err(|e| format!("write comic extra failed: {e}"))?;
+    let _ = tree.flush();
+
+    Ok(page_count)
+}
+
+#[tauri::command]
+async fn api_comic_extra_get(id: String) -> Result<Option<ComicExtraEntry>, String> {
+    let tree = read_comic_extra_tree()?;
+    let Some(bytes) = tree
+        .get(id.as_bytes())
+        .map_err(|e| format!("read comic extra failed: {e}"))?
+    else {
+        return Ok(None);
+    };
+    let entry: ComicExtraEntry =
+        serde_json::from_slice(&bytes).map_err(|e| format!("decode comic extra failed: {e}"))?;
+    Ok(Some(entry))
+}
+
+const SCRAMBLE_220980: i64 = 220_980;
+const SCRAMBLE_268850: i64 = 268_850;
+const SCRAMBLE_421926: i64 = 421_926; // 2023-02-08 changed image split algorithm.
+
+fn parse_scramble_id(text: &str) -> Option<i64> {
+    let needle = "var scramble_id = ";
+    let start = text.find(needle)? + needle.len();
+    let tail = &text[start..];
+    let mut end = 0usize;
+    for (i, ch) in tail.char_indices() {
+        if !ch.is_ascii_digit() {
+            break;
+        }
+        end = i + ch.len_utf8();
+    }
+    if end == 0 {
+        return None;
+    }
+    tail[..end].parse::<i64>().ok()
+}
+
+async fn fetch_chapter_scramble_id(id: String) -> Result<i64, String> {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
+
+    let token_secret = std::env::var("JM_APP_TOKEN_SECRET_2")
+        .unwrap_or_else(|_| JM_APP_TOKEN_SECRET_2_DEFAULT.into());
+    let token = md5_hex(&format!("{ts}{token_secret}"));
+    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+
+    let client = http_client()?;
+    let candidates = api_base_candidates();
+    let mut last_err = None;
+
+    for (pos, (idx, base)) in candidates.iter().enumerate() {
+        let url = format!(
+            "{}/chapter_view_template/?id={}&mode=vertical&page=0&app_img_shunt=NaN",
+            base.trim_end_matches('/'),
+            id
+        );
+
+        let resp = match client
+            .get(&url)
+            .header("tokenparam", tokenparam.clone())
+            .header("token", token.clone())
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (Linux; Android 7.1.2; DT1901A Build/N2G47O; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/86.0.4240.198 Mobile Safari/537.36",
+            )
+            .header("accept-encoding", "identity")
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = format!("request failed: {e}");
+                last_err = Some(msg.clone());
+                if should_retry_error(&e) && pos + 1 < candidates.len() {
+                    logl!("[tauri][chapter_scramble] retry base={} err={:?}", base, msg);
+                    continue;
+                }
+                return Err(msg);
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
+            let msg = format!("http status {status}");
+            last_err = Some(msg.clone());
+            logl!("[tauri][chapter_scramble] retry base={} status={}", base, status);
+            continue;
+        }
+
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| format!("failed reading response text: {e}"))?;
+
+        if !status.is_success() {
+            return Err(format!(
+                "http error: {}, body_preview={:?}",
+                status,
+                &text.chars().take(200).collect::<String>()
+            ));
+        }
+
+        if let Some(v) = parse_scramble_id(&text) {
+            JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+            store_offline_snapshot(&format!("scramble:{id}"), &serde_json::json!(v));
+            return Ok(v);
+        }
+
+        logl!(
+            "[tauri][chapter_scramble] failed parse scramble_id base={:?} body_preview={:?}",
+            base,
+            text.chars().take(300).collect::<String>()
+        );
+        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
+        return Ok(SCRAMBLE_220980);
+    }
+
+    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+}
+
+fn get_segmentation_num(eps_id: i64, scramble_id: i64, picture_name: &str) -> i64 {
+    if eps_id < scramble_id {
+        return 0;
+    }
+    if eps_id < SCRAMBLE_268850 {
+        return 10;
+    }
+
+    let digest = md5_hex(&format!("{eps_id}{picture_name}"));
+    let last = digest.as_bytes().last().copied().unwrap_or(b'0') as i64; // ord(hex[-1])
+
+    if eps_id > SCRAMBLE_421926 {
+        let mut num = last % 8;
+        num = num * 2 + 2;
+        num
+    } else {
+        let mut num = last % 10;
+        num = num * 2 + 2;
+        num
+    }
+}
+
+#[tauri::command]
+async fn api_segmentation_nums(
+    eps_id: String,
+    scramble_id: i64,
+    picture_names: Vec<String>,
+) -> Result<Vec<i64>, String> {
+    let eps = eps_id
+        .parse::<i64>()
+        .map_err(|_| "invalid eps_id".to_string())?;
+    Ok(picture_names
+        .iter()
+        .map(|name| get_segmentation_num(eps, scramble_id, name))
+        .collect())
+}
+
+// AVIF pages decode only with the default `avif` feature; without it, say so instead of
+// image's generic "format not supported".
+fn image_error_text(e: image::ImageError) -> String {
+    if !cfg!(feature = "avif") {
+        if let image::ImageError::Unsupported(u) = &e {
+            if u.format_hint() == image::error::ImageFormatHint::Exact(ImageFormat::Avif) {
+                return "AVIF support is not compiled in (build with the `avif` feature)".to_string();
+            }
+        }
+    }
+    e.to_string()
+}
+
+fn mime_from_format(fmt: ImageFormat) -> &'static str {
+    match fmt {
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Png => "image/png",
+        ImageFormat::WebP => "image/webp",
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::Avif => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
+
+fn ext_from_format(fmt: ImageFormat) -> &'static str {
+    match fmt {
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Png => "png",
+        ImageFormat::WebP => "webp",
+        ImageFormat::Gif => "gif",
+        ImageFormat::Avif => "avif",
+        _ => "bin",
+    }
+}
+
+// Every extension the page/cover caches may hold, checked in this order on lookup.
+const CACHED_IMAGE_EXTS: &[&str] = &["png", "jpg", "gif", "webp", "avif"];
+
+fn find_cached_image(dir: &std::path::Path, key: &str) -> Option<std::path::PathBuf> {
+    CACHED_IMAGE_EXTS
+        .iter()
+        .map(|ext| dir.join(format!("{key}.{ext}")))
+        .find(|p| p.exists())
+}
+
+// Write to a sibling temp file, fsync, then rename so readers never see a partial file.
+fn write_file_atomic(path: &std::path::Path, bytes: &[u8]) -> Result<(), String> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| format!("no parent dir for {:?}", path))?;
+    std::fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let seq = ATOMIC_WRITE_SEQ.fetch_add(1, Ordering::Relaxed);
+    let tmp = parent.join(format!(".{file_name}.{}.{seq}.tmp", std::process::id()));
+
+    let res = (|| -> std::io::Result<()> {
+        let mut f = std::fs::File::create(&tmp)?;
+        f.write_all(bytes)?;
+        f.sync_all()?;
+        drop(f);
+        std::fs::rename(&tmp, path)?;
+        #[cfg(unix)]
+        {
+            if let Ok(dir) = std::fs::File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = res {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("atomic write {:?} failed: {e}", path));
+    }
+    Ok(())
+}
+
+// Cheap pre-commit check: body length matches Content-Length and the header parses.
+fn check_image_header(bytes: &[u8], expected_len: Option<u64>) -> Result<ImageFormat, String> {
+    if let Some(expected) = expected_len {
+        if bytes.len() as u64 != expected {
+            return Err(format!(
+                "image truncated: got {}B, content-length {}B",
+                bytes.len(),
+                expected
+            ));
+        }
+    }
+    if bytes.is_empty() {
+        return Err("image body is empty".to_string());
+    }
+    let fmt = image::guess_format(bytes).map_err(|e| format!("unknown image format: {e}"))?;
+    image::ImageReader::with_format(Cursor::new(bytes), fmt)
+        .into_dimensions()
+        .map_err(|e| format!("invalid image header: {}", image_error_text(e)))?;
+    Ok(fmt)
+}
+
+// Full decode, used by the repair scan; animations are checked frame by frame.
+fn validate_image_bytes(bytes: &[u8]) -> Result<ImageFormat, String> {
+    let fmt = image::guess_format(bytes).map_err(|e| format!("unknown image format: {e}"))?;
+    if decode_animation_frames(bytes, fmt)?.is_none() {
+        image::load_from_memory_with_format(bytes, fmt)
+            .map_err(|e| format!("decode image failed: {}", image_error_text(e)))?;
+    }
+    Ok(fmt)
+}
+
+fn is_stale_temp_file(path: &std::path::Path, meta: &std::fs::Metadata) -> bool {
+    const STALE_SECS: u64 = 10 * 60;
+    let is_tmp = path.extension().and_then(|x| x.to_str()) == Some("tmp");
+    if !is_tmp {
+        return false;
+    }
+    meta.modified()
+        .ok()
+        .and_then(|m| m.elapsed().ok())
+        .map(|age| age.as_secs() >= STALE_SECS)
+        .unwrap_or(true)
+}
+
+fn repair_image_cache_dir(dir: &std::path::Path, result: &mut ReadCacheRepairResult) {
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(cur) = stack.pop() {
+        let entries = match std::fs::read_dir(&cur) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let p = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                stack.push(p);
+                continue;
+            }
+            if !meta.is_file() {
+                continue;
+            }
+            result.scanned_files += 1;
+            let broken = if is_stale_temp_file(&p, &meta) {
+                Some("stale temp file".to_string())
+            } else if p.extension().and_then(|x| x.to_str()) == Some("tmp") {
+                None
+            } else {
+                match std::fs::read(&p) {
+                    Ok(bytes) => validate_image_bytes(&bytes).err(),
+                    Err(e) => Some(format!("read failed: {e}")),
+                }
+            };
+            let Some(reason) = broken else {
+                continue;
+            };
+            match std::fs::remove_file(&p) {
+                Ok(()) => {
+                    logl!("[tauri][cache] repair removed {:?}: {}", p, reason);
+                    cache_index_remove(&p);
+                    result.removed_files += 1;
+                    result.removed_bytes += meta.len();
+                }
+                Err(e) => {
+                    logl!("[tauri][cache] repair remove {:?} failed: {}", p, e);
+                }
+            }
+        }
+    }
+}
+
+fn page_cache_key(url: &str, num: i64) -> String {
+    let num = if num <= 1 { 1 } else { num };
+    md5_hex(&format!("{url}|{num}"))
+}
+
+fn page_cache_dir(base: &std::path::Path, area: &str, aid: Option<&str>) -> std::path::PathBuf {
+    match aid {
+        Some(a) => base.join(area).join(sanitize_path_component(a)),
+        None => base.join(area).join("unknown"),
+    }
+}
+
+const THUMB_MAX_WIDTH: u32 = 240;
+const THUMB_JPEG_QUALITY: u8 = 80;
+
+fn make_thumbnail(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let img = image::load_from_memory(bytes).map_err(|e| format!("decode image failed: {}", image_error_text(e)))?;
+    let thumb = if img.width() > THUMB_MAX_WIDTH {
+        img.thumbnail(THUMB_MAX_WIDTH, u32::MAX)
+    } else {
+        img
+    };
+    let rgb = thumb.to_rgb8();
+    let mut out = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, THUMB_JPEG_QUALITY)
+        .encode_image(&rgb)
+        .map_err(|e| format!("encode thumbnail failed: {e}"))?;
+    Ok(out)
+}
+
+// Thumbnails mirror the page cache layout: `thumb/<aid>/<key>.jpg`.
+fn ensure_page_thumbnail(
+    base: &std::path::Path,
+    aid: Option<&str>,
+    key: &str,
+    source: &std::path::Path,
+) -> Result<std::path::PathBuf, String> {
+    let thumb_path = page_cache_dir(base, "thumb", aid).join(format!("{key}.jpg"));
+    if thumb_path.exists() {
+        return Ok(thumb_path);
+    }
+    let bytes = std::fs::read(source).map_err(|e| format!("read cached page failed: {e}"))?;
+    let out = make_thumbnail(&bytes)?;
+    ensure_cache_write_space(base, out.len() as u64)?;
+    write_file_atomic(&thumb_path, &out)?;
+    cache_index_record(&thumb_path, None);
+    Ok(thumb_path)
+}
+
+// Variant widths are bucketed so nearby display sizes share one cached file.
+const RESIZE_WIDTH_STEP: u32 = 64;
+const RESIZE_JPEG_QUALITY: u8 = 90;
+
+fn resize_target_width(orig_w: u32, max_width: Option<u32>, scale: Option<f64>) -> Option<u32> {
+    let mut target = orig_w;
+    if let Some(s) = scale {
+        if s.is_finite() && s > 0.0 && s < 1.0 {
+            target = target.min((orig_w as f64 * s).ceil() as u32);
+        }
+    }
+    if let Some(w) = max_width {
+        if w > 0 {
+            target = target.min(w);
+        }
+    }
+    let target = target.div_ceil(RESIZE_WIDTH_STEP) * RESIZE_WIDTH_STEP;
+    if target >= orig_w {
+        None
+    } else {
+        Some(target)
+    }
+}
+
+// Reads only the container headers. Every GIF counts, since telling a still one apart
+// means decoding it; WebP and PNG are checked for an animation chunk.
+fn is_multi_frame_image(path: &std::path::Path, fmt: ImageFormat) -> bool {
+    let open = || std::fs::File::open(path).map(std::io::BufReader::new);
+    match fmt {
+        ImageFormat::Gif => true,
+        ImageFormat::WebP => open()
+            .ok()
+            .and_then(|f| image::codecs::webp::WebPDecoder::new(f).ok())
+            .is_some_and(|d| d.has_animation()),
+        ImageFormat::Png => open()
+            .ok()
+            .and_then(|f| image::codecs::png::PngDecoder::new(f).ok())
+            .and_then(|d| d.is_apng().ok())
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// Variants sit next to the original as `<key>_w<width>.<ext>`.
+fn ensure_resized_variant(
+    source: &std::path::Path,
+    key: &str,
+    max_width: Option<u32>,
+    scale: Option<f64>,
+) -> Result<std::path::PathBuf, String> {
+    let fmt = ImageFormat::from_path(source).map_err(|e| format!("unknown image format: {e}"))?;
+    if is_multi_frame_image(source, fmt) {
+        // Animated pages are served as-is; resizing would keep only the first frame.
+        return Ok(source.to_path_buf());
+    }
+    let (orig_w, _) =
+        image::image_dimensions(source).map_err(|e| format!("read image size failed: {e}"))?;
+    let Some(width) = resize_target_width(orig_w, max_width, scale) else {
+        return Ok(source.to_path_buf());
+    };
+    let dir = source
+        .parent()
+        .ok_or_else(|| format!("no parent dir for {:?}", source))?;
+    let ext = if fmt == ImageFormat::Jpeg { "jpg" } else { "png" };
+    let out_path = dir.join(format!("{key}_w{width}.{ext}"));
+    if out_path.exists() {
+        cache_index_touch(&out_path);
+        return Ok(out_path);
+    }
+
+    let started = Instant::now();
+    let img = image::open(source).map_err(|e| format!("decode image failed: {}", image_error_text(e)))?;
+    let resized = img.resize(width, u32::MAX, image::imageops::FilterType::Lanczos3);
+    let mut out = Vec::new();
+    if ext == "jpg" {
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, RESIZE_JPEG_QUALITY)
+            .encode_image(&resized.to_rgb8())
+            .map_err(|e| format!("encode image failed: {e}"))?;
+    } else {
+        resized
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .map_err(|e| format!("encode image failed: {e}"))?;
+    }
+    // `<base>/read/<aid>/<file>`
+    if let Some(base) = source.ancestors().nth(3) {
+        ensure_cache_write_space(base, out.len() as u64)?;
+    }
+    write_file_atomic(&out_path, &out)?;
+    cache_index_record(&out_path, None);
+    logl!(
+        "[tauri][img] resize done {}px -> {}px out={}B cost_ms={}",
+        orig_w,
+        width,
+        out.len(),
+        started.elapsed().as_millis()
+    );
+    Ok(out_path)
+}
+
+// Falls back to the original when no resize is requested or resizing fails.
+async fn resolve_display_variant(
+    source: std::path::PathBuf,
+    key: String,
+    display: Option<ReadDisplaySize>,
+) -> std::path::PathBuf {
+    let Some(display) = display else {
+        return source;
+    };
+    if display.max_width.is_none() && display.scale.is_none() {
+        return source;
+    }
+    let fallback = source.clone();
+    let res = tauri::async_runtime::spawn_blocking(move || {
+        ensure_resized_variant(&source, &key, display.max_width, display.scale)
+    })
+    .await;
+    match res {
+        Ok(Ok(p)) => p,
+        Ok(Err(e)) => {
+            logl!("[tauri][img] resize failed src={:?} err={}", fallback, e);
+            fallback
+        }
+        Err(e) => {
+            logl!("[tauri][img] resize task failed src={:?} err={}", fallback, e);
+            fallback
+        }
+    }
+}
+
+fn page_name_from_url(url: &str) -> String {
+    let clean = url.split('#').next().unwrap_or(url);
+    let clean = clean.split('?').next().unwrap_or(clean);
+    clean.rsplit('/').next().unwrap_or("").to_string()
+}
+
+// Page URLs look like `<img base>/media/photos/<chapter id>/<image name>`.
+fn chapter_id_from_url(url: &str) -> Option<String> {
+    let clean = url.split('?').next().unwrap_or(url);
+    let mut parts = clean.rsplit('/');
+    let _name = parts.next()?;
+    let chapter = parts.next()?;
+    if parts.next()? != "photos" || chapter.is_empty() {
+        return None;
+    }
+    Some(chapter.to_string())
+}
+
+fn page_meta_key(chapter_id: &str, image_name: &str) -> String {
+    format!("{chapter_id}/{image_name}")
+}
+
+fn image_bytes_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+fn record_page_meta(
+    chapter_id: &str,
+    image_name: &str,
+    width: u32,
+    height: u32,
+    file_size: u64,
+    cache_file: Option<String>,
+) -> Result<(), String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    // Size probes don't know the cached file; keep whatever an earlier write recorded.
+    let cache_file =
+        cache_file.or_else(|| load_page_meta(chapter_id, image_name).and_then(|m| m.cache_file));
+    let entry = PageMetaEntry {
+        chapter_id: chapter_id.to_string(),
+        image_name: image_name.to_string(),
+        width,
+        height,
+        file_size,
+        cache_file,
+        updated_at: now,
+    };
+    let tree = read_page_meta_tree()?;
+    let val = serde_json::to_vec(&entry).map_err(|e| format!("encode page meta failed: {e}"))?;
+    tree.insert(page_meta_key(chapter_id, image_name).as_bytes(), val)
+        .map_err(|e| format!("write page meta failed: {e}"))?;
+    Ok(())
+}
 
-        let latest_entry = latest_tree
-            .get(aid.as_bytes())
-            .map_err(|e| format!("read latest failed: {e}"))?
-            .and_then(|bytes| serde_json::from_slice::<LatestChapterEntry>(&bytes).ok());
-        let Some(latest_entry) = latest_entry else {
-            continue;
-        };
-        let latest_id = latest_entry.latest_chapter_id.clone();
-        let latest_sort = latest_entry.latest_chapter_sort.clone();
+fn load_page_meta(chapter_id: &str, image_name: &str) -> Option<PageMetaEntry> {
+    let tree = read_page_meta_tree().ok()?;
+    let bytes = tree
+        .get(page_meta_key(chapter_id, image_name).as_bytes())
+        .ok()??;
+    serde_json::from_slice(&bytes).ok()
+}
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
+// Path of a cached file relative to the cache dir, always with `/` separators.
+fn cache_relative_path(base_dir: &std::path::Path, path: &std::path::Path) -> Option<String> {
+    let rel = path.strip_prefix(base_dir).ok()?;
+    let parts = rel
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
 
-        let prev_state = follow_tree
-            .get(aid.as_bytes())
-            .map_err(|e| format!("read follow state failed: {e}"))?
-            .and_then(|bytes| serde_json::from_slice::<FollowStateEntry>(&bytes).ok());
+// Records size info and location for a cached page unless the store already has both.
+fn remember_cached_page_meta(base_dir: &std::path::Path, url: &str, path: &std::path::Path) {
+    let Some(chapter_id) = chapter_id_from_url(url) else {
+        return;
+    };
+    let image_name = page_name_from_url(url);
+    let cache_file = cache_relative_path(base_dir, path);
+    if load_page_meta(&chapter_id, &image_name)
+        .is_some_and(|m| m.file_size > 0 && (cache_file.is_none() || m.cache_file == cache_file))
+    {
+        return;
+    }
+    let Ok(meta) = std::fs::metadata(path) else {
+        return;
+    };
+    let Ok((width, height)) = image::image_dimensions(path) else {
+        return;
+    };
+    if let Err(e) = record_page_meta(&chapter_id, &image_name, width, height, meta.len(), cache_file) {
+        logl!("[tauri][pagemeta] record failed chapter={} name={} err={}", chapter_id, image_name, e);
+    }
+}
 
-        if let Some(state) = prev_state {
-            if state.last_known_chapter_id != latest_id {
-                let update = FollowUpdateEntry {
-                    aid: aid.clone(),
-                    latest_chapter_id: latest_id.clone(),
-                    latest_chapter_sort: latest_sort.clone(),
-                    updated_at: now,
-                };
-                updates_tree
-                    .insert(
-                        aid.as_bytes(),
-                        serde_json::to_vec(&update)
-                            .map_err(|e| format!("encode update failed: {e}"))?,
-                    )
-                    .map_err(|e| format!("write update failed: {e}"))?;
-                let _ = updates_tree.flush();
-                logl!(
-                    "[tauri][follow] update found aid={} latest={}",
-                    aid,
-                    latest_id
-                );
-            }
-        }
+const PAGE_PROBE_BYTES: usize = 64 * 1024;
 
-        if progress_chapter_id == latest_id {
-            let state = FollowStateEntry {
-                aid: aid.clone(),
-                last_known_chapter_id: latest_id.clone(),
-                last_known_chapter_sort: latest_sort.clone(),
-                updated_at: now,
-            };
-            follow_tree
-                .insert(
-                    aid.as_bytes(),
-                    serde_json::to_vec(&state).map_err(|e| format!("encode follow failed: {e}"))?,
-                )
-                .map_err(|e| format!("write follow failed: {e}"))?;
-            let _ = follow_tree.flush();
-        } else {
-            let _ = follow_tree.remove(aid.as_bytes());
-            let _ = follow_tree.flush();
+// Reads just enough of the remote image to parse its header.
+async fn probe_remote_image_dimensions(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<(u32, u32), String> {
+    let mut resp = client
+        .get(url)
+        .header("accept-encoding", "identity")
+        .header(reqwest::header::RANGE, format!("bytes=0-{}", PAGE_PROBE_BYTES - 1))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("image http error: {status}"));
+    }
+    let mut buf: Vec<u8> = Vec::with_capacity(PAGE_PROBE_BYTES);
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("read image failed: {e}"))?
+    {
+        buf.extend_from_slice(&chunk);
+        if let Some(dims) = image_bytes_dimensions(&buf) {
+            return Ok(dims);
+        }
+        if buf.len() >= PAGE_PROBE_BYTES {
+            break;
         }
     }
+    image_bytes_dimensions(&buf).ok_or_else(|| "image header not found".to_string())
+}
 
-    Ok(())
+fn descramble_image_bytes(bytes: &[u8], num: i64) -> Result<(Vec<u8>, ImageFormat), String> {
+    descramble_image_bytes_with_cancel(bytes, num, None)
 }
 
-async fn fetch_api_domain_list() -> Result<Vec<String>, String> {
-    let client = http_client()?;
-    let mut last_err: Option<String> = None;
-    for url in JM_API_DOMAIN_SERVER_LIST {
-        logl!("[tauri][api] domain fetch start url={}", url);
-        let resp = match client.get(*url).send().await {
-            Ok(r) => r,
-            Err(e) => {
-                last_err = Some(format!("fetch failed: {e}"));
-                logl!("[tauri][api] domain fetch error url={} err={}", url, e);
-                continue;
-            }
-        };
-        if !resp.status().is_success() {
-            last_err = Some(format!("http status {} from {}", resp.status(), url));
-            logl!(
-                "[tauri][api] domain fetch status url={} status={}",
-                url,
-                resp.status()
-            );
-            continue;
-        }
-        let body = resp
-            .text()
-            .await
-            .map_err(|e| format!("read response body failed: {e}"))?;
-        let cleaned = strip_leading_non_ascii(&body);
-        let mut list = Vec::new();
-        match decode_domain_server_payload(cleaned) {
-            Ok(decoded) => {
-                list = parse_api_domain_payload(&decoded);
-                if list.is_empty() {
-                    let preview = decoded.chars().take(200).collect::<String>();
-                    logl!(
-                        "[tauri][api] domain decode payload empty: {}",
-                        preview
-                    );
-                }
-            }
-            Err(e) => {
-                let preview = cleaned.chars().take(200).collect::<String>();
-                logl!(
-                    "[tauri][api] domain decode failed: {} raw_preview={}",
-                    e,
-                    preview
-                );
-            }
+fn descramble_rgba(
+    rgba: &image::RgbaImage,
+    num: u32,
+    cancel: Option<&AtomicBool>,
+) -> Result<image::RgbaImage, String> {
+    let (w, h) = rgba.dimensions();
+    let rem = h % num;
+    let copy_height = h / num;
+
+    let mut blocks: Vec<(u32, u32)> = Vec::with_capacity(num as usize);
+    let mut total_h = 0u32;
+    for i in 0..num {
+        let mut end = copy_height * (i + 1);
+        if i == num - 1 {
+            end += rem;
         }
-        if list.is_empty() {
-            list = parse_api_domain_list(cleaned);
+        blocks.push((total_h, end));
+        total_h = end;
+    }
+
+    let src = rgba.as_raw();
+    let mut dst = vec![0u8; src.len()];
+    let row_bytes = (w * 4) as usize;
+
+    let mut y = 0u32;
+    for (start, end) in blocks.into_iter().rev() {
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err("cancelled".to_string());
         }
-        if !list.is_empty() {
-            logl!(
-                "[tauri][api] domain fetch success url={} count={}",
-                url,
-                list.len()
-            );
-            logl!("[tauri][api] domain fetch list: {}", list.join(", "));
-            return Ok(list);
+        let seg_h = end.saturating_sub(start);
+        for dy in 0..seg_h {
+            if dy % 64 == 0 && cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return Err("cancelled".to_string());
+            }
+            let src_y = start + dy;
+            let dst_y = y + dy;
+            let src_off = (src_y as usize) * row_bytes;
+            let dst_off = (dst_y as usize) * row_bytes;
+            dst[dst_off..dst_off + row_bytes].copy_from_slice(&src[src_off..src_off + row_bytes]);
         }
-        last_err = Some(format!("empty domain list from {}", url));
-        logl!(
-            "[tauri][api] domain fetch empty url={}",
-            url
-        );
+        y += seg_h;
     }
-    Err(last_err.unwrap_or_else(|| "api domain list unavailable".to_string()))
-}
 
-#[tauri::command]
-async fn api_api_domain_fetch() -> Result<Vec<String>, String> {
-    let list = fetch_api_domain_list().await?;
-    update_api_domain_cache(list.clone())?;
-    Ok(list)
+    image::RgbaImage::from_raw(w, h, dst).ok_or_else(|| "image buffer create failed".to_string())
 }
 
-fn api_error(status: reqwest::StatusCode, api_code: i64, msg: impl AsRef<str>) -> String {
-    format!(
-        "HTTP_STATUS={};API_CODE={};{}",
-        status.as_u16(),
-        api_code,
-        msg.as_ref()
-    )
+// Animated GIF/WebP: returns `None` for single-frame images so they take the still path.
+fn decode_animation_frames(bytes: &[u8], fmt: ImageFormat) -> Result<Option<Vec<image::Frame>>, String> {
+    use image::AnimationDecoder as _;
+
+    let frames = match fmt {
+        ImageFormat::Gif => image::codecs::gif::GifDecoder::new(Cursor::new(bytes))
+            .map_err(|e| format!("decode gif failed: {e}"))?
+            .into_frames()
+            .collect_frames()
+            .map_err(|e| format!("decode gif frames failed: {e}"))?,
+        ImageFormat::WebP => {
+            let decoder = image::codecs::webp::WebPDecoder::new(Cursor::new(bytes))
+                .map_err(|e| format!("decode webp failed: {e}"))?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder
+                .into_frames()
+                .collect_frames()
+                .map_err(|e| format!("decode webp frames failed: {e}"))?
+        }
+        _ => return Ok(None),
+    };
+    if frames.len() <= 1 {
+        return Ok(None);
+    }
+    Ok(Some(frames))
 }
 
-async fn api_get_encrypted(
-    path: &str,
-    page: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+fn descramble_animation(
+    frames: Vec<image::Frame>,
+    num: u32,
+    cancel: Option<&AtomicBool>,
+) -> Result<Vec<u8>, String> {
+    let mut out_frames = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let left = frame.left();
+        let top = frame.top();
+        let delay = frame.delay();
+        let buffer = descramble_rgba(frame.buffer(), num, cancel)?;
+        out_frames.push(image::Frame::from_parts(buffer, left, top, delay));
+    }
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+    if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+        return Err("cancelled".to_string());
+    }
+
+    // Animated WebP cannot be encoded by `image`, so all animations come out as GIF.
+    let mut out_bytes = Vec::new();
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(&mut out_bytes, 10);
+        encoder
+            .set_repeat(image::codecs::gif::Repeat::Infinite)
+            .map_err(|e| format!("encode gif failed: {e}"))?;
+        encoder
+            .encode_frames(out_frames)
+            .map_err(|e| format!("encode gif failed: {e}"))?;
+    }
+    Ok(out_bytes)
+}
+
+fn descramble_image_bytes_with_cancel(
+    bytes: &[u8],
+    num: i64,
+    cancel: Option<&AtomicBool>,
+) -> Result<(Vec<u8>, ImageFormat), String> {
+    let started = Instant::now();
+    if num <= 1 {
+        let fmt = image::guess_format(bytes).unwrap_or(ImageFormat::Jpeg);
+        return Ok((bytes.to_vec(), fmt));
+    }
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+    let fmt_in = image::guess_format(bytes).unwrap_or(ImageFormat::Jpeg);
+    let num_u32 = num as u32;
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let url = format!(
-            "{}/{}/?page={}",
-            base.trim_end_matches('/'),
-            path.trim_start_matches('/'),
-            page
+    if let Some(frames) = decode_animation_frames(bytes, fmt_in)? {
+        let frame_count = frames.len();
+        let out_bytes = descramble_animation(frames, num_u32, cancel)?;
+        logl!(
+            "[tauri][img] descramble done num={} fmt_in={:?} fmt_out=Gif frames={} in={}B out={}B cost_ms={}",
+            num,
+            fmt_in,
+            frame_count,
+            bytes.len(),
+            out_bytes.len(),
+            started.elapsed().as_millis()
         );
-        let resp = match client
-            .get(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-            )
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .header(reqwest::header::COOKIE, cookie_header(&cookies))
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][api] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
-            }
-        };
+        return Ok((out_bytes, ImageFormat::Gif));
+    }
 
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][api] retry base={} status={}", base, status);
-            continue;
-        }
+    let img = image::load_from_memory(bytes).map_err(|e| format!("decode image failed: {}", image_error_text(e)))?;
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
+    let rgba = img.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    if num_u32 <= 1 || h == 0 || w == 0 {
+        return Ok((bytes.to_vec(), fmt_in));
+    }
 
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(300)
-            .collect::<String>();
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][api] retry base={} body_hint", base);
-            continue;
-        }
+    let out_rgba = descramble_rgba(&rgba, num_u32, cancel)?;
 
-        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+    let fmt_out = if fmt_in == ImageFormat::Jpeg {
+        ImageFormat::Jpeg
+    } else {
+        ImageFormat::Png
+    };
 
-        if env.code != 200 {
-            let msg = if !env.error_msg.is_empty() {
-                env.error_msg
-            } else if !env.message.is_empty() {
-                env.message
-            } else {
-                format!("request failed, code={}, http_status={}", env.code, status)
-            };
-            logl!(
-                "[tauri][api] error path={} http_status={} api_code={} base={:?} msg={:?}",
-                path, status, env.code, base, msg
-            );
-            return Err(api_error(status, env.code, msg));
-        }
+    if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+        return Err("cancelled".to_string());
+    }
 
-        let encrypted = env
-            .data
-            .as_str()
-            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
-        let decrypted = decode_resp_data(encrypted, ts)?;
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        return serde_json::from_str(&decrypted)
-            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    let mut out_bytes = Vec::new();
+    if fmt_out == ImageFormat::Jpeg {
+        let rgb = image::DynamicImage::ImageRgba8(out_rgba).to_rgb8();
+        image::DynamicImage::ImageRgb8(rgb)
+            .write_to(&mut Cursor::new(&mut out_bytes), fmt_out)
+            .map_err(|e| format!("encode image failed: {e}"))?;
+    } else {
+        image::DynamicImage::ImageRgba8(out_rgba)
+            .write_to(&mut Cursor::new(&mut out_bytes), fmt_out)
+            .map_err(|e| format!("encode image failed: {e}"))?;
     }
 
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+    logl!(
+        "[tauri][img] descramble done num={} fmt_in={:?} fmt_out={:?} in={}B out={}B cost_ms={}",
+        num,
+        fmt_in,
+        fmt_out,
+        bytes.len(),
+        out_bytes.len(),
+        started.elapsed().as_millis()
+    );
+
+    Ok((out_bytes, fmt_out))
 }
 
-async fn api_get_encrypted_with_query(
-    path: &str,
-    query: Option<String>,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+fn sanitize_path_component(s: &str) -> String {
+    let filtered: String = s
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+        .collect();
+    if filtered.is_empty() {
+        "unknown".to_string()
+    } else {
+        filtered
+    }
+}
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+fn configured_cache_root() -> Option<std::path::PathBuf> {
+    config_state()
+        .lock()
+        .ok()
+        .and_then(|c| c.cache_root.clone())
+        .filter(|r| !r.trim().is_empty())
+        .map(std::path::PathBuf::from)
+}
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+// Where the cache lives without a user setting.
+fn default_read_cache_dir() -> std::path::PathBuf {
+    match std::env::var("JM_CACHE_DIR") {
+        Ok(dir) => std::path::PathBuf::from(dir).join("jmcomic-cache"),
+        Err(_) => app_dirs().cache.clone(),
+    }
+}
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let mut url = format!(
-            "{}/{}",
-            base.trim_end_matches('/'),
-            path.trim_start_matches('/')
-        );
+fn resolve_read_cache_dir<R: tauri::Runtime>(_app: &tauri::AppHandle<R>) -> Result<std::path::PathBuf, String> {
+    // A root picked in settings wins over the platform default and the env override.
+    if let Some(root) = configured_cache_root() {
+        let p = root.join("jmcomic-cache");
+        std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
+        return Ok(p);
+    }
+    // Env override for mobile sandboxes and debugging/testing.
+    if let Ok(dir) = std::env::var("JM_CACHE_DIR") {
+        let base = std::path::PathBuf::from(dir);
+        let p = base.join("jmcomic-cache");
+        std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
+        return Ok(p);
+    }
 
-        if let Some(q) = query.clone() {
-            if !q.is_empty() {
-                if !url.ends_with('/') {
-                    url.push('/');
-                }
-                url.push('?');
-                url.push_str(&q);
-            }
-        }
+    let p = app_dirs().cache.clone();
+    std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
+    Ok(p)
+}
 
-        let resp = match client
-            .get(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-            )
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .header(reqwest::header::COOKIE, cookie_header(&cookies))
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][api] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
-            }
-        };
+fn cover_semaphore() -> &'static tokio::sync::Semaphore {
+    COVER_SEMAPHORE.get_or_init(|| tokio::sync::Semaphore::new(4))
+}
 
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][api] retry base={} status={}", base, status);
-            continue;
-        }
+fn resolve_data_dir() -> Result<std::path::PathBuf, String> {
+    if let Ok(dir) = std::env::var("JM_DATA_DIR") {
+        let p = std::path::PathBuf::from(dir);
+        std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
+        return Ok(p);
+    }
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
+    let p = app_dirs().data.clone();
+    std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
+    Ok(p)
+}
 
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(300)
-            .collect::<String>();
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][api] retry base={} body_hint", base);
-            continue;
+fn mime_from_path(path: &std::path::Path) -> &'static str {
+    match path.extension().and_then(|x| x.to_str()).unwrap_or("").to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "avif" => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
+
+const DEFAULT_MEMORY_CACHE_MAX_BYTES: u64 = 96 * 1024 * 1024;
+
+impl PageMemoryCache {
+    fn get(&mut self, key: &str, validator: FileValidator) -> Option<Arc<Vec<u8>>> {
+        let Some(entry) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        if entry.validator != validator {
+            // The file changed or vanished behind our back.
+            self.misses += 1;
+            self.remove(key);
+            return None;
+        }
+        self.hits += 1;
+        self.order.remove(&entry.tick);
+        self.tick += 1;
+        entry.tick = self.tick;
+        self.order.insert(self.tick, key.to_string());
+        Some(entry.bytes.clone())
+    }
+
+    fn insert(&mut self, key: String, bytes: Arc<Vec<u8>>, validator: FileValidator) {
+        let len = bytes.len() as u64;
+        // One page must not push out most of the cache.
+        if len == 0 || len > self.budget_bytes / 8 {
+            return;
         }
+        self.remove(&key);
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            PageMemoryEntry {
+                bytes,
+                validator,
+                tick: self.tick,
+            },
+        );
+        self.used_bytes += len;
+        self.shrink_to(self.budget_bytes);
+    }
 
-        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+    fn remove(&mut self, key: &str) {
+        if let Some(old) = self.entries.remove(key) {
+            self.order.remove(&old.tick);
+            self.used_bytes = self.used_bytes.saturating_sub(old.bytes.len() as u64);
+        }
+    }
 
-        if env.code != 200 {
-            let msg = if !env.error_msg.is_empty() {
-                env.error_msg
-            } else if !env.message.is_empty() {
-                env.message
-            } else {
-                format!("request failed, code={}, http_status={}", env.code, status)
+    fn shrink_to(&mut self, max_bytes: u64) {
+        while self.used_bytes > max_bytes {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
             };
-            logl!(
-                "[tauri][api] error path={} http_status={} api_code={} base={:?} msg={:?}",
-                path, status, env.code, base, msg
-            );
-            return Err(api_error(status, env.code, msg));
+            if let Some(old) = self.entries.remove(&key) {
+                self.used_bytes = self.used_bytes.saturating_sub(old.bytes.len() as u64);
+                self.evictions += 1;
+            }
         }
+    }
 
-        let encrypted = env
-            .data
-            .as_str()
-            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
-        let decrypted = decode_resp_data(encrypted, ts)?;
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        return serde_json::from_str(&decrypted)
-            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    fn stats(&self) -> MemoryCacheStats {
+        MemoryCacheStats {
+            budget_bytes: self.budget_bytes,
+            used_bytes: self.used_bytes,
+            entries: self.entries.len() as u64,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
     }
+}
 
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+fn page_memory_cache() -> &'static Mutex<PageMemoryCache> {
+    PAGE_MEMORY_CACHE.get_or_init(|| {
+        let budget_bytes = config_state()
+            .lock()
+            .ok()
+            .and_then(|c| c.memory_cache_max_bytes)
+            .unwrap_or(DEFAULT_MEMORY_CACHE_MAX_BYTES);
+        Mutex::new(PageMemoryCache {
+            budget_bytes,
+            ..Default::default()
+        })
+    })
 }
 
-async fn api_post_encrypted(
-    path: &str,
-    form: &[(&str, &str)],
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+fn file_validator(meta: &std::fs::Metadata) -> FileValidator {
+    Some((meta.len(), meta.modified().ok()))
+}
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+fn page_memory_get(key: &str, validator: FileValidator) -> Option<Arc<Vec<u8>>> {
+    page_memory_cache().lock().ok()?.get(key, validator)
+}
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+fn page_memory_insert(key: String, bytes: Arc<Vec<u8>>, validator: FileValidator) {
+    if let Ok(mut cache) = page_memory_cache().lock() {
+        cache.insert(key, bytes, validator);
+    }
+}
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let url = format!(
-            "{}/{}",
-            base.trim_end_matches('/'),
-            path.trim_start_matches('/')
+// Keeps freshly written page bytes in memory so the first display skips the disk.
+fn page_memory_insert_file(path: &std::path::Path, bytes: Vec<u8>) {
+    if let Ok(meta) = std::fs::metadata(path) {
+        page_memory_insert(
+            path.to_string_lossy().into_owned(),
+            Arc::new(bytes),
+            file_validator(&meta),
         );
+    }
+}
 
-        let resp = match client
-            .post(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-            )
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .header(reqwest::header::COOKIE, cookie_header(&cookies))
-            .form(form)
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][api] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
-            }
-        };
+#[tauri::command]
+fn api_memory_cache_stats() -> Result<MemoryCacheStats, String> {
+    Ok(page_memory_cache()
+        .lock()
+        .map_err(|_| "memory cache lock poisoned".to_string())?
+        .stats())
+}
 
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][api] retry base={} status={}", base, status);
-            continue;
-        }
+#[tauri::command]
+fn api_memory_cache_clear() -> Result<MemoryCacheStats, String> {
+    let mut cache = page_memory_cache()
+        .lock()
+        .map_err(|_| "memory cache lock poisoned".to_string())?;
+    cache.shrink_to(0);
+    Ok(cache.stats())
+}
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
+fn jmcache_plain(status: tauri::http::StatusCode, body: &[u8]) -> tauri::http::Response<Vec<u8>> {
+    tauri::http::Response::builder()
+        .status(status)
+        .body(body.to_vec())
+        .unwrap_or_else(|_| tauri::http::Response::new(Vec::new()))
+}
 
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(300)
-            .collect::<String>();
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][api] retry base={} body_hint", base);
-            continue;
+fn parse_query_params(query: &str) -> HashMap<String, String> {
+    let mut out = HashMap::new();
+    for pair in query.split('&') {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        let v = v.replace('+', " ");
+        if let (Ok(k), Ok(v)) = (urlencoding::decode(k), urlencoding::decode(&v)) {
+            out.insert(k.into_owned(), v.into_owned());
         }
+    }
+    out
+}
 
-        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+// Cached files are keyed by source URL, so their content never changes in place.
+const JMCACHE_CACHE_CONTROL: &str = "private, max-age=604800";
+// Largest slice returned for one Range request; players ask again for the rest.
+const JMCACHE_MAX_RANGE_BYTES: u64 = 4 * 1024 * 1024;
+
+// UTC `(year, month, day, hour, minute, second)` for a unix timestamp.
+fn civil_from_unix(secs: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Days since epoch to civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (
+        year,
+        month as u32,
+        day as u32,
+        (rem / 3_600) as u32,
+        (rem % 3_600 / 60) as u32,
+        (rem % 60) as u32,
+    )
+}
 
-        if env.code != 200 {
-            let msg = if !env.error_msg.is_empty() {
-                env.error_msg
-            } else if !env.message.is_empty() {
-                env.message
-            } else {
-                format!("request failed, code={}, http_status={}", env.code, status)
-            };
-            logl!(
-                "[tauri][api] error path={} http_status={} api_code={} base={:?} msg={:?}",
-                path, status, env.code, base, msg
-            );
-            return Err(api_error(status, env.code, msg));
-        }
+fn unix_secs(t: std::time::SystemTime) -> u64 {
+    t.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
 
-        let encrypted = env
-            .data
-            .as_str()
-            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
-        let decrypted = decode_resp_data(encrypted, ts)?;
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        return serde_json::from_str(&decrypted)
-            .map_err(|e| format!("parse decrypted json failed: {e}"));
-    }
+// IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`) for Last-Modified.
+fn http_date(t: std::time::SystemTime) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = unix_secs(t);
+    let (year, month, day, hour, minute, second) = civil_from_unix(secs);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(secs / 86_400 % 7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        hour,
+        minute,
+        second
+    )
+}
 
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+// `2024-01-31T08:00:00Z`, as used by `dcterms:modified`.
+fn iso_date_time(t: std::time::SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_unix(unix_secs(t));
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
 }
 
-#[tauri::command]
-async fn login(username: String, password: String) -> Result<LoginResult, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+fn jmcache_etag(len: u64, modified: Option<std::time::SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("\"{len:x}-{nanos:x}\"")
+}
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+// Single `bytes=` range as inclusive offsets. `None` means serve the whole file
+// (absent, malformed or multi-range header), `Some(Err)` means 416.
+fn parse_byte_range(header: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(()));
+        }
+        (len.saturating_sub(suffix), len - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let last = len.saturating_sub(1);
+        let end = if end.is_empty() {
+            last
+        } else {
+            end.parse::<u64>().ok()?.min(last)
+        };
+        if start >= len || start > end {
+            return Some(Err(()));
+        }
+        (start, end)
+    };
+    Some(Ok(range))
+}
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+// Serves a file from the cache with HEAD, conditional and Range support. Page-sized
+// files go through the in-memory cache. Tauri protocol bodies cannot stream, so a plain
+// GET of a large file is still read whole; Range requests are capped at
+// `JMCACHE_MAX_RANGE_BYTES` so media elements fetch big files in bounded chunks.
+fn jmcache_serve_file(
+    request: &tauri::http::Request<Vec<u8>>,
+    path: &std::path::Path,
+) -> tauri::http::Response<Vec<u8>> {
+    use std::io::{Read, Seek, SeekFrom};
+    use tauri::http::{header, Method, Response, StatusCode};
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let url = format!("{}/login", base.trim_end_matches('/'));
+    let mut file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return jmcache_plain(StatusCode::NOT_FOUND, b"not found"),
+    };
+    let meta = match file.metadata() {
+        Ok(m) if m.is_file() => m,
+        _ => return jmcache_plain(StatusCode::NOT_FOUND, b"not found"),
+    };
+    let len = meta.len();
+    let modified = meta.modified().ok();
+    let etag = jmcache_etag(len, modified);
+    let last_modified = modified.map(http_date);
+
+    let mime = match mime_from_path(path) {
+        "application/octet-stream" => {
+            let mut head = [0u8; 32];
+            let n = file.read(&mut head).unwrap_or(0);
+            let _ = file.seek(SeekFrom::Start(0));
+            image::guess_format(&head[..n])
+                .map(mime_from_format)
+                .unwrap_or("application/octet-stream")
+        }
+        m => m,
+    };
 
-        let resp = match client
-            .post(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .form(&[("username", username.as_str()), ("password", password.as_str())])
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][login] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
+    let headers = request.headers();
+    let header_str = |name: header::HeaderName| headers.get(name).and_then(|v| v.to_str().ok());
+    let not_modified = match header_str(header::IF_NONE_MATCH) {
+        Some(tags) => tags
+            .split(',')
+            .map(|t| t.trim())
+            .any(|t| t == "*" || t.trim_start_matches("W/") == etag),
+        None => last_modified
+            .as_deref()
+            .is_some_and(|lm| header_str(header::IF_MODIFIED_SINCE) == Some(lm)),
+    };
+
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, JMCACHE_CACHE_CONTROL)
+        .header(header::ETAG, etag.as_str());
+    if let Some(lm) = &last_modified {
+        builder = builder.header(header::LAST_MODIFIED, lm.as_str());
+    }
+    if not_modified {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .unwrap_or_else(|_| Response::new(Vec::new()));
+    }
+
+    // A stale If-Range validator means the client's partial copy is outdated: send everything.
+    let range = header_str(header::RANGE)
+        .filter(|_| {
+            header_str(header::IF_RANGE)
+                .is_none_or(|v| v == etag || Some(v) == last_modified.as_deref())
+        })
+        .and_then(|h| parse_byte_range(h, len));
+    let (status, start, count) = match range {
+        Some(Err(())) => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{len}"))
+                .body(Vec::new())
+                .unwrap_or_else(|_| Response::new(Vec::new()));
+        }
+        Some(Ok((start, end))) => {
+            let end = end.min(start + JMCACHE_MAX_RANGE_BYTES - 1);
+            builder = builder.header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{len}"));
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+        None => (StatusCode::OK, 0, len),
+    };
+    builder = builder
+        .status(status)
+        .header(header::CONTENT_LENGTH, count);
+
+    if request.method() == Method::HEAD {
+        return builder
+            .body(Vec::new())
+            .unwrap_or_else(|_| Response::new(Vec::new()));
+    }
+
+    let mem_key = path.to_string_lossy().into_owned();
+    let validator = file_validator(&meta);
+    let cached = page_memory_get(&mem_key, validator).or_else(|| {
+        let limit = page_memory_cache().lock().ok()?.budget_bytes / 8;
+        if len > limit {
+            return None;
+        }
+        let mut all = Vec::with_capacity(len as usize);
+        file.read_to_end(&mut all).ok()?;
+        let all = Arc::new(all);
+        page_memory_insert(mem_key.clone(), all.clone(), validator);
+        Some(all)
+    });
+    let body = match cached.filter(|b| b.len() as u64 == len) {
+        Some(all) => all[start as usize..(start + count) as usize].to_vec(),
+        None => {
+            let mut body = Vec::with_capacity(count as usize);
+            if let Err(e) = file
+                .seek(SeekFrom::Start(start))
+                .and_then(|_| file.take(count).read_to_end(&mut body))
+            {
+                logl!("[tauri][jmcache] read failed path={:?} err={}", path, e);
+                return jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"read failed");
             }
-        };
+            body
+        }
+    };
+    builder
+        .body(body)
+        .unwrap_or_else(|_| Response::new(Vec::new()))
+}
 
-        let status = resp.status();
-        let headers = resp.headers().clone();
+fn jmcache_protocol<R: tauri::Runtime>(
+    ctx: tauri::UriSchemeContext<'_, R>,
+    request: tauri::http::Request<Vec<u8>>,
+    responder: tauri::UriSchemeResponder,
+) {
+    use tauri::http::{Method, StatusCode};
+
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn(async move {
+        if request.method() != Method::GET && request.method() != Method::HEAD {
+            responder.respond(jmcache_plain(StatusCode::METHOD_NOT_ALLOWED, b"method not allowed"));
+            return;
+        }
+        let route = request.uri().path().trim_start_matches('/').to_string();
+        if route == "image" {
+            responder.respond(jmcache_image_response(&app, request).await);
+            return;
+        }
+        // The other routes read files, sled and archives, so keep them off the async workers.
+        let res = tauri::async_runtime::spawn_blocking(move || {
+            if let Some(rest) = route.strip_prefix("page/") {
+                jmcache_page_response(&app, &request, "read", rest)
+            } else if let Some(rest) = route.strip_prefix("thumb/") {
+                jmcache_page_response(&app, &request, "thumb", rest)
+            } else if let Some(rest) = route.strip_prefix("local/") {
+                jmcache_local_response(&app, &request, rest)
+            } else {
+                jmcache_file_response(&app, &request)
+            }
+        })
+        .await;
+        responder.respond(res.unwrap_or_else(|e| {
+            logl!("[tauri][jmcache] route task failed: {e}");
+            jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"internal error")
+        }));
+    });
+}
 
-        let mut cookies = HashMap::new();
-        for c in resp.cookies() {
-            cookies.insert(c.name().to_string(), c.value().to_string());
+// `jmcache://localhost/image?url=..&num=..&aid=..[&background=1]`: descrambled page bytes with their
+// Content-Type. `background=1` marks reader prefetch, which counts against the background cap.
+async fn jmcache_image_response<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    use tauri::http::StatusCode;
+
+    let params = parse_query_params(request.uri().query().unwrap_or(""));
+    let Some(url) = params.get("url").filter(|u| !u.trim().is_empty()) else {
+        return jmcache_plain(StatusCode::BAD_REQUEST, b"missing url");
+    };
+    let num = params
+        .get("num")
+        .and_then(|n| n.parse::<i64>().ok())
+        .unwrap_or(1);
+    let aid = params.get("aid").map(|a| a.as_str()).filter(|a| !a.is_empty());
+    let background = params.get("background").map(|v| v == "1" || v == "true");
+
+    let base = match resolve_read_cache_dir(app) {
+        Ok(p) => p,
+        Err(e) => {
+            logl!("[tauri][jmcache] resolve cache dir failed: {e}");
+            return jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"cache dir error");
         }
+    };
+    let class = Transfer::from_background(background);
+    let path = match ensure_descrambled_page(&base, url, num, aid, None, None, class).await {
+        Ok(p) => p,
+        Err(e) => {
+            logl!("[tauri][jmcache] image failed url={:?} err={}", url, e);
+            return jmcache_plain(StatusCode::BAD_GATEWAY, e.as_bytes());
+        }
+    };
+    tauri::async_runtime::spawn_blocking(move || jmcache_serve_file(&request, &path))
+        .await
+        .unwrap_or_else(|e| {
+            logl!("[tauri][jmcache] serve task failed: {e}");
+            jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"internal error")
+        })
+}
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
-
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(500)
-            .collect::<String>();
+// `jmcache://localhost/page/<chapter id>/<image name>` and `.../thumb/<chapter id>/<image name>`:
+// resolves an already cached page through the page meta store, no on-disk path needed.
+fn jmcache_page_response<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: &tauri::http::Request<Vec<u8>>,
+    area: &str,
+    rest: &str,
+) -> tauri::http::Response<Vec<u8>> {
+    use tauri::http::StatusCode;
 
-        logl!(
-            "[tauri][login] status={} url={} content-type={:?} content-encoding={:?} body_len={} body_preview={:?}",
-            status,
-            url,
-            headers.get(reqwest::header::CONTENT_TYPE),
-            headers.get(reqwest::header::CONTENT_ENCODING),
-            body_bytes.len(),
-            body_preview
-        );
+    let Some((chapter_id, image_name)) = rest.split_once('/') else {
+        return jmcache_plain(StatusCode::BAD_REQUEST, b"expected <chapter id>/<image name>");
+    };
+    let (Ok(chapter_id), Ok(image_name)) =
+        (urlencoding::decode(chapter_id), urlencoding::decode(image_name))
+    else {
+        return jmcache_plain(StatusCode::BAD_REQUEST, b"bad path encoding");
+    };
+    let Some(cache_file) =
+        load_page_meta(&chapter_id, &image_name).and_then(|m| m.cache_file)
+    else {
+        return jmcache_plain(StatusCode::NOT_FOUND, b"page not cached");
+    };
+    // Stored as `read/<aid dir>/<file>`; anything else is not ours to serve.
+    let parts: Vec<&str> = cache_file.split('/').collect();
+    let [stored_area, aid_dir, file_name] = parts[..] else {
+        return jmcache_plain(StatusCode::NOT_FOUND, b"page not cached");
+    };
+    if stored_area != "read"
+        || [aid_dir, file_name]
+            .iter()
+            .any(|p| p.is_empty() || *p == "." || *p == ".." || p.contains('\\'))
+    {
+        return jmcache_plain(StatusCode::NOT_FOUND, b"page not cached");
+    }
 
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][login] retry base={} status={}", base, status);
-            continue;
+    let base = match resolve_read_cache_dir(app) {
+        Ok(p) => p,
+        Err(e) => {
+            logl!("[tauri][jmcache] resolve cache dir failed: {e}");
+            return jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"cache dir error");
         }
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][login] retry base={} body_hint", base);
-            continue;
+    };
+    let source = base.join("read").join(aid_dir).join(file_name);
+    if area != "thumb" {
+        cache_index_touch(&source);
+        return jmcache_serve_file(request, &source);
+    }
+    let key = file_name.rsplit_once('.').map(|(k, _)| k).unwrap_or(file_name);
+    match ensure_page_thumbnail(&base, Some(aid_dir), key, &source) {
+        Ok(thumb) => jmcache_serve_file(request, &thumb),
+        Err(e) => {
+            logl!("[tauri][jmcache] thumb failed src={:?} err={}", source, e);
+            jmcache_plain(StatusCode::NOT_FOUND, b"not found")
         }
+    }
+}
 
-        let body: ApiLoginResponse = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+// `jmcache://localhost/local/<id>/<page index>`: folder pages are served from the source,
+// archive pages are extracted once into `<cache>/local/<id>/`.
+fn jmcache_local_response<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: &tauri::http::Request<Vec<u8>>,
+    rest: &str,
+) -> tauri::http::Response<Vec<u8>> {
+    use std::io::Read;
+    use tauri::http::StatusCode;
 
-        if body.code != 200 {
-            let msg = if !body.error_msg.is_empty() {
-                body.error_msg
-            } else if !body.message.is_empty() {
-                body.message
-            } else {
-                format!("login failed, code={}", body.code)
-            };
-            return Err(msg);
+    let Some((id, index)) = rest.split_once('/') else {
+        return jmcache_plain(StatusCode::BAD_REQUEST, b"expected <id>/<page index>");
+    };
+    let Ok(index) = index.parse::<usize>() else {
+        return jmcache_plain(StatusCode::BAD_REQUEST, b"bad page index");
+    };
+    let comic = match load_local_comic(id) {
+        Ok(Some(c)) => c,
+        Ok(None) => return jmcache_plain(StatusCode::NOT_FOUND, b"unknown local comic"),
+        Err(e) => {
+            logl!("[tauri][jmcache] load local comic failed id={} err={}", id, e);
+            return jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"local comic error");
         }
+    };
+    let Some(entry) = comic.pages.get(index) else {
+        return jmcache_plain(StatusCode::NOT_FOUND, b"page out of range");
+    };
+    let source = std::path::PathBuf::from(&comic.source);
+    if comic.kind == "folder" {
+        return jmcache_serve_file(request, &source.join(entry));
+    }
 
-        let data_b64 = body
-            .data
-            .ok_or_else(|| "missing response data".to_string())?;
-        let decrypted = decode_resp_data(&data_b64, ts)?;
-        let user: ApiUserInfo =
-            serde_json::from_str(&decrypted).map_err(|e| format!("parse user data failed: {e}"))?;
-
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        if let Ok(mut cfg) = config_state().lock() {
-            cfg.session_cookies = cookies.clone();
-            if let Err(e) = save_config_to_disk(&cfg) {
-                logl!("[tauri][login] save cookies failed: {e}");
+    let base = match resolve_read_cache_dir(app) {
+        Ok(p) => p,
+        Err(e) => {
+            logl!("[tauri][jmcache] resolve cache dir failed: {e}");
+            return jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"cache dir error");
+        }
+    };
+    let ext = std::path::Path::new(entry)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_else(|| "jpg".to_string());
+    let dir = local_extract_dir(&base, &comic.id);
+    let out = dir.join(format!("{index:05}.{ext}"));
+    if out.exists() {
+        // The mtime doubles as last access for the extraction budget.
+        if let Ok(f) = std::fs::File::options().write(true).open(&out) {
+            let _ = f.set_modified(std::time::SystemTime::now());
+        }
+    } else {
+        let extracted = (|| {
+            let file = std::fs::File::open(&source).map_err(|e| format!("open archive failed: {e}"))?;
+            let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("read archive failed: {e}"))?;
+            let mut item = zip.by_name(entry).map_err(|e| format!("archive entry missing: {e}"))?;
+            let mut bytes = Vec::with_capacity(item.size() as usize);
+            item.read_to_end(&mut bytes)
+                .map_err(|e| format!("read archive entry failed: {e}"))?;
+            std::fs::create_dir_all(&dir).map_err(|e| format!("mkdir failed: {e}"))?;
+            ensure_cache_write_space(&base, bytes.len() as u64)?;
+            write_file_atomic(&out, &bytes)?;
+            Ok::<u64, String>(bytes.len() as u64)
+        })();
+        match extracted {
+            Ok(len) => enforce_local_extract_budget(&base, len),
+            Err(e) => {
+                logl!("[tauri][jmcache] extract failed id={} page={} err={}", comic.id, index, e);
+                return jmcache_plain(StatusCode::NOT_FOUND, b"page unavailable");
             }
         }
-        return Ok(LoginResult { user, cookies });
     }
-
-    Err(last_err.unwrap_or_else(|| "login failed".to_string()))
+    jmcache_serve_file(request, &out)
 }
 
-#[tauri::command]
-async fn api_latest(
-    page: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    api_get_encrypted("/latest", page, cookies).await
+fn local_extract_dir(base: &std::path::Path, id: &str) -> std::path::PathBuf {
+    base.join("local").join(sanitize_path_component(id))
 }
 
-#[tauri::command]
-async fn api_promote(
-    page: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    api_get_encrypted("/promote", page, cookies).await
+// Extracted archive pages are a disposable copy of the source, so `local/` is kept under
+// its own budget by least recent access (file mtime) instead of joining the page index.
+fn enforce_local_extract_budget(base: &std::path::Path, added: u64) {
+    let since = LOCAL_EXTRACTED_SINCE_CHECK.fetch_add(added, Ordering::Relaxed) + added;
+    if since < LOCAL_EXTRACT_CHECK_BYTES || LOCAL_EVICTION_RUNNING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    LOCAL_EXTRACTED_SINCE_CHECK.store(0, Ordering::Relaxed);
+    let root = base.join("local");
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut files = Vec::new();
+        let mut total = 0u64;
+        let mut stack = vec![root];
+        while let Some(dir) = stack.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if meta.is_dir() {
+                    stack.push(entry.path());
+                } else if meta.is_file() {
+                    total += meta.len();
+                    let mtime = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+                    files.push((mtime, meta.len(), entry.path()));
+                }
+            }
+        }
+        if total > LOCAL_EXTRACT_MAX_BYTES {
+            files.sort_by_key(|(mtime, _, _)| *mtime);
+            let target = LOCAL_EXTRACT_MAX_BYTES / 10 * 9;
+            let (mut removed, mut freed) = (0u64, 0u64);
+            for (_, len, path) in files {
+                if total <= target {
+                    break;
+                }
+                if std::fs::remove_file(&path).is_ok() {
+                    total -= len;
+                    freed += len;
+                    removed += 1;
+                }
+            }
+            logl!("[tauri][local] extract budget evicted files={} bytes={}", removed, freed);
+        }
+        LOCAL_EVICTION_RUNNING.store(false, Ordering::Release);
+    });
 }
 
-#[tauri::command]
-async fn api_history(page: String, cookies: HashMap<String, String>) -> Result<serde_json::Value, String> {
-    api_get_encrypted("/watch_list", page, cookies).await
-}
+fn jmcache_file_response<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: &tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    use tauri::http::StatusCode;
 
-#[tauri::command]
-async fn api_daily(user_id: String, cookies: HashMap<String, String>) -> Result<serde_json::Value, String> {
-    let query = format!("user_id={}", user_id);
-    api_get_encrypted_with_query("/daily", Some(query), cookies).await
-}
+    let uri = request.uri().to_string();
+    let path_part = uri
+        .split("://")
+        .nth(1)
+        .and_then(|s| s.splitn(2, '/').nth(1))
+        .unwrap_or("");
+    let path_part = path_part.split('?').next().unwrap_or(path_part);
+    let path_part = path_part.strip_prefix('/').unwrap_or(path_part);
 
-#[tauri::command]
-async fn api_daily_check(
-    user_id: String,
-    daily_id: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    api_post_encrypted(
-        "/daily_chk",
-        &[("user_id", user_id.as_str()), ("daily_id", daily_id.as_str())],
-        cookies,
-    )
-    .await
+    let decoded = match urlencoding::decode(path_part) {
+        Ok(s) => s.into_owned(),
+        Err(_) => return jmcache_plain(StatusCode::BAD_REQUEST, b"bad path encoding"),
+    };
+
+    let requested = std::path::PathBuf::from(decoded);
+    let base = match resolve_read_cache_dir(app) {
+        Ok(p) => p,
+        Err(e) => {
+            logl!("[tauri][jmcache] resolve cache dir failed: {e}");
+            return jmcache_plain(StatusCode::INTERNAL_SERVER_ERROR, b"cache dir error");
+        }
+    };
+
+    let (requested_canon, base_canon) = match (std::fs::canonicalize(&requested), std::fs::canonicalize(&base)) {
+        (Ok(r), Ok(b)) => (r, b),
+        _ => return jmcache_plain(StatusCode::NOT_FOUND, b"not found"),
+    };
+
+    if !requested_canon.starts_with(&base_canon) {
+        logl!(
+            "[tauri][jmcache] forbidden path requested={:?} base={:?}",
+            requested_canon,
+            base_canon
+        );
+        return jmcache_plain(StatusCode::FORBIDDEN, b"forbidden");
+    }
+
+    cache_index_touch(&requested_canon);
+    jmcache_serve_file(request, &requested_canon)
 }
 
 #[tauri::command]
-async fn api_comments(
-    aid: String,
-    page: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let aid_trim = aid.trim();
-    let page_trim = page.trim();
-    let page_val = if page_trim.is_empty() { "1" } else { page_trim };
-    let query = format!(
-        "mode=manhua&aid={}&page={}",
-        urlencoding::encode(aid_trim),
-        urlencoding::encode(page_val)
-    );
-    api_get_encrypted_with_query("/forum", Some(query), cookies).await
+async fn api_local_favorites_list(
+    kind: Option<String>,
+    store: tauri::State<'_, LocalFavoritesStore>,
+) -> Result<LocalFavoritesListResponse, String> {
+    let tree = store.tree()?;
+    let latest_tree = read_latest_tree()?;
+
+    let kind = kind.unwrap_or_else(|| "all".to_string());
+    let kind = kind.trim();
+    let filter_multi = if kind.eq_ignore_ascii_case("single") {
+        Some(false)
+    } else if kind.eq_ignore_ascii_case("multi") {
+        Some(true)
+    } else {
+        None
+    };
+
+    let mut latest_map = HashMap::new();
+    for res in latest_tree.iter() {
+        let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(entry) = serde_json::from_slice::<LatestChapterEntry>(&v) {
+            latest_map.insert(k.to_vec(), entry);
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut total: u64 = 0;
+    let mut filtered: u64 = 0;
+    for res in tree.iter() {
+        let (_, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
+        let it: LocalFavoriteItem =
+            bincode::deserialize(&v).map_err(|e| format!("decode local favorite failed: {e}"))?;
+        total += 1;
+        let latest = latest_map
+            .get(it.aid.as_bytes())
+            .and_then(|entry| entry.latest_chapter_sort.clone());
+        if let Some(need_multi) = filter_multi {
+            let is_multi = latest.is_some();
+            if need_multi != is_multi {
+                continue;
+            }
+        }
+        filtered += 1;
+        items.push(LocalFavoriteView {
+            aid: it.aid,
+            title: it.title,
+            author: it.author,
+            cover_url: it.cover_url,
+            added_at: it.added_at,
+            updated_at: it.updated_at,
+            latest_chapter_sort: latest,
+        });
+    }
+    items.sort_by_key(|x| -x.updated_at);
+    Ok(LocalFavoritesListResponse {
+        total,
+        filtered,
+        list: items,
+    })
 }
 
 #[tauri::command]
-async fn api_comment_send(
+async fn api_local_favorite_has(
     aid: String,
-    comment: String,
-    comment_id: Option<String>,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let cid = comment_id.unwrap_or_default();
-    let mut form = vec![("comment", comment.as_str()), ("aid", aid.as_str())];
-    if !cid.trim().is_empty() {
-        form.push(("comment_id", cid.as_str()));
-    }
-    api_post_encrypted("/comment", &form, cookies).await
+    store: tauri::State<'_, LocalFavoritesStore>,
+) -> Result<bool, String> {
+    let tree = store.tree()?;
+    Ok(tree.get(aid.as_bytes()).map_err(|e| format!("sled get failed: {e}"))?.is_some())
 }
 
 #[tauri::command]
-async fn api_categories(cookies: HashMap<String, String>) -> Result<serde_json::Value, String> {
-    api_get_encrypted_with_query("/categories", None, cookies).await
-}
+async fn api_local_favorite_toggle(
+    aid: String,
+    title: Option<String>,
+    author: Option<String>,
+    cover_url: Option<String>,
+    store: tauri::State<'_, LocalFavoritesStore>,
+    app: tauri::AppHandle,
+) -> Result<bool, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("system time error: {e}"))?
+        .as_secs() as i64;
 
-#[tauri::command]
-fn api_read_cache_stats() -> Result<ReadCacheStats, String> {
-    let data_dir = resolve_data_dir()?;
-    let db = sled::open(data_dir.join("cache-stats.sled"))
-        .map_err(|e| format!("open cache stats db failed: {e}"))?;
-    let summary_tree = db
-        .open_tree("read_cache_summary")
-        .map_err(|e| format!("open cache summary tree failed: {e}"))?;
-    if let Some(val) = summary_tree.get("summary").map_err(|e| format!("read summary failed: {e}"))? {
-        let stats: ReadCacheStats =
-            serde_json::from_slice(&val).map_err(|e| format!("decode summary failed: {e}"))?;
-        return Ok(stats);
+    let tree = store.tree()?;
+
+    if tree
+        .get(aid.as_bytes())
+        .map_err(|e| format!("sled get failed: {e}"))?
+        .is_some()
+    {
+        tree.remove(aid.as_bytes())
+            .map_err(|e| format!("sled remove failed: {e}"))?;
+        let _ = tree.flush();
+        logl!("[tauri][localfav] removed aid={}", aid);
+        let _ = read_latest_tree().and_then(|t| {
+            let _ = t.remove(aid.as_bytes());
+            let _ = t.flush();
+            Ok(())
+        });
+        let _ = read_latest_seen_tree().and_then(|t| {
+            let _ = t.remove(aid.as_bytes());
+            let _ = t.flush();
+            Ok(())
+        });
+        let _ = read_follow_tree().and_then(|t| {
+            let _ = t.remove(aid.as_bytes());
+            let _ = t.flush();
+            Ok(())
+        });
+        let _ = read_auto_download_rule_tree().map(|t| {
+            let _ = t.remove(aid.as_bytes());
+            let _ = t.flush();
+        });
+        clear_auto_download_pending(&aid);
+        return Ok(false);
     }
-    Ok(ReadCacheStats::default())
-}
 
-#[tauri::command]
-fn api_read_cache_refresh(app: tauri::AppHandle) -> Result<(), String> {
-    update_read_cache_stats(app)
+    let title_log = title.clone();
+    let item = LocalFavoriteItem {
+        aid: aid.clone(),
+        title: title.unwrap_or_default(),
+        author: author.unwrap_or_default(),
+        cover_url: cover_url.unwrap_or_default(),
+        added_at: now,
+        updated_at: now,
+    };
+    let val = bincode::serialize(&item).map_err(|e| format!("encode local favorite failed: {e}"))?;
+    tree.insert(aid.as_bytes(), val)
+        .map_err(|e| format!("sled insert failed: {e}"))?;
+    let _ = tree.flush();
+    logl!("[tauri][localfav] added aid={} title={:?}", aid, title_log);
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async {
+            if let Err(e) = scan_latest_chapters(handle).await {
+                logl!("[tauri][latest] scan failed: {e}");
+            }
+        });
+    });
+    Ok(true)
 }
 
 #[tauri::command]
-fn api_read_cache_cleanup(app: tauri::AppHandle, max_bytes: u64) -> Result<ReadCacheStats, String> {
-    let base = resolve_read_cache_dir(&app)?;
-    let read_dir = base.join("read");
-    if !read_dir.exists() {
-        return Ok(ReadCacheStats::default());
-    }
-
-    let mut entries = scan_read_cache_dirs(&read_dir);
-    let mut total: u64 = entries.iter().map(|e| e.bytes).sum();
-
-    if total > max_bytes {
-        entries.sort_by(|a, b| a.newest_ms.cmp(&b.newest_ms));
-        for entry in entries {
-            if total <= max_bytes {
-                break;
-            }
-            let dir = read_dir.join(sanitize_path_component(&entry.aid));
-            if let Err(e) = std::fs::remove_dir_all(&dir) {
-                logl!("[tauri][cache] remove {:?} failed: {}", dir, e);
-                continue;
-            }
-            total = total.saturating_sub(entry.bytes);
-        }
-    }
+async fn api_read_cancel(
+    read_key: String,
+    registry: tauri::State<'_, CancelRegistry>,
+) -> Result<(), String> {
+    logl!("[tauri][cancel] read_key={:?} set=true", read_key);
+    registry.cancel(&read_key);
+    Ok(())
+}
 
-    update_read_cache_stats(app.clone())?;
-    api_read_cache_stats()
+// Binary IPC body: the MIME type, a `\n`, then the image bytes. Raw bytes skip the
+// base64/JSON round trip while still telling callers what they got.
+fn ipc_image_response(bytes: &[u8]) -> tauri::ipc::Response {
+    let mime = image::guess_format(bytes)
+        .map(mime_from_format)
+        .unwrap_or("application/octet-stream");
+    let mut body = Vec::with_capacity(mime.len() + 1 + bytes.len());
+    body.extend_from_slice(mime.as_bytes());
+    body.push(b'\n');
+    body.extend_from_slice(bytes);
+    tauri::ipc::Response::new(body)
 }
 
 #[tauri::command]
-async fn api_register_captcha(web_base: Option<String>) -> Result<String, String> {
-    let base = web_base_from_opt(web_base);
-    let url = format!("{}/captcha", base.trim_end_matches('/'));
-    let referer = format!("{}/signup", base.trim_end_matches('/'));
+async fn api_image_descramble(url: String, num: i64) -> Result<tauri::ipc::Response, String> {
+    let started = Instant::now();
+    let mem_key = format!("ipc:{}", page_cache_key(&url, num));
+    if let Some(bytes) = page_memory_get(&mem_key, None) {
+        logl!("[tauri][img] memory hit url={:?} num={}", url, num);
+        return Ok(ipc_image_response(&bytes));
+    }
+    logl!("[tauri][img] start url={:?} num={}", url, num);
 
-    let client = register_client()?;
+    let client = http_client()?;
     let resp = client
         .get(&url)
-        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
-        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
-        .header("accept-language", "zh-CN,zh;q=0.9")
         .header("accept-encoding", "identity")
-        .header(reqwest::header::REFERER, referer)
         .send()
         .await
-        .map_err(|e| format!("captcha request failed: {e}"))?;
+        .map_err(|e| format!("request failed: {e}"))?;
 
     let status = resp.status();
-    let bytes = resp.bytes().await.map_err(|e| format!("read captcha failed: {e}"))?;
     if !status.is_success() {
-        return Err(format!("captcha http status {}", status.as_u16()));
+        logl!(
+            "[tauri][img] http_error status={} url={:?} cost_ms={}",
+            status,
+            url,
+            started.elapsed().as_millis()
+        );
+        return Err(format!("image http error: {status}"));
     }
-    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+
+    let bytes = read_body_paced(resp, Transfer::Interactive)
+        .await
+        .map_err(|e| format!("read image failed: {e}"))?;
+
+    let (out, fmt) = descramble_image_bytes(&bytes, num)?;
+
+    logl!(
+        "[tauri][img] ok url={:?} num={} mime={} out_len={} cost_ms={}",
+        url,
+        num,
+        mime_from_format(fmt),
+        out.len(),
+        started.elapsed().as_millis()
+    );
+    let resp = ipc_image_response(&out);
+    page_memory_insert(mem_key, Arc::new(out), None);
+    Ok(resp)
 }
 
-#[tauri::command]
-async fn api_register(
-    username: String,
-    email: String,
-    password: String,
-    verification: String,
-    gender: String,
-    web_base: Option<String>,
-) -> Result<serde_json::Value, String> {
-    let base = web_base_from_opt(web_base);
-    let url = format!("{}/signup", base.trim_end_matches('/'));
+// Shared by the reader command and the `jmcache` image route: cache hit or download + descramble + store.
+async fn ensure_descrambled_page(
+    base_dir: &std::path::Path,
+    url: &str,
+    num: i64,
+    aid: Option<&str>,
+    read_key: Option<&str>,
+    token: Option<Arc<AtomicBool>>,
+    class: Transfer,
+) -> Result<std::path::PathBuf, String> {
+    let started = Instant::now();
+    let num = if num <= 1 { 1 } else { num };
+    let out_dir = page_cache_dir(base_dir, "read", aid);
+    std::fs::create_dir_all(&out_dir).map_err(|e| format!("mkdir failed: {e}"))?;
 
-    let gender = if gender.eq_ignore_ascii_case("female") {
-        "Female"
-    } else {
-        "Male"
-    };
+    let key = page_cache_key(url, num);
+    if let Some(cached) = find_cached_image(&out_dir, &key) {
+        logl!(
+            "[tauri][imgfile] hit num={} out={:?} read_key={:?} cost_ms={}",
+            num,
+            cached,
+            read_key,
+            started.elapsed().as_millis()
+        );
+        remember_cached_page_meta(base_dir, url, &cached);
+        cache_index_touch(&cached);
+        return Ok(cached);
+    }
+    if offline_mode() == "on" {
+        return Err("offline: page not cached".to_string());
+    }
+    // Downloads wait for a background window; prefetch gives up instead of hanging.
+    if class == Transfer::Background {
+        match &token {
+            Some(stop) => wait_background_window(stop).await?,
+            None if !background_window_open() => return Err(OUTSIDE_WINDOW_ERROR.to_string()),
+            None => {}
+        }
+    }
 
-    let client = register_client()?;
+    let client = http_client()?;
     let resp = client
-        .post(&url)
-        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
-        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
-        .header("accept-language", "zh-CN,zh;q=0.9")
+        .get(url)
         .header("accept-encoding", "identity")
-        .form(&[
-            ("username", username.as_str()),
-            ("password", password.as_str()),
-            ("email", email.as_str()),
-            ("verification", verification.as_str()),
-            ("password_confirm", password.as_str()),
-            ("gender", gender),
-            ("age", "on"),
-            ("terms", "on"),
-            ("submit_signup", ""),
-        ])
         .send()
         .await
-        .map_err(|e| format!("register request failed: {e}"))?;
+        .map_err(|e| format!("request failed: {e}"))?;
+
+    if let Some(t) = &token {
+        if t.load(Ordering::Relaxed) {
+            logl!(
+                "[tauri][imgfile] cancelled(after_http) url={:?} num={} read_key={:?} cost_ms={}",
+                url,
+                num,
+                read_key,
+                started.elapsed().as_millis()
+            );
+            return Err("cancelled".to_string());
+        }
+    }
 
     let status = resp.status();
-    let text = resp.text().await.map_err(|e| format!("read register response failed: {e}"))?;
-    let (ok, msg) = parse_toastr_message(&text);
+    if !status.is_success() {
+        logl!(
+            "[tauri][imgfile] http_error status={} url={:?} cost_ms={}",
+            status,
+            url,
+            started.elapsed().as_millis()
+        );
+        return Err(format!("image http error: {status}"));
+    }
 
-    if ok {
-        return Ok(serde_json::json!({ "ok": true, "message": msg }));
+    let content_len = resp.content_length();
+    let bytes = read_body_paced(resp, class)
+        .await
+        .map_err(|e| format!("read image failed: {e}"))?;
+    check_image_header(&bytes, content_len)?;
+
+    if let Some(t) = &token {
+        if t.load(Ordering::Relaxed) {
+            logl!(
+                "[tauri][imgfile] cancelled(after_body) url={:?} num={} read_key={:?} cost_ms={}",
+                url,
+                num,
+                read_key,
+                started.elapsed().as_millis()
+            );
+            return Err("cancelled".to_string());
+        }
+    }
+
+    let (out, fmt) = match descramble_image_bytes_with_cancel(&bytes, num, token.as_deref()) {
+        Ok(v) => v,
+        Err(e) if e == "cancelled" => {
+            logl!(
+                "[tauri][imgfile] cancelled(descramble) url={:?} num={} read_key={:?} cost_ms={}",
+                url,
+                num,
+                read_key,
+                started.elapsed().as_millis()
+            );
+            return Err("cancelled".to_string());
+        }
+        Err(e) => return Err(e),
+    };
+    let ext = ext_from_format(fmt);
+
+    let out_path = out_dir.join(format!("{key}.{ext}"));
+    if !out_path.exists() {
+        ensure_cache_write_space_async(base_dir, out.len() as u64).await?;
+        write_file_atomic(&out_path, &out)?;
+        cache_index_record(&out_path, chapter_id_from_url(url).as_deref());
+        // Only pages the reader asked for; bulk work (a stop token without a read key) and
+        // prefetch would push out the spreads being read.
+        if class == Transfer::Interactive && (token.is_none() || read_key.is_some()) {
+            page_memory_insert_file(&out_path, out.clone());
+        }
     }
 
-    if status.is_redirection() && msg.is_empty() {
-        return Ok(serde_json::json!({ "ok": true, "message": "" }));
+    if let (Some(chapter_id), Some((width, height))) =
+        (chapter_id_from_url(url), image_bytes_dimensions(&out))
+    {
+        let image_name = page_name_from_url(url);
+        let cache_file = cache_relative_path(base_dir, &out_path);
+        if let Err(e) =
+            record_page_meta(&chapter_id, &image_name, width, height, out.len() as u64, cache_file)
+        {
+            logl!("[tauri][pagemeta] record failed chapter={} name={} err={}", chapter_id, image_name, e);
+        }
     }
 
-    if status != reqwest::StatusCode::OK && msg.is_empty() {
-        return Err(format!("register http status {}", status.as_u16()));
+    {
+        let base_dir = base_dir.to_path_buf();
+        let aid = aid.map(|a| a.to_string());
+        let key = key.clone();
+        let source = out_path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = ensure_page_thumbnail(&base_dir, aid.as_deref(), &key, &source) {
+                logl!("[tauri][thumb] generate failed src={:?} err={}", source, e);
+            }
+        });
     }
 
-    Ok(serde_json::json!({ "ok": false, "message": msg }))
+    logl!(
+        "[tauri][imgfile] ok num={} out={:?} out_bytes={} read_key={:?} cost_ms={}",
+        num,
+        out_path,
+        out.len(),
+        read_key,
+        started.elapsed().as_millis()
+    );
+
+    Ok(out_path)
 }
 
 #[tauri::command]
-async fn api_register_verify(email: String, web_base: Option<String>) -> Result<serde_json::Value, String> {
-    let base = web_base_from_opt(web_base);
-    let url = format!("{}/confirm", base.trim_end_matches('/'));
-
-    let client = register_client()?;
-    let resp = client
-        .post(&url)
-        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
-        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
-        .header("accept-language", "zh-CN,zh;q=0.9")
-        .header("accept-encoding", "identity")
-        .form(&[("email", email.as_str()), ("submit_confirm", "發送EMAIL")])
-        .send()
-        .await
-        .map_err(|e| format!("verify mail request failed: {e}"))?;
-
-    let status = resp.status();
-    let text = resp.text().await.map_err(|e| format!("read verify mail response failed: {e}"))?;
-    let (ok, msg) = parse_toastr_message(&text);
+async fn api_image_descramble_file(
+    app: tauri::AppHandle,
+    url: String,
+    num: i64,
+    aid: Option<String>,
+    read_key: Option<String>,
+    display: Option<ReadDisplaySize>,
+    registry: tauri::State<'_, CancelRegistry>,
+) -> Result<String, String> {
+    let started = Instant::now();
+    let num = if num <= 1 { 1 } else { num };
+    logl!(
+        "[tauri][imgfile] start url={:?} num={} read_key={:?}",
+        url, num, read_key
+    );
 
-    if ok {
-        return Ok(serde_json::json!({ "ok": true, "message": msg }));
-    }
-    if status.is_redirection() && msg.is_empty() {
-        return Ok(serde_json::json!({ "ok": true, "message": "" }));
-    }
-    if status != reqwest::StatusCode::OK && msg.is_empty() {
-        return Err(format!("verify mail http status {}", status.as_u16()));
+    let token = read_key.as_deref().map(|k| registry.token_for(k));
+    if let Some(t) = &token {
+        if t.load(Ordering::Relaxed) {
+            logl!(
+                "[tauri][imgfile] cancelled(before) url={:?} num={} read_key={:?} cost_ms={}",
+                url,
+                num,
+                read_key,
+                started.elapsed().as_millis()
+            );
+            return Err("cancelled".to_string());
+        }
     }
-    Ok(serde_json::json!({ "ok": false, "message": msg }))
+
+    let base_dir = resolve_read_cache_dir(&app)?;
+    let out_path =
+        ensure_descrambled_page(
+            &base_dir,
+            &url,
+            num,
+            aid.as_deref(),
+            read_key.as_deref(),
+            token,
+            Transfer::Interactive,
+        )
+        .await?;
+    let shown = resolve_display_variant(out_path, page_cache_key(&url, num), display).await;
+    Ok(shown.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-async fn api_reset_password(email: String, web_base: Option<String>) -> Result<serde_json::Value, String> {
-    let base = web_base_from_opt(web_base);
-    let url = format!("{}/lost", base.trim_end_matches('/'));
-
-    let client = register_client()?;
-    let resp = client
-        .post(&url)
-        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
-        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
-        .header("accept-language", "zh-CN,zh;q=0.9")
-        .header("accept-encoding", "identity")
-        .form(&[("email", email.as_str()), ("submit_lost", "恢復密碼")])
-        .send()
-        .await
-        .map_err(|e| format!("reset password request failed: {e}"))?;
-
-    let status = resp.status();
-    let text = resp.text().await.map_err(|e| format!("read reset response failed: {e}"))?;
-    let (ok, msg) = parse_toastr_message(&text);
-
-    if ok {
-        return Ok(serde_json::json!({ "ok": true, "message": msg }));
-    }
-    if status.is_redirection() && msg.is_empty() {
-        return Ok(serde_json::json!({ "ok": true, "message": "" }));
-    }
-    if status != reqwest::StatusCode::OK && msg.is_empty() {
-        return Err(format!("reset password http status {}", status.as_u16()));
-    }
-    Ok(serde_json::json!({ "ok": false, "message": msg }))
+async fn api_chapter_thumbnails(
+    app: tauri::AppHandle,
+    aid: Option<String>,
+    pages: Vec<ReadPageRef>,
+) -> Result<Vec<PageThumbnail>, String> {
+    let base_dir = resolve_read_cache_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let read_dir = page_cache_dir(&base_dir, "read", aid.as_deref());
+        let mut out = Vec::with_capacity(pages.len());
+        for (index, page) in pages.iter().enumerate() {
+            let key = page_cache_key(&page.url, page.num);
+            // Pages that were never read have nothing to thumbnail yet.
+            let path = find_cached_image(&read_dir, &key).and_then(|source| {
+                match ensure_page_thumbnail(&base_dir, aid.as_deref(), &key, &source) {
+                    Ok(p) => Some(p.to_string_lossy().to_string()),
+                    Err(e) => {
+                        logl!("[tauri][thumb] generate failed src={:?} err={}", source, e);
+                        None
+                    }
+                }
+            });
+            out.push(PageThumbnail { index, path });
+        }
+        out
+    })
+    .await
+    .map_err(|e| format!("thumbnail task failed: {e}"))
 }
 
 #[tauri::command]
-async fn api_verify_mail(url: String, web_base: Option<String>) -> Result<serde_json::Value, String> {
-    let url = normalize_verify_url(&url, web_base);
-    if url.is_empty() {
-        return Err("empty verify url".to_string());
+async fn api_chapter_page_dimensions(
+    app: tauri::AppHandle,
+    chapter_id: String,
+    aid: Option<String>,
+    pages: Vec<ReadPageRef>,
+) -> Result<Vec<PageDimension>, String> {
+    let base_dir = resolve_read_cache_dir(&app)?;
+    let read_dir = page_cache_dir(&base_dir, "read", aid.as_deref());
+    let mut out = Vec::with_capacity(pages.len());
+    let mut missing = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
+        let image_name = page_name_from_url(&page.url);
+        let mut known = load_page_meta(&chapter_id, &image_name);
+        if known.is_none() {
+            let key = page_cache_key(&page.url, page.num);
+            if let Some(cached) = find_cached_image(&read_dir, &key) {
+                if let (Ok((width, height)), Ok(meta)) =
+                    (image::image_dimensions(&cached), std::fs::metadata(&cached))
+                {
+                    let cache_file = cache_relative_path(&base_dir, &cached);
+                    let _ = record_page_meta(&chapter_id, &image_name, width, height, meta.len(), cache_file);
+                    known = load_page_meta(&chapter_id, &image_name);
+                }
+            }
+        }
+        if known.is_none() {
+            missing.push(index);
+        }
+        out.push(PageDimension {
+            index,
+            image_name,
+            width: known.as_ref().map(|m| m.width),
+            height: known.as_ref().map(|m| m.height),
+            file_size: known.as_ref().map(|m| m.file_size).filter(|n| *n > 0),
+        });
     }
 
-    let client = register_client()?;
-    let resp = client
-        .get(&url)
-        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43")
-        .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
-        .header("accept-language", "zh-CN,zh;q=0.9")
-        .header("accept-encoding", "identity")
-        .send()
-        .await
-        .map_err(|e| format!("verify url request failed: {e}"))?;
-
-    let status = resp.status();
-    let text = resp.text().await.map_err(|e| format!("read verify url response failed: {e}"))?;
-    let (ok, msg) = parse_toastr_message(&text);
-
-    if ok {
-        return Ok(serde_json::json!({ "ok": true, "message": msg }));
-    }
-    if status.is_redirection() && msg.is_empty() {
-        return Ok(serde_json::json!({ "ok": true, "message": "" }));
+    if !missing.is_empty() {
+        let client = http_client()?;
+        let limit = Arc::new(tokio::sync::Semaphore::new(6));
+        let mut tasks = Vec::with_capacity(missing.len());
+        for index in missing {
+            let client = client.clone();
+            let limit = limit.clone();
+            let url = pages[index].url.clone();
+            tasks.push(tauri::async_runtime::spawn(async move {
+                let _permit = limit.acquire_owned().await.ok()?;
+                match probe_remote_image_dimensions(&client, &url).await {
+                    Ok(dims) => Some((index, dims)),
+                    Err(e) => {
+                        logl!("[tauri][pagemeta] probe failed url={:?} err={}", url, e);
+                        None
+                    }
+                }
+            }));
+        }
+        for task in tasks {
+            let Ok(Some((index, (width, height)))) = task.await else {
+                continue;
+            };
+            let item = &mut out[index];
+            if let Err(e) = record_page_meta(&chapter_id, &item.image_name, width, height, 0, None) {
+                logl!("[tauri][pagemeta] record failed chapter={} err={}", chapter_id, e);
+            }
+            item.width = Some(width);
+            item.height = Some(height);
+        }
     }
-    if status != reqwest::StatusCode::OK && msg.is_empty() {
-        return Err(format!("verify url http status {}", status.as_u16()));
+
+    if let Ok(tree) = read_page_meta_tree() {
+        let _ = tree.flush();
     }
-    Ok(serde_json::json!({ "ok": false, "message": msg }))
+    Ok(out)
 }
 
-#[tauri::command]
-async fn api_category_search(
-    category: String,
-    page: String,
-    sort: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let mut params: Vec<(String, String)> = Vec::new();
-    if let Ok(p) = page.parse::<i64>() {
-        if p > 1 {
-            params.push(("page".to_string(), p.to_string()));
+const IMG_BASE_DEFAULT: &str = "https://cdn-msp.jmapinodeudzn.net";
+const DOWNLOAD_PAGE_CONCURRENCY: usize = 3;
+const DOWNLOAD_PAGE_ATTEMPTS: u64 = 3;
+const DOWNLOAD_SAVE_INTERVAL_MS: i64 = 1_000;
+
+impl DownloadJob {
+    fn recount(&mut self) {
+        let pages = self.chapters.iter().flat_map(|c| c.pages.iter());
+        let (mut total, mut done, mut failed) = (0, 0, 0);
+        for page in pages {
+            total += 1;
+            if page.done {
+                done += 1;
+            } else if page.error.is_some() {
+                failed += 1;
+            }
+        }
+        self.total_pages = total;
+        self.done_pages = done;
+        self.failed_pages = failed;
+    }
+
+    fn summary(&self) -> DownloadJobSummary {
+        DownloadJobSummary {
+            id: self.id.clone(),
+            kind: self.kind.clone(),
+            aid: self.aid.clone(),
+            title: self.title.clone(),
+            state: self.state.clone(),
+            error: self.error.clone(),
+            chapters: self.chapters.len() as u64,
+            resolved_chapters: self.chapters.iter().filter(|c| c.resolved).count() as u64,
+            total_pages: self.total_pages,
+            done_pages: self.done_pages,
+            failed_pages: self.failed_pages,
+            auto: self.auto,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
         }
     }
-    if !sort.trim().is_empty() {
-        params.push(("o".to_string(), sort));
-    }
-    if !category.trim().is_empty() {
-        params.push(("c".to_string(), category));
-    }
+}
 
-    let query = if params.is_empty() {
-        None
-    } else {
-        Some(
-            params
-                .into_iter()
-                .map(|(k, v)| format!("{k}={v}"))
-                .collect::<Vec<_>>()
-                .join("&"),
-        )
+fn load_download_job(id: &str) -> Result<Option<DownloadJob>, String> {
+    let tree = read_download_tree()?;
+    let Some(val) = tree
+        .get(id.as_bytes())
+        .map_err(|e| format!("read download job failed: {e}"))?
+    else {
+        return Ok(None);
     };
-
-    api_get_encrypted_with_query("/categories/filter", query, cookies).await
+    serde_json::from_slice(&val)
+        .map(Some)
+        .map_err(|e| format!("decode download job failed: {e}"))
 }
 
-#[tauri::command]
-async fn api_cover_cache(url: String, app: tauri::AppHandle) -> Result<String, String> {
-    let url = url.trim().to_string();
-    if url.is_empty() {
-        return Err("empty url".to_string());
-    }
-
-    let _permit = cover_semaphore()
-        .acquire()
-        .await
-        .map_err(|_| "cover cache queue closed".to_string())?;
-
-    let base = resolve_read_cache_dir(&app)?;
-    let cover_dir = base.join("cover");
-    std::fs::create_dir_all(&cover_dir).map_err(|e| format!("mkdir failed: {e}"))?;
+fn save_download_job(job: &DownloadJob) -> Result<(), String> {
+    let tree = read_download_tree()?;
+    let val = serde_json::to_vec(job).map_err(|e| format!("encode download job failed: {e}"))?;
+    tree.insert(job.id.as_bytes(), val)
+        .map_err(|e| format!("write download job failed: {e}"))?;
+    let _ = tree.flush();
+    Ok(())
+}
 
-    let clean_url = url.split('#').next().unwrap_or(&url);
-    let clean_url = clean_url.split('?').next().unwrap_or(clean_url);
-    let ext = std::path::Path::new(clean_url)
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| sanitize_path_component(s))
-        .filter(|s| !s.is_empty())
-        .unwrap_or_else(|| "jpg".to_string());
-    let hash = md5_hex(&url);
-    let file_name = format!("{hash}.{ext}");
-    let out_path = cover_dir.join(file_name);
+fn remove_download_job(id: &str) -> Result<(), String> {
+    let tree = read_download_tree()?;
+    tree.remove(id.as_bytes())
+        .map_err(|e| format!("remove download job failed: {e}"))?;
+    let _ = tree.flush();
+    Ok(())
+}
 
-    if out_path.exists() {
-        return Ok(out_path.to_string_lossy().to_string());
-    }
+// Cache dir names (`read/<aid>`) of every comic with a download job, whatever its state.
+fn downloaded_aids() -> Result<std::collections::HashSet<String>, String> {
+    Ok(load_download_jobs()?
+        .into_iter()
+        .map(|job| sanitize_path_component(&job.aid))
+        .collect())
+}
 
-    let client = http_client()?;
-    let mut last_err = None::<String>;
-    for attempt in 0..3 {
-        let resp = client
-            .get(&url)
-            .timeout(Duration::from_secs(10))
-            .send()
-            .await;
-        match resp {
-            Ok(resp) => {
-                let status = resp.status();
-                if !status.is_success() {
-                    last_err = Some(format!("http status {}", status.as_u16()));
-                } else {
-                    let bytes = resp
-                        .bytes()
-                        .await
-                        .map_err(|e| format!("read body failed: {e}"))?;
-                    std::fs::write(&out_path, &bytes).map_err(|e| format!("write cache failed: {e}"))?;
-                    return Ok(out_path.to_string_lossy().to_string());
-                }
-            }
+fn load_download_jobs() -> Result<Vec<DownloadJob>, String> {
+    let tree = read_download_tree()?;
+    let mut out = Vec::new();
+    for item in tree.iter() {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        match serde_json::from_slice::<DownloadJob>(&val) {
+            Ok(job) => out.push(job),
             Err(e) => {
-                last_err = Some(format!("request failed: {e}"));
+                logl!("[tauri][download] skip undecodable job err={}", e);
             }
         }
-        if attempt < 2 {
-            tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+    out.sort_by_key(|j| j.created_at);
+    Ok(out)
+}
+
+// Keeps stored titles current so a renamed album moves its library folder.
+fn refresh_download_meta(aid: &str, album: &serde_json::Value) {
+    let Some(name) = album.get("name").and_then(|v| v.as_str()).filter(|n| !n.is_empty()) else {
+        return;
+    };
+    // Point lookups: the album job plus one chapter job per listed chapter.
+    let ids = std::iter::once(format!("album:{aid}")).chain(
+        album_download_chapters(album, aid)
+            .into_iter()
+            .map(|c| format!("chapter:{}", c.chapter_id)),
+    );
+    let jobs = ids.filter_map(|id| load_download_job(&id).ok().flatten());
+    let mut changed = false;
+    for mut job in jobs.filter(|j| j.aid == aid && j.title != name) {
+        // The running job keeps its own copy and would overwrite this.
+        if job.state == "running" {
+            continue;
         }
+        apply_album_meta(&mut job, album);
+        changed |= save_download_job(&job).is_ok();
     }
+    if changed {
+        if let Some(app) = APP_HANDLE.get() {
+            spawn_library_sync(app, vec![aid.to_string()]);
+        }
+    }
+}
 
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+fn emit_download_progress(app: &tauri::AppHandle, job: &DownloadJob) {
+    let _ = app.emit("download-progress", job.summary());
 }
 
-#[tauri::command]
-async fn api_search(
-    search_query: String,
-    sort: String,
-    page: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+fn json_id(v: &serde_json::Value) -> String {
+    v.to_string().trim_matches('"').to_string()
+}
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+// `author` is a string or a (possibly nested) list, like `toAuthorText` on the frontend.
+fn json_string_list(v: Option<&serde_json::Value>) -> Vec<String> {
+    fn collect(v: &serde_json::Value, out: &mut Vec<String>) {
+        match v {
+            serde_json::Value::Array(items) => items.iter().for_each(|x| collect(x, out)),
+            serde_json::Value::String(s) if !s.trim().is_empty() => out.push(s.trim().to_string()),
+            serde_json::Value::Number(n) => out.push(n.to_string()),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    if let Some(v) = v {
+        collect(v, &mut out);
+    }
+    out
+}
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+fn apply_album_meta(job: &mut DownloadJob, album: &serde_json::Value) {
+    if let Some(name) = album.get("name").and_then(|v| v.as_str()) {
+        job.title = name.to_string();
+    }
+    job.authors = json_string_list(album.get("author"));
+    job.tags = json_string_list(album.get("tags"));
+    job.description = album
+        .get("description")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+}
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let mut url = format!(
-            "{}/search/?search_query={}&o={}",
-            base.trim_end_matches('/'),
-            urlencoding::encode(&search_query),
-            urlencoding::encode(&sort)
-        );
-        if page.trim() != "1" && !page.trim().is_empty() {
-            url.push_str(&format!("&page={}", urlencoding::encode(page.trim())));
-        }
+// Mirrors `normalizeImgUrl` in ReadingPage.tsx.
+fn download_page_url(path: &str, chapter_id: &str, img_base: Option<&str>) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return path.to_string();
+    }
+    let base = img_base.unwrap_or(IMG_BASE_DEFAULT).trim_end_matches('/');
+    if path.starts_with('/') {
+        format!("{base}{path}")
+    } else {
+        format!("{base}/media/photos/{chapter_id}/{path}")
+    }
+}
 
-        let resp = match client
-            .get(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-            )
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .header(reqwest::header::COOKIE, cookie_header(&cookies))
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][search] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
-            }
-        };
+// Reader page order: by the first number in the file name, names without one last.
+fn sort_chapter_images(images: &mut [String]) {
+    fn num_key(s: &str) -> Option<u64> {
+        let digits: String = s
+            .chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    }
+    images.sort_by(|a, b| match (num_key(a), num_key(b)) {
+        (Some(na), Some(nb)) => na.cmp(&nb),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.cmp(b),
+    });
+}
 
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][search] retry base={} status={}", base, status);
-            continue;
-        }
+// Single-chapter albums have no (or one) `series` entry; the album id is the chapter id.
+fn album_download_chapters(album: &serde_json::Value, aid: &str) -> Vec<DownloadChapter> {
+    let series = album
+        .get("series")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if series.len() <= 1 {
+        return vec![DownloadChapter {
+            chapter_id: aid.to_string(),
+            ..Default::default()
+        }];
+    }
+    let sort_num = |v: &serde_json::Value| {
+        let raw = v.get("sort");
+        raw.and_then(|s| s.as_f64())
+            .or_else(|| raw.and_then(|s| s.as_str().and_then(|s| s.parse::<f64>().ok())))
+            .unwrap_or(0.0)
+    };
+    let mut items: Vec<&serde_json::Value> = series.iter().collect();
+    items.sort_by(|a, b| {
+        sort_num(a)
+            .partial_cmp(&sort_num(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let chapter_id = item.get("id").map(json_id).filter(|id| !id.is_empty())?;
+            Some(DownloadChapter {
+                chapter_id,
+                sort: item.get("sort").map(json_id).filter(|s| !s.is_empty()),
+                name: item
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .filter(|s| !s.is_empty()),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
+// Empty entries are never downloadable, so they don't count as pages.
+fn chapter_api_image_count(data: &serde_json::Value) -> Option<u64> {
+    data.get("images").and_then(|v| v.as_array()).map(|l| {
+        l.iter()
+            .filter(|v| v.as_str().is_some_and(|s| !s.is_empty()))
+            .count() as u64
+    })
+}
 
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(300)
-            .collect::<String>();
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][search] retry base={} body_hint", base);
-            continue;
+async fn resolve_download_chapter(
+    chapter: &mut DownloadChapter,
+    img_base: Option<&str>,
+    cookies: &HashMap<String, String>,
+) -> Result<(), String> {
+    let data = api_chapter(chapter.chapter_id.clone(), cookies.clone()).await?;
+    store_offline_snapshot(&format!("chapter:{}", chapter.chapter_id), &data);
+    chapter.expected_pages = chapter_api_image_count(&data);
+    let mut images: Vec<String> = data
+        .get("images")
+        .and_then(|v| v.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if images.is_empty() {
+        return Err(format!("chapter {} has no images", chapter.chapter_id));
+    }
+    sort_chapter_images(&mut images);
+    if chapter.name.is_none() {
+        chapter.name = data
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty());
+    }
+
+    // Same fallback as the reader when the template page cannot be fetched.
+    let scramble_id = match api_chapter_scramble_id(chapter.chapter_id.clone()).await {
+        Ok(id) => id,
+        Err(e) => {
+            logl!("[tauri][download] scramble id failed chapter={} err={}", chapter.chapter_id, e);
+            SCRAMBLE_220980
         }
+    };
+    let eps_id = chapter
+        .chapter_id
+        .parse::<i64>()
+        .map_err(|_| format!("invalid chapter id: {}", chapter.chapter_id))?;
 
-        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+    chapter.pages = images
+        .iter()
+        .map(|path| {
+            let url = download_page_url(path, &chapter.chapter_id, img_base);
+            let name = page_name_from_url(&url);
+            let picture = name.split('.').next().unwrap_or_default().to_string();
+            DownloadPage {
+                num: get_segmentation_num(eps_id, scramble_id, &picture).max(1),
+                name,
+                url,
+                done: false,
+                error: None,
+            }
+        })
+        .collect();
+    chapter.resolved = true;
+    Ok(())
+}
 
-        if env.code != 200 {
-            let msg = if !env.error_msg.is_empty() {
-                env.error_msg
-            } else if !env.message.is_empty() {
-                env.message
-            } else {
-                format!("request failed, code={}, http_status={}", env.code, status)
-            };
-            logl!(
-                "[tauri][search] error http_status={} api_code={} base={:?} msg={:?}",
-                status, env.code, base, msg
-            );
-            return Err(api_error(status, env.code, msg));
+// Retries transient failures; cancellation, a cache move and low disk space end the page
+// immediately.
+// Queued downloads run as `Transfer::Background`; export and repair are user-started and
+// pass `Transfer::Interactive` so the background windows never hold them.
+async fn download_page(
+    base_dir: &std::path::Path,
+    url: &str,
+    num: i64,
+    aid: &str,
+    stop: &Arc<AtomicBool>,
+    class: Transfer,
+) -> Result<(), String> {
+    let mut last_err = String::new();
+    for attempt in 1..=DOWNLOAD_PAGE_ATTEMPTS {
+        if stop.load(Ordering::Relaxed) {
+            return Err("cancelled".to_string());
+        }
+        let token = Some(stop.clone());
+        match ensure_descrambled_page(base_dir, url, num, Some(aid), None, token, class).await {
+            Ok(_) => return Ok(()),
+            Err(e) if e == "cancelled" || e == CACHE_MOVING_ERROR || is_disk_space_low(&e) => {
+                return Err(e);
+            }
+            Err(e) => {
+                logl!("[tauri][download] page failed attempt={} url={:?} err={}", attempt, url, e);
+                last_err = e;
+            }
+        }
+        if attempt < DOWNLOAD_PAGE_ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(1000 * attempt)).await;
         }
-
-        let encrypted = env
-            .data
-            .as_str()
-            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
-        let decrypted = decode_resp_data(encrypted, ts)?;
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        return serde_json::from_str(&decrypted)
-            .map_err(|e| format!("parse decrypted json failed: {e}"));
     }
-
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+    Err(last_err)
 }
 
-#[tauri::command]
-async fn api_album(
-    id: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+async fn download_chapter_pages(
+    app: &tauri::AppHandle,
+    base_dir: &std::path::Path,
+    job: &mut DownloadJob,
+    chapter_index: usize,
+    stop: &Arc<AtomicBool>,
+) -> Result<(), String> {
+    let pending: Vec<usize> = job.chapters[chapter_index]
+        .pages
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| !p.done)
+        .map(|(i, _)| i)
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let limit = Arc::new(tokio::sync::Semaphore::new(DOWNLOAD_PAGE_CONCURRENCY));
+    for index in pending {
+        let page = &job.chapters[chapter_index].pages[index];
+        let (url, num) = (page.url.clone(), page.num);
+        let (aid, base_dir) = (job.aid.clone(), base_dir.to_path_buf());
+        let (tx, limit, stop) = (tx.clone(), limit.clone(), stop.clone());
+        tauri::async_runtime::spawn(async move {
+            let Ok(_permit) = limit.acquire_owned().await else {
+                return;
+            };
+            let res = download_page(&base_dir, &url, num, &aid, &stop, Transfer::Background).await;
+            let _ = tx.send((index, res));
+        });
+    }
+    drop(tx);
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+    let mut fatal = None;
+    let mut last_save = now_millis();
+    while let Some((index, res)) = rx.recv().await {
+        let page = &mut job.chapters[chapter_index].pages[index];
+        match res {
+            Ok(()) => {
+                page.done = true;
+                page.error = None;
+            }
+            Err(e) if e == "cancelled" => {}
+            Err(e) => {
+                if is_disk_space_low(&e) && fatal.is_none() {
+                    fatal = Some(e.clone());
+                    stop.store(true, Ordering::Relaxed);
+                }
+                page.error = Some(e);
+            }
+        }
+        job.recount();
+        job.updated_at = now_millis();
+        emit_download_progress(app, job);
+        if job.updated_at - last_save >= DOWNLOAD_SAVE_INTERVAL_MS {
+            save_download_job(job)?;
+            last_save = job.updated_at;
+        }
+    }
+    save_download_job(job)?;
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let url = format!(
-            "{}/album/?comicName=&id={}",
-            base.trim_end_matches('/'),
-            id
-        );
+    if let Some(e) = fatal {
+        return Err(e);
+    }
+    if stop.load(Ordering::Relaxed) {
+        return Err("cancelled".to_string());
+    }
+    Ok(())
+}
 
-        let resp = match client
-            .get(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-            )
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .header(reqwest::header::COOKIE, cookie_header(&cookies))
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][album] retry base={} err={:?}", base, msg);
-                    continue;
+async fn run_download_job(
+    app: &tauri::AppHandle,
+    job: &mut DownloadJob,
+    stop: &Arc<AtomicBool>,
+) -> Result<(), String> {
+    let cookies = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?
+        .session_cookies
+        .clone();
+    let base_dir = resolve_read_cache_dir(app)?;
+
+    if job.chapters.is_empty() {
+        let album = api_album(job.aid.clone(), cookies.clone()).await?;
+        store_offline_snapshot(&format!("album:{}", job.aid), &album);
+        apply_album_meta(job, &album);
+        job.chapters = album_download_chapters(&album, &job.aid);
+        job.updated_at = now_millis();
+        save_download_job(job)?;
+        emit_download_progress(app, job);
+    } else if job.kind == "chapter" && job.authors.is_empty() && job.tags.is_empty() {
+        // Best effort: chapter jobs only need the album for export metadata.
+        match api_album(job.aid.clone(), cookies.clone()).await {
+            Ok(album) => {
+                store_offline_snapshot(&format!("album:{}", job.aid), &album);
+                apply_album_meta(job, &album);
+                let listed = album_download_chapters(&album, &job.aid);
+                for chapter in job.chapters.iter_mut() {
+                    if let Some(c) = listed.iter().find(|c| c.chapter_id == chapter.chapter_id) {
+                        chapter.sort = c.sort.clone();
+                        chapter.name = c.name.clone().or(chapter.name.take());
+                    }
                 }
-                return Err(msg);
             }
-        };
+            Err(e) => {
+                logl!("[tauri][download] album meta failed aid={} err={}", job.aid, e);
+            }
+        }
+    }
 
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][album] retry base={} status={}", base, status);
-            continue;
+    for index in 0..job.chapters.len() {
+        if stop.load(Ordering::Relaxed) {
+            return Err("cancelled".to_string());
+        }
+        if !job.chapters[index].resolved {
+            let img_base = job.img_base.clone();
+            resolve_download_chapter(&mut job.chapters[index], img_base.as_deref(), &cookies).await?;
+            if job.title.is_empty() {
+                job.title = job.chapters[index].name.clone().unwrap_or_default();
+            }
+            job.recount();
+            job.updated_at = now_millis();
+            save_download_job(job)?;
+            emit_download_progress(app, job);
         }
+        download_chapter_pages(app, &base_dir, job, index, stop).await?;
+    }
+    Ok(())
+}
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
+async fn process_download_job(app: &tauri::AppHandle, mut job: DownloadJob) {
+    let started = Instant::now();
+    let stop = Arc::new(AtomicBool::new(false));
+    *DOWNLOAD_ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = Some(ActiveDownload {
+        id: job.id.clone(),
+        stop: stop.clone(),
+        stop_to: None,
+    });
+    job.state = "running".to_string();
+    job.error = None;
+    job.updated_at = now_millis();
+    if let Err(e) = save_download_job(&job) {
+        logl!("[tauri][download] save failed id={} err={}", job.id, e);
+    }
+    emit_download_progress(app, &job);
+    logl!("[tauri][download] start id={} aid={}", job.id, job.aid);
 
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(300)
-            .collect::<String>();
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][album] retry base={} body_hint", base);
-            continue;
+    let res = run_download_job(app, &mut job, &stop).await;
+    let requested = DOWNLOAD_ACTIVE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .and_then(|a| a.stop_to);
+    job.updated_at = now_millis();
+    match (requested, res) {
+        (Some(state), _) if state == "cancelled" => {
+            if let Err(e) = remove_download_job(&job.id) {
+                logl!("[tauri][download] remove failed id={} err={}", job.id, e);
+            }
+            job.state = state;
+            emit_download_progress(app, &job);
+            logl!("[tauri][download] cancelled id={}", job.id);
+            return;
+        }
+        (Some(state), _) => job.state = state,
+        (None, Ok(())) if job.failed_pages > 0 => {
+            job.state = "failed".to_string();
+            job.error = Some(format!("{} pages failed", job.failed_pages));
+        }
+        (None, Ok(())) => job.state = "done".to_string(),
+        (None, Err(e)) => {
+            job.state = "failed".to_string();
+            job.error = Some(e);
+        }
+    }
+    if let Err(e) = save_download_job(&job) {
+        logl!("[tauri][download] save failed id={} err={}", job.id, e);
+    }
+    emit_download_progress(app, &job);
+    if job.done_pages > 0 {
+        if let Ok(base_dir) = resolve_read_cache_dir(app) {
+            let aid = job.aid.clone();
+            let res = tauri::async_runtime::spawn_blocking(move || {
+                update_download_manifest(&base_dir, &aid, false)
+            })
+            .await;
+            if let Ok(Err(e)) = res {
+                logl!("[tauri][integrity] manifest update failed aid={} err={}", job.aid, e);
+            }
         }
+        spawn_library_sync(app, vec![job.aid.clone()]);
+    }
+    logl!(
+        "[tauri][download] end id={} state={} pages={}/{} failed={} err={:?} cost_ms={}",
+        job.id,
+        job.state,
+        job.done_pages,
+        job.total_pages,
+        job.failed_pages,
+        job.error,
+        started.elapsed().as_millis()
+    );
+}
 
-        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+fn next_queued_download() -> Option<DownloadJob> {
+    match load_download_jobs() {
+        Ok(jobs) => jobs.into_iter().find(|j| j.state == "queued"),
+        Err(e) => {
+            logl!("[tauri][download] load jobs failed: {e}");
+            None
+        }
+    }
+}
 
-        if env.code != 200 {
-            let msg = if !env.error_msg.is_empty() {
-                env.error_msg
-            } else if !env.message.is_empty() {
-                env.message
-            } else {
-                format!("request failed, code={}, http_status={}", env.code, status)
-            };
-            return Err(msg);
+// Jobs run one at a time, oldest first; pages within a chapter run concurrently.
+fn kick_download_worker(app: &tauri::AppHandle) {
+    if DOWNLOAD_WORKER_RUNNING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // Jobs hold the cache dir for their whole run, so none starts during a move.
+            while CACHE_MOVE_RUNNING.load(Ordering::Acquire) {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            if let Some(job) = next_queued_download() {
+                process_download_job(&app, job).await;
+                continue;
+            }
+            DOWNLOAD_WORKER_RUNNING.store(false, Ordering::Release);
+            // A job queued between the lookup and the reset would otherwise wait for the next kick.
+            if next_queued_download().is_some() && !DOWNLOAD_WORKER_RUNNING.swap(true, Ordering::AcqRel) {
+                continue;
+            }
+            break;
         }
+    });
+}
 
-        let encrypted = env
-            .data
-            .as_str()
-            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
-        let decrypted = decode_resp_data(encrypted, ts)?;
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        let album: serde_json::Value =
-            serde_json::from_str(&decrypted).map_err(|e| format!("parse decrypted json failed: {e}"))?;
-        if let Some(series) = album.get("series").and_then(|v| v.as_array()) {
-            if let Ok(tree) = read_latest_tree() {
-                let now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs() as i64)
-                    .unwrap_or(0);
-                if let Some((latest_id, latest_sort)) = parse_series_latest(series) {
-                    let entry = LatestChapterEntry {
-                        aid: id.clone(),
-                        latest_chapter_id: latest_id,
-                        latest_chapter_sort: latest_sort,
-                        updated_at: now,
-                    };
-                    if let Ok(val) = serde_json::to_vec(&entry) {
-                        let _ = tree.insert(id.as_bytes(), val);
-                        let _ = tree.flush();
-                    }
-                } else {
-                    let _ = tree.remove(id.as_bytes());
-                    let _ = tree.flush();
-                }
+// Jobs left `running` by a previous session go back to the queue.
+fn resume_download_jobs(app: &tauri::AppHandle) {
+    let jobs = match load_download_jobs() {
+        Ok(v) => v,
+        Err(e) => {
+            logl!("[tauri][download] load jobs failed: {e}");
+            return;
+        }
+    };
+    let mut queued = 0;
+    for mut job in jobs {
+        if job.state == "running" {
+            job.state = "queued".to_string();
+            if let Err(e) = save_download_job(&job) {
+                logl!("[tauri][download] requeue failed id={} err={}", job.id, e);
             }
         }
-        return Ok(album);
+        if job.state == "queued" {
+            queued += 1;
+        }
+    }
+    if queued > 0 {
+        logl!("[tauri][download] resuming queued={}", queued);
+        kick_download_worker(app);
     }
+}
 
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+// Returns true when `id` is the running job and it has been told to stop.
+fn request_download_stop(id: &str, state: &str) -> bool {
+    let mut active = DOWNLOAD_ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    match active.as_mut() {
+        Some(a) if a.id == id => {
+            a.stop_to = Some(state.to_string());
+            a.stop.store(true, Ordering::Relaxed);
+            true
+        }
+        _ => false,
+    }
 }
 
+fn require_download_job(id: &str) -> Result<DownloadJob, String> {
+    load_download_job(id)?.ok_or_else(|| format!("download job not found: {id}"))
+}
+
+// `kind` is "album" (all chapters of album `id`) or "chapter" (chapter `id` of album `aid`).
+// Re-enqueueing a finished, failed or paused job queues it again; cached pages are skipped.
 #[tauri::command]
-async fn api_chapter(
+fn api_download_enqueue(
+    app: tauri::AppHandle,
+    kind: String,
     id: String,
-    cookies: HashMap<String, String>,
-) -> Result<serde_json::Value, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+    aid: Option<String>,
+    img_base: Option<String>,
+) -> Result<DownloadJobSummary, String> {
+    enqueue_download(&app, &kind, &id, aid, img_base, false).map(|job| job.summary())
+}
 
-    let token_secret =
-        std::env::var("JM_APP_TOKEN_SECRET").unwrap_or_else(|_| JM_APP_TOKEN_SECRET_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
+fn enqueue_download(
+    app: &tauri::AppHandle,
+    kind: &str,
+    id: &str,
+    aid: Option<String>,
+    img_base: Option<String>,
+    auto: bool,
+) -> Result<DownloadJob, String> {
+    let id = id.trim().to_string();
+    if id.is_empty() {
+        return Err("id is empty".to_string());
+    }
+    let img_base = img_base
+        .map(|b| b.trim().trim_end_matches('/').to_string())
+        .filter(|b| b.starts_with("http://") || b.starts_with("https://"));
+    let now = now_millis();
+    let job_id = format!("{kind}:{id}");
+    let mut job = match kind {
+        "album" => DownloadJob {
+            id: job_id.clone(),
+            kind: kind.to_string(),
+            aid: id.clone(),
+            ..Default::default()
+        },
+        "chapter" => DownloadJob {
+            id: job_id.clone(),
+            kind: kind.to_string(),
+            aid: aid
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty())
+                .unwrap_or_else(|| id.clone()),
+            chapters: vec![DownloadChapter {
+                chapter_id: id.clone(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        _ => return Err(format!("unknown download kind: {kind}")),
+    };
+    if let Some(existing) = load_download_job(&job_id)? {
+        if existing.state == "queued" || existing.state == "running" {
+            return Ok(existing);
+        }
+        // Album chapters are re-resolved so newly published ones are picked up.
+        if kind == "chapter" {
+            job.chapters = existing.chapters;
+        }
+        job.title = existing.title;
+        job.created_at = existing.created_at;
+    } else {
+        job.created_at = now;
+    }
+    job.state = "queued".to_string();
+    job.img_base = img_base;
+    job.auto = auto;
+    job.updated_at = now;
+    for page in job.chapters.iter_mut().flat_map(|c| c.pages.iter_mut()) {
+        page.error = None;
+    }
+    job.recount();
+    save_download_job(&job)?;
+    emit_download_progress(app, &job);
+    logl!("[tauri][download] enqueue id={} aid={}", job.id, job.aid);
+    kick_download_worker(app);
+    Ok(job)
+}
 
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
+#[tauri::command]
+fn api_download_list() -> Result<Vec<DownloadJobSummary>, String> {
+    Ok(load_download_jobs()?.iter().map(|j| j.summary()).collect())
+}
 
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let url = format!(
-            "{}/chapter/?comicName=&skip=&id={}",
-            base.trim_end_matches('/'),
-            id
-        );
+// Full job including per-page state.
+#[tauri::command]
+fn api_download_get(id: String) -> Result<Option<DownloadJob>, String> {
+    load_download_job(&id)
+}
 
-        let resp = match client
-            .get(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36 Edg/114.0.1823.43",
-            )
-            .header("accept-encoding", "identity")
-            .header("version", JM_APP_VERSION)
-            .header(reqwest::header::COOKIE, cookie_header(&cookies))
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][chapter] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
-            }
-        };
+#[tauri::command]
+fn api_download_pause(app: tauri::AppHandle, id: String) -> Result<DownloadJobSummary, String> {
+    let mut job = require_download_job(&id)?;
+    if request_download_stop(&id, "paused") {
+        job.state = "paused".to_string();
+        return Ok(job.summary());
+    }
+    if job.state == "queued" {
+        job.state = "paused".to_string();
+        job.updated_at = now_millis();
+        save_download_job(&job)?;
+        emit_download_progress(&app, &job);
+    }
+    Ok(job.summary())
+}
 
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][chapter] retry base={} status={}", base, status);
-            continue;
-        }
+#[tauri::command]
+fn api_download_resume(app: tauri::AppHandle, id: String) -> Result<DownloadJobSummary, String> {
+    let mut job = require_download_job(&id)?;
+    if job.state == "paused" {
+        job.state = "queued".to_string();
+        job.updated_at = now_millis();
+        save_download_job(&job)?;
+        emit_download_progress(&app, &job);
+        kick_download_worker(&app);
+    }
+    Ok(job.summary())
+}
 
-        let body_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("failed reading response body: {e}"))?;
+// Re-queues a failed (or finished) job; only pages that are not done are fetched again.
+#[tauri::command]
+fn api_download_retry(app: tauri::AppHandle, id: String) -> Result<DownloadJobSummary, String> {
+    let mut job = require_download_job(&id)?;
+    if job.state == "running" || job.state == "queued" {
+        return Ok(job.summary());
+    }
+    for page in job.chapters.iter_mut().flat_map(|c| c.pages.iter_mut()) {
+        page.error = None;
+    }
+    job.state = "queued".to_string();
+    job.error = None;
+    job.updated_at = now_millis();
+    job.recount();
+    save_download_job(&job)?;
+    emit_download_progress(&app, &job);
+    kick_download_worker(&app);
+    Ok(job.summary())
+}
 
-        let body_preview = String::from_utf8_lossy(&body_bytes)
-            .chars()
-            .take(300)
-            .collect::<String>();
-        if should_retry_body(&body_preview) && pos + 1 < candidates.len() {
-            let msg = "body indicates invalid domain".to_string();
-            last_err = Some(msg.clone());
-            logl!("[tauri][chapter] retry base={} body_hint", base);
-            continue;
+// Drops the job; pages already downloaded stay in the cache.
+#[tauri::command]
+fn api_download_cancel(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    if request_download_stop(&id, "cancelled") {
+        return Ok(());
+    }
+    let mut job = require_download_job(&id)?;
+    remove_download_job(&id)?;
+    job.state = "cancelled".to_string();
+    job.updated_at = now_millis();
+    emit_download_progress(&app, &job);
+    Ok(())
+}
+
+const AUTO_DOWNLOAD_MAX_JOBS_DEFAULT: u64 = 2;
+const AUTO_DOWNLOAD_LOG_MAX: usize = 500;
+// Favorites with auto-download on are rescanned this often; others are scanned once.
+const AUTO_DOWNLOAD_RESCAN_SECS: i64 = 6 * 3600;
+// Rescan interval while a batch is deferred by the job or size limit.
+const AUTO_DOWNLOAD_RETRY_SECS: i64 = 30 * 60;
+
+fn load_auto_download_rule(aid: &str) -> Option<AutoDownloadRule> {
+    read_auto_download_rule_tree()
+        .ok()?
+        .get(aid.as_bytes())
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
+}
+
+fn auto_download_enabled(aid: &str) -> Result<bool, String> {
+    match load_auto_download_rule(aid) {
+        Some(rule) => Ok(rule.enabled),
+        None => Ok(config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?
+            .auto_download
+            .unwrap_or(false)),
+    }
+}
+
+fn load_auto_download_pending(aid: &str) -> Result<Option<AutoDownloadPending>, String> {
+    Ok(read_auto_download_pending_tree()?
+        .get(aid.as_bytes())
+        .map_err(|e| format!("read auto download pending failed: {e}"))?
+        .and_then(|v| serde_json::from_slice(&v).ok()))
+}
+
+fn save_auto_download_pending(aid: &str, from: &str) -> Result<(), String> {
+    let tree = read_auto_download_pending_tree()?;
+    let entry = AutoDownloadPending {
+        aid: aid.to_string(),
+        from_chapter_id: from.to_string(),
+        deferred_at: now_millis(),
+    };
+    let val = serde_json::to_vec(&entry)
+        .map_err(|e| format!("encode auto download pending failed: {e}"))?;
+    tree.insert(aid.as_bytes(), val)
+        .map_err(|e| format!("write auto download pending failed: {e}"))?;
+    let _ = tree.flush();
+    Ok(())
+}
+
+fn clear_auto_download_pending(aid: &str) {
+    if let Ok(tree) = read_auto_download_pending_tree() {
+        if matches!(tree.remove(aid.as_bytes()), Ok(Some(_))) {
+            let _ = tree.flush();
         }
+    }
+}
 
-        let env: ApiEnvelope = serde_json::from_slice(&body_bytes).map_err(|e| {
-            format!(
-                "invalid json response: status={status}, err={e}, body_preview={:?}",
-                body_preview
-            )
-        })?;
+fn record_auto_download(entry: &AutoDownloadLogEntry) {
+    logl!(
+        "[tauri][autodl] {} aid={} chapter={} job={:?} reason={:?}",
+        entry.outcome,
+        entry.aid,
+        entry.chapter_id,
+        entry.job_id,
+        entry.reason
+    );
+    let Ok(tree) = read_auto_download_log_tree() else {
+        return;
+    };
+    let key = format!("{:013}:{}:{}", entry.at, entry.aid, entry.chapter_id);
+    if let Ok(val) = serde_json::to_vec(entry) {
+        let _ = tree.insert(key.as_bytes(), val);
+    }
+    let excess = tree.len().saturating_sub(AUTO_DOWNLOAD_LOG_MAX);
+    for (k, _) in tree.iter().flatten().take(excess) {
+        let _ = tree.remove(k);
+    }
+    let _ = tree.flush();
+}
 
-        if env.code != 200 {
-            let msg = if !env.error_msg.is_empty() {
-                env.error_msg
-            } else if !env.message.is_empty() {
-                env.message
-            } else {
-                format!("request failed, code={}, http_status={}", env.code, status)
-            };
-            return Err(msg);
+// Why no more auto downloads may be queued right now, if anything.
+fn auto_download_blocked() -> Result<Option<String>, String> {
+    let (max_jobs, max_bytes) = {
+        let cfg = config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?;
+        (
+            cfg.auto_download_max_jobs.unwrap_or(AUTO_DOWNLOAD_MAX_JOBS_DEFAULT),
+            cfg.auto_download_max_bytes,
+        )
+    };
+    let pending = load_download_jobs()?
+        .iter()
+        .filter(|j| j.auto && (j.state == "queued" || j.state == "running"))
+        .count() as u64;
+    if pending >= max_jobs {
+        return Ok(Some(format!("{pending} auto downloads pending (limit {max_jobs})")));
+    }
+    if let Some(max) = max_bytes {
+        let used = load_cache_summary(&cache_summary_tree()?).total_bytes;
+        if used >= max {
+            return Ok(Some(format!("cache uses {used}B (limit {max}B)")));
         }
+    }
+    Ok(None)
+}
+
+// Queues the chapters published after `previous_latest`. An album that already has an album
+// job gets that job re-run instead, which picks the new chapters up. Returns false when a
+// limit deferred the work, so the caller retries on the next scan.
+fn auto_download_new_chapters(
+    app: &tauri::AppHandle,
+    fav: &LocalFavoriteItem,
+    album: &serde_json::Value,
+    previous_latest: &str,
+) -> Result<bool, String> {
+    let chapters = album_download_chapters(album, &fav.aid);
+    let start = chapters
+        .iter()
+        .position(|c| c.chapter_id == previous_latest)
+        .map(|i| i + 1)
+        .unwrap_or(chapters.len().saturating_sub(1));
+    let fresh = &chapters[start.min(chapters.len())..];
+    if fresh.is_empty() {
+        return Ok(true);
+    }
+    let album_job = load_download_job(&format!("album:{}", fav.aid))?;
+    let entry = |chapter: &DownloadChapter, outcome: &str| AutoDownloadLogEntry {
+        aid: fav.aid.clone(),
+        title: fav.title.clone(),
+        chapter_id: chapter.chapter_id.clone(),
+        chapter_sort: chapter.sort.clone(),
+        chapter_name: chapter.name.clone(),
+        outcome: outcome.to_string(),
+        at: now_millis(),
+        ..Default::default()
+    };
 
-        let encrypted = env
-            .data
-            .as_str()
-            .ok_or_else(|| format!("unexpected data type: {}", env.data))?;
-        let decrypted = decode_resp_data(encrypted, ts)?;
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        return serde_json::from_str(&decrypted)
-            .map_err(|e| format!("parse decrypted json failed: {e}"));
+    if album_job.is_some() {
+        if let Some(reason) = auto_download_blocked()? {
+            let mut e = entry(&fresh[fresh.len() - 1], "deferred");
+            e.reason = Some(reason);
+            record_auto_download(&e);
+            return Ok(false);
+        }
+        let res = enqueue_download(app, "album", &fav.aid, None, None, true);
+        for chapter in fresh {
+            let mut e = entry(chapter, if res.is_ok() { "queued" } else { "failed" });
+            match &res {
+                Ok(job) => e.job_id = Some(job.id.clone()),
+                Err(err) => e.reason = Some(err.clone()),
+            }
+            record_auto_download(&e);
+        }
+        return Ok(true);
     }
 
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+    for chapter in fresh {
+        let existing = load_download_job(&format!("chapter:{}", chapter.chapter_id))?;
+        if existing.is_some_and(|j| j.state != "failed") {
+            continue;
+        }
+        if let Some(reason) = auto_download_blocked()? {
+            let mut e = entry(chapter, "deferred");
+            e.reason = Some(reason);
+            record_auto_download(&e);
+            return Ok(false);
+        }
+        let mut e = entry(chapter, "queued");
+        match enqueue_download(app, "chapter", &chapter.chapter_id, Some(fav.aid.clone()), None, true) {
+            Ok(job) => e.job_id = Some(job.id),
+            Err(err) => {
+                e.outcome = "failed".to_string();
+                e.reason = Some(err);
+            }
+        }
+        record_auto_download(&e);
+    }
+    Ok(true)
 }
 
 #[tauri::command]
-async fn api_comic_page_count(
-    id: String,
-    chapter_id: Option<String>,
-    cookies: HashMap<String, String>,
-) -> Result<u64, String> {
-    let fetch_id = chapter_id.unwrap_or_else(|| id.clone());
-    let chapter = api_chapter(fetch_id, cookies).await?;
-    let page_count = chapter
-        .get("images")
-        .and_then(|v| v.as_array())
-        .map(|arr| arr.len() as u64)
-        .unwrap_or(0);
+fn api_auto_download_settings() -> Result<AutoDownloadSettings, String> {
+    let mut rules = Vec::new();
+    for item in read_auto_download_rule_tree()?.iter() {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(rule) = serde_json::from_slice::<AutoDownloadRule>(&val) {
+            rules.push(rule);
+        }
+    }
+    let cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    Ok(AutoDownloadSettings {
+        enabled: cfg.auto_download.unwrap_or(false),
+        max_jobs: cfg.auto_download_max_jobs.unwrap_or(AUTO_DOWNLOAD_MAX_JOBS_DEFAULT),
+        max_bytes: cfg.auto_download_max_bytes,
+        rules,
+    })
+}
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
+// `None` resets a value to its default (off, 2 pending jobs, no size limit).
+#[tauri::command]
+async fn api_config_set_auto_download(
+    enabled: Option<bool>,
+    max_jobs: Option<u64>,
+    max_bytes: Option<u64>,
+) -> Result<(), String> {
+    let mut cfg = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?;
+    cfg.auto_download = enabled;
+    cfg.auto_download_max_jobs = max_jobs.filter(|n| *n > 0);
+    cfg.auto_download_max_bytes = max_bytes.filter(|n| *n > 0);
+    save_config_to_disk(&cfg)?;
+    Ok(())
+}
 
-    let entry = ComicExtraEntry {
-        id,
-        page_count,
-        updated_at: now,
-    };
-    let tree = read_comic_extra_tree()?;
-    let val =
-        serde_json::to_vec(&entry).map_err(|e| format!("encode comic extra failed: {e}"))?;
-    tree.insert(entry.id.as_bytes(), val)
-        .map_err(|e| format!("write comic extra failed: {e}"))?;
+// Per-favorite switch; `enabled: None` drops the override so the global setting applies.
+#[tauri::command]
+fn api_auto_download_set_rule(aid: String, enabled: Option<bool>) -> Result<(), String> {
+    let aid = aid.trim().to_string();
+    if aid.is_empty() {
+        return Err("aid is empty".to_string());
+    }
+    let tree = read_auto_download_rule_tree()?;
+    match enabled {
+        Some(enabled) => {
+            let rule = AutoDownloadRule {
+                aid: aid.clone(),
+                enabled,
+                updated_at: now_millis(),
+            };
+            let val = serde_json::to_vec(&rule)
+                .map_err(|e| format!("encode auto download rule failed: {e}"))?;
+            tree.insert(aid.as_bytes(), val)
+                .map_err(|e| format!("write auto download rule failed: {e}"))?;
+        }
+        None => {
+            tree.remove(aid.as_bytes())
+                .map_err(|e| format!("remove auto download rule failed: {e}"))?;
+        }
+    }
     let _ = tree.flush();
-
-    Ok(page_count)
+    Ok(())
 }
 
+// Newest first.
 #[tauri::command]
-async fn api_comic_extra_get(id: String) -> Result<Option<ComicExtraEntry>, String> {
-    let tree = read_comic_extra_tree()?;
-    let Some(bytes) = tree
-        .get(id.as_bytes())
-        .map_err(|e| format!("read comic extra failed: {e}"))?
+fn api_auto_download_log(limit: Option<u64>) -> Result<Vec<AutoDownloadLogEntry>, String> {
+    let limit = limit.unwrap_or(100) as usize;
+    let mut out = Vec::new();
+    for item in read_auto_download_log_tree()?.iter().rev().take(limit) {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        if let Ok(entry) = serde_json::from_slice::<AutoDownloadLogEntry>(&val) {
+            out.push(entry);
+        }
+    }
+    Ok(out)
+}
+
+fn load_download_manifest(aid: &str) -> Result<Option<DownloadManifest>, String> {
+    let Some(val) = read_manifest_tree()?
+        .get(aid.as_bytes())
+        .map_err(|e| format!("read download manifest failed: {e}"))?
     else {
         return Ok(None);
     };
-    let entry: ComicExtraEntry =
-        serde_json::from_slice(&bytes).map_err(|e| format!("decode comic extra failed: {e}"))?;
-    Ok(Some(entry))
+    serde_json::from_slice(&val)
+        .map(Some)
+        .map_err(|e| format!("decode download manifest failed: {e}"))
 }
 
-const SCRAMBLE_220980: i64 = 220_980;
-const SCRAMBLE_268850: i64 = 268_850;
-const SCRAMBLE_421926: i64 = 421_926; // 2023-02-08 changed image split algorithm.
+fn save_download_manifest(manifest: &DownloadManifest) -> Result<(), String> {
+    let tree = read_manifest_tree()?;
+    let val =
+        serde_json::to_vec(manifest).map_err(|e| format!("encode download manifest failed: {e}"))?;
+    tree.insert(manifest.aid.as_bytes(), val)
+        .map_err(|e| format!("write download manifest failed: {e}"))?;
+    let _ = tree.flush();
+    Ok(())
+}
 
-fn parse_scramble_id(text: &str) -> Option<i64> {
-    let needle = "var scramble_id = ";
-    let start = text.find(needle)? + needle.len();
-    let tail = &text[start..];
-    let mut end = 0usize;
-    for (i, ch) in tail.char_indices() {
-        if !ch.is_ascii_digit() {
-            break;
+// Hashes the cached file of `page`; with `check_decode` an undecodable file is not recorded.
+fn manifest_page_record(
+    read_dir: &std::path::Path,
+    page: &DownloadPage,
+    check_decode: bool,
+) -> ManifestPage {
+    let mut out = ManifestPage {
+        name: page.name.clone(),
+        url: page.url.clone(),
+        num: page.num,
+        ..Default::default()
+    };
+    let Some(path) = find_cached_image(read_dir, &page_cache_key(&page.url, page.num)) else {
+        return out;
+    };
+    let Ok(bytes) = std::fs::read(&path) else {
+        return out;
+    };
+    if check_decode && validate_image_bytes(&bytes).is_err() {
+        return out;
+    }
+    out.file = path.file_name().map(|n| n.to_string_lossy().to_string());
+    out.size = bytes.len() as u64;
+    out.md5 = Some(format!("{:x}", md5::compute(&bytes)));
+    out
+}
+
+// Adds pages downloaded since the last update. Recorded hashes are never recomputed, so a
+// page that changes on disk afterwards shows up as broken instead of being re-blessed.
+fn update_download_manifest(
+    base_dir: &std::path::Path,
+    aid: &str,
+    check_decode: bool,
+) -> Result<Option<DownloadManifest>, String> {
+    let Some(source) = downloaded_source(aid)? else {
+        return Ok(None);
+    };
+    let read_dir = page_cache_dir(base_dir, "read", Some(aid));
+    let now = now_millis();
+    let mut manifest = load_download_manifest(aid)?.unwrap_or_else(|| DownloadManifest {
+        aid: aid.to_string(),
+        created_at: now,
+        ..Default::default()
+    });
+    if !source.title.is_empty() {
+        manifest.title = source.title.clone();
+    }
+    let mut previous = std::mem::take(&mut manifest.chapters);
+    for chapter in source.chapters.iter().filter(|c| c.resolved) {
+        let known = previous
+            .iter()
+            .position(|c| c.chapter_id == chapter.chapter_id)
+            .map(|i| previous.remove(i));
+        // Jobs resolved before the count was stored fall back to the chapter snapshot.
+        let expected_pages = chapter
+            .expected_pages
+            .or_else(|| {
+                load_offline_snapshot(&format!("chapter:{}", chapter.chapter_id))
+                    .ok()
+                    .flatten()
+                    .and_then(|data| chapter_api_image_count(&data))
+            })
+            .unwrap_or(chapter.pages.len() as u64);
+        let mut pages = Vec::with_capacity(chapter.pages.len());
+        for page in &chapter.pages {
+            let recorded = known.as_ref().and_then(|k| {
+                k.pages
+                    .iter()
+                    .find(|p| p.url == page.url && p.num == page.num && p.md5.is_some())
+            });
+            match recorded {
+                Some(p) => pages.push(p.clone()),
+                None if page.done || check_decode => {
+                    pages.push(manifest_page_record(&read_dir, page, check_decode))
+                }
+                None => pages.push(ManifestPage {
+                    name: page.name.clone(),
+                    url: page.url.clone(),
+                    num: page.num,
+                    ..Default::default()
+                }),
+            }
         }
-        end = i + ch.len_utf8();
+        manifest.chapters.push(ManifestChapter {
+            chapter_id: chapter.chapter_id.clone(),
+            sort: chapter.sort.clone(),
+            name: chapter.name.clone(),
+            expected_pages,
+            pages,
+        });
     }
-    if end == 0 {
+    // Chapters whose job was cancelled keep their record; their pages may still be cached.
+    manifest.chapters.append(&mut previous);
+    manifest.updated_at = now;
+    save_download_manifest(&manifest)?;
+    Ok(Some(manifest))
+}
+
+fn verify_manifest_page(read_dir: &std::path::Path, page: &ManifestPage) -> Option<(String, String)> {
+    let (Some(file), Some(md5)) = (page.file.as_deref(), page.md5.as_deref()) else {
+        return Some(("missing".to_string(), "never downloaded".to_string()));
+    };
+    let bytes = match std::fs::read(read_dir.join(file)) {
+        Ok(b) => b,
+        Err(e) => return Some(("missing".to_string(), format!("read failed: {e}"))),
+    };
+    if format!("{:x}", md5::compute(&bytes)) == md5 {
         return None;
     }
-    tail[..end].parse::<i64>().ok()
+    if (bytes.len() as u64) < page.size {
+        return Some((
+            "truncated".to_string(),
+            format!("got {}B, expected {}B", bytes.len(), page.size),
+        ));
+    }
+    match validate_image_bytes(&bytes) {
+        Err(e) => Some(("undecodable".to_string(), e)),
+        Ok(_) => Some(("mismatch".to_string(), "content hash differs".to_string())),
+    }
 }
 
-#[tauri::command]
-async fn api_chapter_scramble_id(id: String) -> Result<i64, String> {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
-
-    let token_secret = std::env::var("JM_APP_TOKEN_SECRET_2")
-        .unwrap_or_else(|_| JM_APP_TOKEN_SECRET_2_DEFAULT.into());
-    let token = md5_hex(&format!("{ts}{token_secret}"));
-    let tokenparam = format!("{ts},{JM_HEADER_VER}");
-
-    let client = http_client()?;
-    let candidates = api_base_candidates();
-    let mut last_err = None;
-
-    for (pos, (idx, base)) in candidates.iter().enumerate() {
-        let url = format!(
-            "{}/chapter_view_template/?id={}&mode=vertical&page=0&app_img_shunt=NaN",
-            base.trim_end_matches('/'),
-            id
-        );
-
-        let resp = match client
-            .get(&url)
-            .header("tokenparam", tokenparam.clone())
-            .header("token", token.clone())
-            .header(
-                "user-agent",
-                "Mozilla/5.0 (Linux; Android 7.1.2; DT1901A Build/N2G47O; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/86.0.4240.198 Mobile Safari/537.36",
-            )
-            .header("accept-encoding", "identity")
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let msg = format!("request failed: {e}");
-                last_err = Some(msg.clone());
-                if should_retry_error(&e) && pos + 1 < candidates.len() {
-                    logl!("[tauri][chapter_scramble] retry base={} err={:?}", base, msg);
-                    continue;
-                }
-                return Err(msg);
+// Albums without a manifest (downloaded before manifests existed) get one first, recording
+// only pages that still decode.
+fn verify_download(base_dir: &std::path::Path, aid: &str) -> Result<IntegrityReport, String> {
+    let started = Instant::now();
+    let manifest = match load_download_manifest(aid)? {
+        Some(m) => m,
+        None => update_download_manifest(base_dir, aid, true)?
+            .ok_or_else(|| format!("no download for {aid}"))?,
+    };
+    let read_dir = page_cache_dir(base_dir, "read", Some(aid));
+    let mut report = IntegrityReport {
+        aid: aid.to_string(),
+        title: manifest.title.clone(),
+        chapters: manifest.chapters.len() as u64,
+        ..Default::default()
+    };
+    for chapter in &manifest.chapters {
+        report.expected_pages += chapter.expected_pages;
+        if chapter.pages.len() as u64 != chapter.expected_pages {
+            report.issues.push(IntegrityIssue {
+                chapter_id: chapter.chapter_id.clone(),
+                index: chapter.pages.len() as u64,
+                problem: "count".to_string(),
+                detail: Some(format!(
+                    "{} pages listed, chapter has {}",
+                    chapter.pages.len(),
+                    chapter.expected_pages
+                )),
+                ..Default::default()
+            });
+        }
+        for (index, page) in chapter.pages.iter().enumerate() {
+            report.checked_pages += 1;
+            match verify_manifest_page(&read_dir, page) {
+                None => report.ok_pages += 1,
+                Some((problem, detail)) => report.issues.push(IntegrityIssue {
+                    chapter_id: chapter.chapter_id.clone(),
+                    index: index as u64,
+                    name: page.name.clone(),
+                    problem,
+                    detail: Some(detail),
+                }),
             }
+        }
+    }
+    report.elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(report)
+}
+
+// Chapters with a short listing are fetched again and listed in full; their pages are
+// then checked anew, so pages the listing missed show up as missing.
+async fn relist_short_chapters(
+    aid: &str,
+    read_dir: &std::path::Path,
+    manifest: &mut DownloadManifest,
+    report: &mut IntegrityReport,
+) -> Result<HashMap<String, DownloadChapter>, String> {
+    let mut relisted = HashMap::new();
+    let short: Vec<String> = report
+        .issues
+        .iter()
+        .filter(|i| i.problem == "count")
+        .map(|i| i.chapter_id.clone())
+        .collect();
+    if short.is_empty() {
+        return Ok(relisted);
+    }
+    let cookies = config_state()
+        .lock()
+        .map_err(|_| "config lock poisoned".to_string())?
+        .session_cookies
+        .clone();
+    let img_base = downloaded_source(aid)?.and_then(|s| s.img_base);
+    for chapter_id in short {
+        let mut fresh = DownloadChapter {
+            chapter_id: chapter_id.clone(),
+            ..Default::default()
         };
-
-        let status = resp.status();
-        if !status.is_success() && should_retry_status(status) && pos + 1 < candidates.len() {
-            let msg = format!("http status {status}");
-            last_err = Some(msg.clone());
-            logl!("[tauri][chapter_scramble] retry base={} status={}", base, status);
+        if let Err(e) = resolve_download_chapter(&mut fresh, img_base.as_deref(), &cookies).await {
+            logl!("[tauri][integrity] relist failed aid={} chapter={} err={}", aid, chapter_id, e);
+            if let Some(issue) = report
+                .issues
+                .iter_mut()
+                .find(|i| i.problem == "count" && i.chapter_id == chapter_id)
+            {
+                issue.detail = Some(e);
+            }
             continue;
         }
+        let Some(chapter) = manifest.chapters.iter_mut().find(|c| c.chapter_id == chapter_id) else {
+            continue;
+        };
+        let mut old = std::mem::take(&mut chapter.pages);
+        let old_len = old.len() as u64;
+        let old_expected = chapter.expected_pages;
+        chapter.pages = fresh
+            .pages
+            .iter()
+            .map(|p| {
+                old.iter()
+                    .position(|o| o.url == p.url && o.num == p.num)
+                    .map(|i| old.remove(i))
+                    .unwrap_or_else(|| ManifestPage {
+                        name: p.name.clone(),
+                        url: p.url.clone(),
+                        num: p.num,
+                        ..Default::default()
+                    })
+            })
+            .collect();
+        chapter.expected_pages = fresh.expected_pages.unwrap_or(chapter.pages.len() as u64);
 
-        let text = resp
-            .text()
-            .await
-            .map_err(|e| format!("failed reading response text: {e}"))?;
-
-        if !status.is_success() {
-            return Err(format!(
-                "http error: {}, body_preview={:?}",
-                status,
-                &text.chars().take(200).collect::<String>()
-            ));
+        let old_issues = report
+            .issues
+            .iter()
+            .filter(|i| i.chapter_id == chapter_id && i.problem != "count")
+            .count() as u64;
+        // The count issue goes too: the fresh listing is the chapter's full page list.
+        report.issues.retain(|i| i.chapter_id != chapter_id);
+        let pages = chapter.pages.clone();
+        let dir = read_dir.to_path_buf();
+        let checked = tauri::async_runtime::spawn_blocking(move || {
+            pages
+                .iter()
+                .map(|p| verify_manifest_page(&dir, p))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|e| format!("verify task failed: {e}"))?;
+        let mut new_issues = 0;
+        for (index, (page, res)) in chapter.pages.iter().zip(checked).enumerate() {
+            let Some((problem, detail)) = res else {
+                continue;
+            };
+            new_issues += 1;
+            report.issues.push(IntegrityIssue {
+                chapter_id: chapter_id.clone(),
+                index: index as u64,
+                name: page.name.clone(),
+                problem,
+                detail: Some(detail),
+            });
         }
-
-        if let Some(v) = parse_scramble_id(&text) {
-            JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-            return Ok(v);
+        let new_len = chapter.pages.len() as u64;
+        report.expected_pages = report.expected_pages - old_expected + chapter.expected_pages;
+        report.checked_pages = report.checked_pages - old_len + new_len;
+        report.ok_pages = report.ok_pages - (old_len - old_issues) + (new_len - new_issues);
+        relisted.insert(chapter_id, fresh);
+    }
+    Ok(relisted)
+}
+
+// Re-fetches the pages listed in `report` and records their new hashes; download jobs of
+// the album get the pages marked done so exports and the library mirror see them. A page
+// is only replaced once its new copy is in; a failed fetch keeps the old file.
+async fn repair_download(
+    app: &tauri::AppHandle,
+    base_dir: &std::path::Path,
+    report: &mut IntegrityReport,
+) -> Result<(), String> {
+    let aid = report.aid.clone();
+    let Some(mut manifest) = load_download_manifest(&aid)? else {
+        return Ok(());
+    };
+    let read_dir = page_cache_dir(base_dir, "read", Some(&aid));
+    let relisted = relist_short_chapters(&aid, &read_dir, &mut manifest, report).await?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let total = report.issues.iter().filter(|i| i.problem != "count").count() as u64;
+    let mut progress = IntegrityProgress {
+        aid: aid.clone(),
+        phase: "repair".to_string(),
+        total,
+        ..Default::default()
+    };
+    let mut repaired = Vec::new();
+    let mut remaining = Vec::new();
+    for mut issue in std::mem::take(&mut report.issues) {
+        let page = manifest
+            .chapters
+            .iter_mut()
+            .find(|c| c.chapter_id == issue.chapter_id)
+            .and_then(|c| c.pages.get_mut(issue.index as usize));
+        let Some(page) = page else {
+            remaining.push(issue);
+            continue;
+        };
+        // Move the old copy out of the cache key so the page is fetched again, and keep it
+        // until the new one is in.
+        let old = page
+            .file
+            .as_deref()
+            .map(|f| read_dir.join(f))
+            .filter(|p| p.exists());
+        let backup = old.as_ref().map(|p| p.with_extension("repairing"));
+        if let (Some(old), Some(backup)) = (&old, &backup) {
+            if let Err(e) = std::fs::rename(old, backup) {
+                issue.detail = Some(format!("set aside old copy failed: {e}"));
+                remaining.push(issue);
+                continue;
+            }
+        }
+        let res = download_page(base_dir, &page.url, page.num, &aid, &stop, Transfer::Interactive).await;
+        let fetched = DownloadPage {
+            name: page.name.clone(),
+            url: page.url.clone(),
+            num: page.num,
+            ..Default::default()
+        };
+        let record = res.map(|_| manifest_page_record(&read_dir, &fetched, false));
+        let replaced = matches!(&record, Ok(r) if r.md5.is_some());
+        if let (Some(old), Some(backup)) = (&old, &backup) {
+            if replaced {
+                let _ = std::fs::remove_file(backup);
+                if !old.exists() {
+                    // The new copy was saved under another extension.
+                    cache_index_remove(old);
+                }
+            } else if let Err(e) = std::fs::rename(backup, old) {
+                logl!("[tauri][integrity] restore {:?} failed: {}", old, e);
+            }
+        }
+        match record {
+            Ok(record) if replaced => {
+                *page = record;
+                repaired.push((page.url.clone(), page.num));
+            }
+            Ok(_) => {
+                issue.detail = Some("page vanished after download".to_string());
+                remaining.push(issue);
+            }
+            Err(e) => {
+                logl!("[tauri][integrity] repair failed aid={} url={:?} err={}", aid, page.url, e);
+                issue.detail = Some(e);
+                remaining.push(issue);
+            }
+        }
+        progress.done += 1;
+        let _ = app.emit("integrity-progress", &progress);
+    }
+    report.issues = remaining;
+    report.repaired_pages = repaired.len() as u64;
+    report.ok_pages += report.repaired_pages;
+    if repaired.is_empty() && relisted.is_empty() {
+        return Ok(());
+    }
+    manifest.updated_at = now_millis();
+    save_download_manifest(&manifest)?;
+    for mut job in load_download_jobs()?.into_iter().filter(|j| j.aid == aid) {
+        let mut changed = false;
+        for chapter in job.chapters.iter_mut() {
+            let Some(fresh) = relisted.get(&chapter.chapter_id) else {
+                continue;
+            };
+            let old = std::mem::take(&mut chapter.pages);
+            chapter.pages = fresh
+                .pages
+                .iter()
+                .map(|p| DownloadPage {
+                    done: old.iter().any(|o| o.url == p.url && o.num == p.num && o.done),
+                    ..p.clone()
+                })
+                .collect();
+            chapter.expected_pages = fresh.expected_pages;
+            chapter.resolved = true;
+            changed = true;
+        }
+        for page in job.chapters.iter_mut().flat_map(|c| c.pages.iter_mut()) {
+            if !page.done && repaired.iter().any(|(u, n)| *u == page.url && *n == page.num) {
+                page.done = true;
+                page.error = None;
+                changed = true;
+            }
+        }
+        if changed {
+            job.recount();
+            if job.state == "failed" && job.failed_pages == 0 && job.done_pages == job.total_pages {
+                job.state = "done".to_string();
+                job.error = None;
+            }
+            job.updated_at = now_millis();
+            save_download_job(&job)?;
+            emit_download_progress(app, &job);
         }
-
-        logl!(
-            "[tauri][chapter_scramble] failed parse scramble_id base={:?} body_preview={:?}",
-            base,
-            text.chars().take(300).collect::<String>()
-        );
-        JM_API_BASE_INDEX.store(*idx, Ordering::Relaxed);
-        return Ok(SCRAMBLE_220980);
     }
-
-    Err(last_err.unwrap_or_else(|| "request failed".to_string()))
+    Ok(())
 }
 
-fn get_segmentation_num(eps_id: i64, scramble_id: i64, picture_name: &str) -> i64 {
-    if eps_id < scramble_id {
-        return 0;
+fn integrity_targets(aid: Option<String>) -> Result<Vec<String>, String> {
+    if let Some(aid) = aid.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()) {
+        return Ok(vec![aid]);
     }
-    if eps_id < SCRAMBLE_268850 {
-        return 10;
+    let mut aids: Vec<String> = load_download_jobs()?.into_iter().map(|j| j.aid).collect();
+    for item in read_manifest_tree()?.iter() {
+        let (key, _) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        aids.push(String::from_utf8_lossy(&key).to_string());
     }
+    aids.sort();
+    aids.dedup();
+    Ok(aids)
+}
 
-    let digest = md5_hex(&format!("{eps_id}{picture_name}"));
-    let last = digest.as_bytes().last().copied().unwrap_or(b'0') as i64; // ord(hex[-1])
-
-    if eps_id > SCRAMBLE_421926 {
-        let mut num = last % 8;
-        num = num * 2 + 2;
-        num
-    } else {
-        let mut num = last % 10;
-        num = num * 2 + 2;
-        num
+async fn verify_downloads(app: &tauri::AppHandle, aids: Vec<String>) -> Result<Vec<IntegrityReport>, String> {
+    let base_dir = resolve_read_cache_dir(app)?;
+    let mut progress = IntegrityProgress {
+        phase: "verify".to_string(),
+        total: aids.len() as u64,
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    for aid in aids {
+        let base = base_dir.clone();
+        let target = aid.clone();
+        let res = tauri::async_runtime::spawn_blocking(move || verify_download(&base, &target))
+            .await
+            .map_err(|e| format!("verify task failed: {e}"))?;
+        match res {
+            Ok(report) => {
+                logl!(
+                    "[tauri][integrity] verified aid={} ok={}/{} issues={} cost_ms={}",
+                    aid,
+                    report.ok_pages,
+                    report.checked_pages,
+                    report.issues.len(),
+                    report.elapsed_ms
+                );
+                out.push(report);
+            }
+            Err(e) => {
+                logl!("[tauri][integrity] verify failed aid={} err={}", aid, e);
+            }
+        }
+        progress.aid = aid;
+        progress.done += 1;
+        let _ = app.emit("integrity-progress", &progress);
     }
+    Ok(out)
 }
 
+// Checks every page of downloaded albums (or just `aid`) against its manifest.
 #[tauri::command]
-async fn api_segmentation_nums(
-    eps_id: String,
-    scramble_id: i64,
-    picture_names: Vec<String>,
-) -> Result<Vec<i64>, String> {
-    let eps = eps_id
-        .parse::<i64>()
-        .map_err(|_| "invalid eps_id".to_string())?;
-    Ok(picture_names
-        .iter()
-        .map(|name| get_segmentation_num(eps, scramble_id, name))
-        .collect())
+async fn api_download_verify(
+    app: tauri::AppHandle,
+    aid: Option<String>,
+) -> Result<Vec<IntegrityReport>, String> {
+    let aids = integrity_targets(aid)?;
+    verify_downloads(&app, aids).await
 }
 
-fn mime_from_format(fmt: ImageFormat) -> &'static str {
-    match fmt {
-        ImageFormat::Jpeg => "image/jpeg",
-        ImageFormat::Png => "image/png",
-        ImageFormat::WebP => "image/webp",
-        _ => "application/octet-stream",
+// Verifies, then re-fetches only the broken pages; the returned issues are what is still broken.
+#[tauri::command]
+async fn api_download_repair(
+    app: tauri::AppHandle,
+    aid: Option<String>,
+) -> Result<Vec<IntegrityReport>, String> {
+    let running: Vec<String> = load_download_jobs()?
+        .into_iter()
+        .filter(|j| j.state == "running")
+        .map(|j| j.aid)
+        .collect();
+    let aids: Vec<String> = integrity_targets(aid)?
+        .into_iter()
+        .filter(|a| !running.contains(a))
+        .collect();
+    let base_dir = resolve_read_cache_dir(&app)?;
+    let mut reports = verify_downloads(&app, aids).await?;
+    for report in reports.iter_mut().filter(|r| !r.issues.is_empty()) {
+        let started = Instant::now();
+        if let Err(e) = repair_download(&app, &base_dir, report).await {
+            logl!("[tauri][integrity] repair failed aid={} err={}", report.aid, e);
+            report.issues.push(IntegrityIssue {
+                problem: "repair".to_string(),
+                detail: Some(e),
+                ..Default::default()
+            });
+        }
+        report.elapsed_ms += started.elapsed().as_millis() as u64;
+        logl!(
+            "[tauri][integrity] repaired aid={} pages={} remaining={}",
+            report.aid,
+            report.repaired_pages,
+            report.issues.len()
+        );
     }
+    Ok(reports)
 }
 
-fn descramble_image_bytes(bytes: &[u8], num: i64) -> Result<(Vec<u8>, ImageFormat), String> {
-    descramble_image_bytes_with_cancel(bytes, num, None)
+fn emit_export_progress(app: &tauri::AppHandle, progress: &ExportProgress) {
+    let _ = app.emit("export-progress", progress);
 }
 
-fn descramble_image_bytes_with_cancel(
-    bytes: &[u8],
-    num: i64,
-    cancel: Option<&AtomicBool>,
-) -> Result<(Vec<u8>, ImageFormat), String> {
-    let started = Instant::now();
-    if num <= 1 {
-        let fmt = image::guess_format(bytes).unwrap_or(ImageFormat::Jpeg);
-        return Ok((bytes.to_vec(), fmt));
+// Keeps non-ASCII titles readable while dropping characters file systems reject.
+fn sanitize_file_name(s: &str) -> String {
+    let cleaned: String = s
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let cut: String = cleaned.trim().chars().take(120).collect();
+    let cut = cut.trim().trim_end_matches('.').trim();
+    if cut.is_empty() {
+        "unknown".to_string()
+    } else {
+        cut.to_string()
     }
+}
 
-    let fmt_in = image::guess_format(bytes).unwrap_or(ImageFormat::Jpeg);
-    let img = image::load_from_memory(bytes).map_err(|e| format!("decode image failed: {e}"))?;
-
-    let rgba = img.to_rgba8();
-    let (w, h) = rgba.dimensions();
-    let num_u32 = num as u32;
-    if num_u32 <= 1 || h == 0 || w == 0 {
-        return Ok((bytes.to_vec(), fmt_in));
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if c.is_control() && !matches!(c, '\n' | '\t') => {}
+            c => out.push(c),
+        }
     }
+    out
+}
+
+fn export_output_dir(dir: Option<String>) -> Result<std::path::PathBuf, String> {
+    let dir = match dir.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()) {
+        Some(d) => std::path::PathBuf::from(d),
+        None => resolve_data_dir()?.join("exports"),
+    };
+    std::fs::create_dir_all(&dir).map_err(|e| format!("mkdir failed: {e}"))?;
+    Ok(dir)
+}
 
-    let rem = h % num_u32;
-    let copy_height = h / num_u32;
+fn export_file_stem(plan: &ExportPlan) -> String {
+    let title = if plan.title.trim().is_empty() { &plan.aid } else { &plan.title };
+    format!("{} [{}]", sanitize_file_name(title), plan.aid)
+}
 
-    let mut blocks: Vec<(u32, u32)> = Vec::with_capacity(num_u32 as usize);
-    let mut total_h = 0u32;
-    for i in 0..num_u32 {
-        let mut end = copy_height * (i + 1);
-        if i == num_u32 - 1 {
-            end += rem;
+// The chapter's `sort` when the API gave one, else its position in the album.
+fn export_chapter_number(chapter: &ExportChapter, index: usize) -> String {
+    chapter
+        .sort
+        .clone()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| (index + 1).to_string())
+}
+
+// Zero-padded for file names so chapters sort correctly.
+fn export_chapter_label(chapter: &ExportChapter, index: usize) -> String {
+    let number = export_chapter_number(chapter, index);
+    match number.parse::<u64>() {
+        Ok(n) => format!("{n:03}"),
+        Err(_) => sanitize_file_name(&number),
+    }
+}
+
+// Everything downloaded for `aid`: the album job (whose chapter order wins) merged with
+// any chapter jobs.
+fn downloaded_source(aid: &str) -> Result<Option<DownloadJob>, String> {
+    let (albums, chapters): (Vec<DownloadJob>, Vec<DownloadJob>) = load_download_jobs()?
+        .into_iter()
+        .filter(|j| j.aid == aid)
+        .partition(|j| j.kind == "album");
+    let mut source: Option<DownloadJob> = None;
+    for job in albums.into_iter().chain(chapters) {
+        let Some(s) = source.as_mut() else {
+            source = Some(job);
+            continue;
+        };
+        if s.title.is_empty() {
+            s.title = job.title;
+        }
+        if s.authors.is_empty() && s.tags.is_empty() {
+            s.authors = job.authors;
+            s.tags = job.tags;
+            s.description = job.description;
+        }
+        for chapter in job.chapters.into_iter().filter(|c| c.resolved) {
+            match s.chapters.iter_mut().find(|c| c.chapter_id == chapter.chapter_id) {
+                Some(known) if !known.resolved => {
+                    known.pages = chapter.pages;
+                    known.expected_pages = chapter.expected_pages;
+                    known.resolved = true;
+                }
+                Some(_) => {}
+                None => s.chapters.push(chapter),
+            }
         }
-        blocks.push((total_h, end));
-        total_h = end;
     }
+    Ok(source)
+}
+
+// Without an album job the chapter list is partial, so it only stands in for the album
+// when specific chapters were asked for.
+fn local_export_source(aid: &str, chapter_ids: &[String]) -> Result<Option<DownloadJob>, String> {
+    Ok(downloaded_source(aid)?.filter(|s| s.kind == "album" || !chapter_ids.is_empty()))
+}
+
+// Resolves the chapters to export and makes sure every page is in the read cache.
+// When earlier downloads cover the selection and its pages are cached, no network is used.
+async fn prepare_export(
+    app: &tauri::AppHandle,
+    aid: &str,
+    chapter_ids: &[String],
+    format: &str,
+) -> Result<ExportPlan, String> {
+    let base_dir = resolve_read_cache_dir(app)?;
+    let read_dir = page_cache_dir(&base_dir, "read", Some(aid));
+    let wanted = |id: &str| chapter_ids.is_empty() || chapter_ids.iter().any(|c| c == id);
+    let is_cached =
+        |p: &DownloadPage| find_cached_image(&read_dir, &page_cache_key(&p.url, p.num)).is_some();
+    let mut progress = ExportProgress {
+        aid: aid.to_string(),
+        format: format.to_string(),
+        phase: "resolve".to_string(),
+        ..Default::default()
+    };
+    emit_export_progress(app, &progress);
 
-    let src = rgba.as_raw();
-    let mut dst = vec![0u8; src.len()];
-    let row_bytes = (w * 4) as usize;
-
-    let mut y = 0u32;
-    for (start, end) in blocks.into_iter().rev() {
-        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
-            return Err("cancelled".to_string());
-        }
-        let seg_h = end.saturating_sub(start);
-        for dy in 0..seg_h {
-            if dy % 64 == 0 && cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
-                return Err("cancelled".to_string());
+    let mut source = local_export_source(aid, chapter_ids)?.unwrap_or_else(|| DownloadJob {
+        aid: aid.to_string(),
+        ..Default::default()
+    });
+    // An album job lists every chapter; chapter jobs alone do not.
+    let mut series_count = (source.kind == "album").then_some(source.chapters.len());
+    let offline_ready = {
+        let mut selected = source.chapters.iter().filter(|c| wanted(&c.chapter_id)).peekable();
+        selected.peek().is_some() && selected.all(|c| c.resolved && c.pages.iter().all(is_cached))
+    };
+    if !offline_ready {
+        let cookies = config_state()
+            .lock()
+            .map_err(|_| "config lock poisoned".to_string())?
+            .session_cookies
+            .clone();
+        match api_album(aid.to_string(), cookies.clone()).await {
+            Ok(album) => {
+                apply_album_meta(&mut source, &album);
+                let known: HashMap<String, DownloadChapter> = source
+                    .chapters
+                    .drain(..)
+                    .filter(|c| c.resolved)
+                    .map(|c| (c.chapter_id.clone(), c))
+                    .collect();
+                source.chapters = album_download_chapters(&album, aid)
+                    .into_iter()
+                    .map(|c| known.get(&c.chapter_id).cloned().unwrap_or(c))
+                    .collect();
+                series_count = Some(source.chapters.len());
             }
-            let src_y = start + dy;
-            let dst_y = y + dy;
-            let src_off = (src_y as usize) * row_bytes;
-            let dst_off = (dst_y as usize) * row_bytes;
-            dst[dst_off..dst_off + row_bytes].copy_from_slice(&src[src_off..src_off + row_bytes]);
+            Err(e) if !source.chapters.is_empty() => {
+                logl!("[tauri][export] album fetch failed, using download job aid={} err={}", aid, e);
+            }
+            Err(e) => return Err(e),
+        }
+        let img_base = source.img_base.clone();
+        for chapter in source
+            .chapters
+            .iter_mut()
+            .filter(|c| wanted(&c.chapter_id) && !c.resolved)
+        {
+            resolve_download_chapter(chapter, img_base.as_deref(), &cookies).await?;
         }
-        y += seg_h;
     }
 
-    let out_rgba = image::RgbaImage::from_raw(w, h, dst).ok_or("image buffer create failed")?;
+    let selected: Vec<DownloadChapter> = source
+        .chapters
+        .iter()
+        .filter(|c| wanted(&c.chapter_id))
+        .cloned()
+        .collect();
+    if selected.is_empty() {
+        return Err("no chapters to export".to_string());
+    }
 
-    let fmt_out = if fmt_in == ImageFormat::Jpeg {
-        ImageFormat::Jpeg
-    } else {
-        ImageFormat::Png
-    };
+    let missing: Vec<(usize, usize)> = selected
+        .iter()
+        .enumerate()
+        .flat_map(|(ci, c)| {
+            c.pages
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !is_cached(p))
+                .map(move |(pi, _)| (ci, pi))
+        })
+        .collect();
+    if !missing.is_empty() {
+        progress.phase = "download".to_string();
+        progress.total = missing.len() as u64;
+        emit_export_progress(app, &progress);
+        let stop = Arc::new(AtomicBool::new(false));
+        let limit = Arc::new(tokio::sync::Semaphore::new(DOWNLOAD_PAGE_CONCURRENCY));
+        let mut tasks = Vec::with_capacity(missing.len());
+        for &(ci, pi) in &missing {
+            let page = &selected[ci].pages[pi];
+            let (url, num) = (page.url.clone(), page.num);
+            let (aid, base_dir) = (aid.to_string(), base_dir.clone());
+            let (limit, stop) = (limit.clone(), stop.clone());
+            tasks.push(tauri::async_runtime::spawn(async move {
+                let _permit = limit
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| format!("semaphore closed: {e}"))?;
+                download_page(&base_dir, &url, num, &aid, &stop, Transfer::Interactive).await
+            }));
+        }
+        let mut failed = 0;
+        for task in tasks {
+            match task.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    failed += 1;
+                    if is_disk_space_low(&e) {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                Err(e) => {
+                    failed += 1;
+                    logl!("[tauri][export] page task failed: {e}");
+                }
+            }
+            progress.done += 1;
+            emit_export_progress(app, &progress);
+        }
+        if failed > 0 {
+            return Err(format!("{failed} pages could not be downloaded"));
+        }
+    }
 
-    if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
-        return Err("cancelled".to_string());
+    let mut chapters = Vec::with_capacity(selected.len());
+    for chapter in selected {
+        let mut pages = Vec::with_capacity(chapter.pages.len());
+        for page in &chapter.pages {
+            let path = find_cached_image(&read_dir, &page_cache_key(&page.url, page.num))
+                .ok_or_else(|| format!("page missing from cache: {}", page.url))?;
+            pages.push(path);
+        }
+        chapters.push(ExportChapter {
+            sort: chapter.sort,
+            name: chapter.name,
+            pages,
+        });
     }
+    Ok(ExportPlan {
+        aid: aid.to_string(),
+        title: source.title,
+        authors: source.authors,
+        tags: source.tags,
+        description: source.description,
+        chapters,
+        series_count,
+        downloaded: missing.len() as u64,
+    })
+}
 
-    let mut out_bytes = Vec::new();
-    if fmt_out == ImageFormat::Jpeg {
-        let rgb = image::DynamicImage::ImageRgba8(out_rgba).to_rgb8();
-        image::DynamicImage::ImageRgb8(rgb)
-            .write_to(&mut Cursor::new(&mut out_bytes), fmt_out)
-            .map_err(|e| format!("encode image failed: {e}"))?;
-    } else {
-        image::DynamicImage::ImageRgba8(out_rgba)
-            .write_to(&mut Cursor::new(&mut out_bytes), fmt_out)
-            .map_err(|e| format!("encode image failed: {e}"))?;
+// `chapter` is set for per-chapter archives. `Count` is the series total and is left out
+// when only some chapters are known.
+fn comic_info_xml(plan: &ExportPlan, chapter: Option<(usize, &ExportChapter)>, page_count: usize) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ComicInfo xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n",
+    );
+    let mut field = |name: &str, value: &str| {
+        if !value.trim().is_empty() {
+            xml.push_str(&format!("  <{name}>{}</{name}>\n", xml_escape(value.trim())));
+        }
+    };
+    match chapter {
+        Some((index, c)) => {
+            field("Title", c.name.as_deref().unwrap_or(&plan.title));
+            field("Series", &plan.title);
+            field("Number", &export_chapter_number(c, index));
+        }
+        None => {
+            field("Title", &plan.title);
+            field("Series", &plan.title);
+        }
     }
+    if let Some(count) = plan.series_count {
+        field("Count", &count.to_string());
+    }
+    field("Summary", &plan.description);
+    field("Writer", &plan.authors.join(", "));
+    field("Tags", &plan.tags.join(","));
+    field("PageCount", &page_count.to_string());
+    field("Web", &format!("{}/album/{}/", web_base_from_opt(None), plan.aid));
+    xml.push_str("</ComicInfo>\n");
+    xml
+}
+
+fn write_cbz(path: &std::path::Path, pages: &[&std::path::PathBuf], comic_info: &str) -> Result<u64, String> {
+    let tmp = path.with_extension("cbz.part");
+    let res = (|| {
+        let file = std::fs::File::create(&tmp).map_err(|e| format!("create cbz failed: {e}"))?;
+        let mut zip = zip::ZipWriter::new(file);
+        // Pages are already compressed images.
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let width = pages.len().to_string().len().max(3);
+        for (i, page) in pages.iter().enumerate() {
+            let ext = page.extension().and_then(|e| e.to_str()).unwrap_or("jpg");
+            zip.start_file(format!("{:0width$}.{ext}", i + 1), options)
+                .map_err(|e| format!("zip start file failed: {e}"))?;
+            let mut f = std::fs::File::open(page).map_err(|e| format!("open page failed: {e}"))?;
+            std::io::copy(&mut f, &mut zip).map_err(|e| format!("zip write file failed: {e}"))?;
+        }
+        zip.start_file("ComicInfo.xml", options)
+            .map_err(|e| format!("zip start file failed: {e}"))?;
+        zip.write_all(comic_info.as_bytes())
+            .map_err(|e| format!("zip write file failed: {e}"))?;
+        zip.finish().map_err(|e| format!("zip finish failed: {e}"))?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("commit cbz failed: {e}"))?;
+        std::fs::metadata(path)
+            .map(|m| m.len())
+            .map_err(|e| format!("stat cbz failed: {e}"))
+    })();
+    if res.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    res
+}
+
+fn export_pages_bytes(plan: &ExportPlan) -> u64 {
+    plan.chapters
+        .iter()
+        .flat_map(|c| c.pages.iter())
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum()
+}
 
+// One CBZ for the selection, or one per chapter with `per_chapter`; written to `dir`
+// (default `<data>/exports`). Empty `chapter_ids` means every chapter of the album.
+#[tauri::command]
+async fn api_export_cbz(
+    app: tauri::AppHandle,
+    aid: String,
+    chapter_ids: Option<Vec<String>>,
+    per_chapter: Option<bool>,
+    dir: Option<String>,
+) -> Result<ComicExportResult, String> {
+    let started = Instant::now();
+    let aid = aid.trim().to_string();
+    if aid.is_empty() {
+        return Err("aid is empty".to_string());
+    }
+    let out_dir = export_output_dir(dir)?;
+    let plan = prepare_export(&app, &aid, &chapter_ids.unwrap_or_default(), "cbz").await?;
+    ensure_write_space(&out_dir, export_pages_bytes(&plan))?;
+
+    let per_chapter = per_chapter.unwrap_or(false);
+    let chapters = plan.chapters.len() as u64;
+    let pages = plan.chapters.iter().map(|c| c.pages.len() as u64).sum();
+    let downloaded_pages = plan.downloaded;
+    let handle = app.clone();
+    let (files, bytes) = tauri::async_runtime::spawn_blocking(move || {
+        let stem = export_file_stem(&plan);
+        let mut progress = ExportProgress {
+            aid: plan.aid.clone(),
+            format: "cbz".to_string(),
+            phase: "write".to_string(),
+            done: 0,
+            total: if per_chapter { plan.chapters.len() as u64 } else { 1 },
+        };
+        emit_export_progress(&handle, &progress);
+        let mut files = Vec::new();
+        let mut bytes = 0;
+        if per_chapter {
+            for (index, chapter) in plan.chapters.iter().enumerate() {
+                let path = out_dir.join(format!(
+                    "{stem} - Chapter {}.cbz",
+                    export_chapter_label(chapter, index)
+                ));
+                let pages: Vec<&std::path::PathBuf> = chapter.pages.iter().collect();
+                let info = comic_info_xml(&plan, Some((index, chapter)), pages.len());
+                bytes += write_cbz(&path, &pages, &info)?;
+                files.push(path.to_string_lossy().to_string());
+                progress.done += 1;
+                emit_export_progress(&handle, &progress);
+            }
+        } else {
+            let path = out_dir.join(format!("{stem}.cbz"));
+            let pages: Vec<&std::path::PathBuf> =
+                plan.chapters.iter().flat_map(|c| c.pages.iter()).collect();
+            let info = match plan.chapters.as_slice() {
+                [only] => comic_info_xml(&plan, Some((0, only)), pages.len()),
+                _ => comic_info_xml(&plan, None, pages.len()),
+            };
+            bytes += write_cbz(&path, &pages, &info)?;
+            files.push(path.to_string_lossy().to_string());
+            progress.done = 1;
+        }
+        progress.phase = "done".to_string();
+        emit_export_progress(&handle, &progress);
+        Ok::<_, String>((files, bytes))
+    })
+    .await
+    .map_err(|e| format!("cbz export task failed: {e}"))??;
+
+    let result = ComicExportResult {
+        files,
+        chapters,
+        pages,
+        bytes,
+        downloaded_pages,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
     logl!(
-        "[tauri][img] descramble done num={} fmt_in={:?} fmt_out={:?} in={}B out={}B cost_ms={}",
-        num,
-        fmt_in,
-        fmt_out,
-        bytes.len(),
-        out_bytes.len(),
-        started.elapsed().as_millis()
+        "[tauri][export] cbz aid={} files={} pages={} downloaded={} bytes={} cost_ms={}",
+        aid,
+        result.files.len(),
+        result.pages,
+        result.downloaded_pages,
+        result.bytes,
+        result.elapsed_ms
     );
-
-    Ok((out_bytes, fmt_out))
+    Ok(result)
 }
 
-fn sanitize_path_component(s: &str) -> String {
-    let filtered: String = s
-        .chars()
-        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
-        .collect();
-    if filtered.is_empty() {
-        "unknown".to_string()
-    } else {
-        filtered
+// Matches `formatChapterTitle` in ReadingPage.tsx.
+fn export_chapter_title(chapter: &ExportChapter, index: usize) -> String {
+    let number = export_chapter_number(chapter, index);
+    match chapter.name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => format!("第{number}话：{name}"),
+        None => format!("第{number}话"),
     }
 }
 
-fn resolve_read_cache_dir<R: tauri::Runtime>(_app: &tauri::AppHandle<R>) -> Result<std::path::PathBuf, String> {
-    // Always store cache alongside the executable (cross-platform, easy for user to locate).
-    // Env override for debugging/testing.
-    if let Ok(dir) = std::env::var("JM_CACHE_DIR") {
-        let base = std::path::PathBuf::from(dir);
-        let p = base.join("jmcomic-cache");
-        std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
-        return Ok(p);
-    }
+const EPUB_CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
 
-    let exe_dir = std::env::current_exe()
-        .map_err(|e| format!("current exe error: {e}"))?
-        .parent()
-        .ok_or_else(|| "current exe has no parent dir".to_string())?
-        .to_path_buf();
+// Fixed-layout EPUB 3: one XHTML page per image with a viewport of the image's size.
+fn write_epub(
+    path: &std::path::Path,
+    plan: &ExportPlan,
+    rtl: bool,
+    on_page: &mut dyn FnMut(),
+) -> Result<u64, String> {
+    let tmp = path.with_extension("epub.part");
+    let res = (|| {
+        let file = std::fs::File::create(&tmp).map_err(|e| format!("create epub failed: {e}"))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let stored = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let deflated =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let put = |zip: &mut zip::ZipWriter<std::fs::File>,
+                       name: &str,
+                       options: zip::write::FileOptions,
+                       bytes: &[u8]| {
+            zip.start_file(name, options)
+                .map_err(|e| format!("zip start file failed: {e}"))?;
+            zip.write_all(bytes)
+                .map_err(|e| format!("zip write file failed: {e}"))
+        };
+        // `mimetype` must be the first entry and uncompressed.
+        put(&mut zip, "mimetype", stored, b"application/epub+zip")?;
+        put(&mut zip, "META-INF/container.xml", deflated, EPUB_CONTAINER_XML.as_bytes())?;
+
+        let title = xml_escape(if plan.title.trim().is_empty() { &plan.aid } else { plan.title.trim() });
+        let total: usize = plan.chapters.iter().map(|c| c.pages.len()).sum();
+        let width = total.to_string().len().max(4);
+        let mut manifest = String::new();
+        let mut spine = String::new();
+        let mut toc = String::new();
+        let mut n = 0;
+        for (index, chapter) in plan.chapters.iter().enumerate() {
+            for (page_index, page) in chapter.pages.iter().enumerate() {
+                n += 1;
+                let id = format!("{n:0width$}");
+                let ext = page.extension().and_then(|e| e.to_str()).unwrap_or("jpg");
+                let (w, h) = image::image_dimensions(page)
+                    .map_err(|e| format!("read page size failed {:?}: {e}", page))?;
+                let bytes = std::fs::read(page).map_err(|e| format!("read page failed: {e}"))?;
+                put(&mut zip, &format!("OEBPS/images/{id}.{ext}"), stored, &bytes)?;
+                let xhtml = format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\">\n<head>\n<title>{title} {n}</title>\n<meta name=\"viewport\" content=\"width={w}, height={h}\"/>\n<style>html,body{{margin:0;padding:0;}}img{{display:block;width:{w}px;height:{h}px;}}</style>\n</head>\n<body><img src=\"../images/{id}.{ext}\" alt=\"\"/></body>\n</html>\n"
+                );
+                put(&mut zip, &format!("OEBPS/pages/{id}.xhtml"), deflated, xhtml.as_bytes())?;
+
+                let cover = if n == 1 { " properties=\"cover-image\"" } else { "" };
+                manifest.push_str(&format!(
+                    "    <item id=\"img-{id}\" href=\"images/{id}.{ext}\" media-type=\"{}\"{cover}/>\n    <item id=\"page-{id}\" href=\"pages/{id}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+                    mime_from_path(page)
+                ));
+                spine.push_str(&format!("    <itemref idref=\"page-{id}\"/>\n"));
+                if page_index == 0 {
+                    let label = if plan.chapters.len() == 1 {
+                        title.clone()
+                    } else {
+                        xml_escape(&export_chapter_title(chapter, index))
+                    };
+                    toc.push_str(&format!("      <li><a href=\"pages/{id}.xhtml\">{label}</a></li>\n"));
+                }
+                on_page();
+            }
+        }
 
-    let p = exe_dir.join("jmcomic-cache");
-    std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
-    Ok(p)
+        let nav = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n<head><title>{title}</title></head>\n<body>\n  <nav epub:type=\"toc\" id=\"toc\">\n    <h1>{title}</h1>\n    <ol>\n{toc}    </ol>\n  </nav>\n</body>\n</html>\n"
+        );
+        put(&mut zip, "OEBPS/nav.xhtml", deflated, nav.as_bytes())?;
+
+        let mut meta = format!(
+            "    <dc:identifier id=\"bookid\">urn:jm:{}</dc:identifier>\n    <dc:title>{title}</dc:title>\n    <dc:language>zh</dc:language>\n",
+            xml_escape(&plan.aid)
+        );
+        for author in &plan.authors {
+            meta.push_str(&format!("    <dc:creator>{}</dc:creator>\n", xml_escape(author)));
+        }
+        for tag in &plan.tags {
+            meta.push_str(&format!("    <dc:subject>{}</dc:subject>\n", xml_escape(tag)));
+        }
+        if !plan.description.trim().is_empty() {
+            meta.push_str(&format!(
+                "    <dc:description>{}</dc:description>\n",
+                xml_escape(plan.description.trim())
+            ));
+        }
+        let direction = if rtl { "rtl" } else { "ltr" };
+        let cover_id = format!("{:0width$}", 1);
+        let opf = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"bookid\" prefix=\"rendition: http://www.idpf.org/vocab/rendition/#\">\n  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{meta}    <meta property=\"dcterms:modified\">{}</meta>\n    <meta property=\"rendition:layout\">pre-paginated</meta>\n    <meta property=\"rendition:orientation\">auto</meta>\n    <meta property=\"rendition:spread\">none</meta>\n    <meta name=\"cover\" content=\"img-{cover_id}\"/>\n  </metadata>\n  <manifest>\n    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n{manifest}  </manifest>\n  <spine page-progression-direction=\"{direction}\">\n{spine}  </spine>\n</package>\n",
+            iso_date_time(std::time::SystemTime::now()),
+        );
+        put(&mut zip, "OEBPS/content.opf", deflated, opf.as_bytes())?;
+
+        zip.finish().map_err(|e| format!("zip finish failed: {e}"))?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("commit epub failed: {e}"))?;
+        std::fs::metadata(path)
+            .map(|m| m.len())
+            .map_err(|e| format!("stat epub failed: {e}"))
+    })();
+    if res.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    res
 }
 
-fn cover_semaphore() -> &'static tokio::sync::Semaphore {
-    COVER_SEMAPHORE.get_or_init(|| tokio::sync::Semaphore::new(4))
+// One fixed-layout EPUB for the selection; `rtl` sets a right-to-left page progression.
+#[tauri::command]
+async fn api_export_epub(
+    app: tauri::AppHandle,
+    aid: String,
+    chapter_ids: Option<Vec<String>>,
+    rtl: Option<bool>,
+    dir: Option<String>,
+) -> Result<ComicExportResult, String> {
+    let started = Instant::now();
+    let aid = aid.trim().to_string();
+    if aid.is_empty() {
+        return Err("aid is empty".to_string());
+    }
+    let out_dir = export_output_dir(dir)?;
+    let plan = prepare_export(&app, &aid, &chapter_ids.unwrap_or_default(), "epub").await?;
+    ensure_write_space(&out_dir, export_pages_bytes(&plan))?;
+
+    let chapters = plan.chapters.len() as u64;
+    let pages = plan.chapters.iter().map(|c| c.pages.len() as u64).sum();
+    let downloaded_pages = plan.downloaded;
+    let handle = app.clone();
+    let path = out_dir.join(format!("{}.epub", export_file_stem(&plan)));
+    let (file, bytes) = tauri::async_runtime::spawn_blocking(move || {
+        let mut progress = ExportProgress {
+            aid: plan.aid.clone(),
+            format: "epub".to_string(),
+            phase: "write".to_string(),
+            done: 0,
+            total: pages,
+        };
+        emit_export_progress(&handle, &progress);
+        let bytes = write_epub(&path, &plan, rtl.unwrap_or(false), &mut || {
+            progress.done += 1;
+            if progress.done.is_multiple_of(20) {
+                emit_export_progress(&handle, &progress);
+            }
+        })?;
+        progress.phase = "done".to_string();
+        emit_export_progress(&handle, &progress);
+        Ok::<_, String>((path.to_string_lossy().to_string(), bytes))
+    })
+    .await
+    .map_err(|e| format!("epub export task failed: {e}"))??;
+
+    let result = ComicExportResult {
+        files: vec![file],
+        chapters,
+        pages,
+        bytes,
+        downloaded_pages,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    logl!(
+        "[tauri][export] epub aid={} pages={} downloaded={} bytes={} cost_ms={}",
+        aid,
+        result.pages,
+        result.downloaded_pages,
+        result.bytes,
+        result.elapsed_ms
+    );
+    Ok(result)
 }
 
-fn resolve_data_dir() -> Result<std::path::PathBuf, String> {
-    if let Ok(dir) = std::env::var("JM_DATA_DIR") {
-        let p = std::path::PathBuf::from(dir);
-        std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
-        return Ok(p);
-    }
-
-    let exe_dir = std::env::current_exe()
-        .map_err(|e| format!("current exe error: {e}"))?
-        .parent()
-        .ok_or_else(|| "current exe has no parent dir".to_string())?
-        .to_path_buf();
+const PDF_JPEG_QUALITY: u8 = 90;
+// Acrobat's page size limit (200 in); larger images get a scaled-down page, not a smaller image.
+const PDF_MAX_PAGE_POINTS: f64 = 14_400.0;
 
-    let p = exe_dir.join("data");
-    std::fs::create_dir_all(&p).map_err(|e| format!("mkdir failed: {e}"))?;
-    Ok(p)
+struct PdfImage {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    gray: bool,
 }
 
-fn mime_from_path(path: &std::path::Path) -> &'static str {
-    match path.extension().and_then(|x| x.to_str()).unwrap_or("").to_ascii_lowercase().as_str() {
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "webp" => "image/webp",
-        _ => "application/octet-stream",
+// Colour component count from a JPEG's SOF marker.
+fn jpeg_components(bytes: &[u8]) -> Option<u8> {
+    let mut i = 2;
+    while i + 4 <= bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        if matches!(marker, 0x01 | 0xD0..=0xD8) {
+            i += 2;
+            continue;
+        }
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            return bytes.get(i + 9).copied();
+        }
+        if marker == 0xDA {
+            return None;
+        }
+        i += 2 + u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+    }
+    None
+}
+
+// Gray/RGB JPEGs are embedded untouched; everything else (or any resize/quality request)
+// is re-encoded as JPEG, since PDF can embed DCT data directly.
+fn pdf_page_image(
+    path: &std::path::Path,
+    max_dimension: Option<u32>,
+    quality: Option<u8>,
+) -> Result<PdfImage, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("read page failed: {e}"))?;
+    let (width, height) =
+        image::image_dimensions(path).map_err(|e| format!("read page size failed {:?}: {e}", path))?;
+    let limit = max_dimension.filter(|m| *m > 0 && width.max(height) > *m);
+    if limit.is_none() && quality.is_none() && bytes.starts_with(&[0xFF, 0xD8]) {
+        match jpeg_components(&bytes) {
+            Some(1) => return Ok(PdfImage { data: bytes, width, height, gray: true }),
+            Some(3) => return Ok(PdfImage { data: bytes, width, height, gray: false }),
+            _ => {}
+        }
     }
+    let img = image::load_from_memory(&bytes).map_err(|e| format!("decode image failed: {}", image_error_text(e)))?;
+    let img = match limit {
+        Some(m) => img.resize(m, m, image::imageops::FilterType::Lanczos3),
+        None => img,
+    };
+    let rgb = img.to_rgb8();
+    let mut data = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(
+        &mut data,
+        quality.unwrap_or(PDF_JPEG_QUALITY).clamp(1, 100),
+    )
+    .encode_image(&rgb)
+    .map_err(|e| format!("encode image failed: {e}"))?;
+    Ok(PdfImage {
+        data,
+        width: rgb.width(),
+        height: rgb.height(),
+        gray: false,
+    })
 }
 
-fn jmcache_protocol<R: tauri::Runtime>(
-    ctx: tauri::UriSchemeContext<'_, R>,
-    request: tauri::http::Request<Vec<u8>>,
-) -> tauri::http::Response<Vec<u8>> {
-    use tauri::http::{Response, StatusCode};
-
-    let app = ctx.app_handle();
-    let uri = request.uri().to_string();
-    let path_part = uri
-        .split("://")
-        .nth(1)
-        .and_then(|s| s.splitn(2, '/').nth(1))
-        .unwrap_or("");
-    let path_part = path_part.strip_prefix('/').unwrap_or(path_part);
-
-    let decoded = match urlencoding::decode(path_part) {
-        Ok(s) => s.into_owned(),
-        Err(_) => {
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(b"bad path encoding".to_vec())
-                .unwrap_or_else(|_| Response::new(Vec::new()));
-        }
-    };
+// PDF text string as UTF-16BE with a BOM, hex encoded, so CJK titles survive.
+fn pdf_text(s: &str) -> String {
+    let mut out = String::from("<FEFF");
+    for unit in s.encode_utf16() {
+        out.push_str(&format!("{unit:04X}"));
+    }
+    out.push('>');
+    out
+}
 
-    let requested = std::path::PathBuf::from(decoded);
-    let base = match resolve_read_cache_dir(app) {
-        Ok(p) => p,
-        Err(e) => {
-            logl!("[tauri][jmcache] resolve cache dir failed: {e}");
-            return Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(b"cache dir error".to_vec())
-                .unwrap_or_else(|_| Response::new(Vec::new()));
-        }
-    };
+// Minimal object writer that tracks byte offsets for the xref table.
+struct PdfWriter<W: std::io::Write> {
+    out: W,
+    pos: u64,
+    offsets: Vec<u64>,
+}
 
-    let (requested_canon, base_canon) = match (std::fs::canonicalize(&requested), std::fs::canonicalize(&base)) {
-        (Ok(r), Ok(b)) => (r, b),
-        _ => {
-            return Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(b"not found".to_vec())
-                .unwrap_or_else(|_| Response::new(Vec::new()));
+impl<W: std::io::Write> PdfWriter<W> {
+    fn new(out: W) -> Self {
+        PdfWriter {
+            out,
+            pos: 0,
+            offsets: vec![0],
         }
-    };
+    }
 
-    if !requested_canon.starts_with(&base_canon) {
-        logl!(
-            "[tauri][jmcache] forbidden path requested={:?} base={:?}",
-            requested_canon,
-            base_canon
-        );
-        return Response::builder()
-            .status(StatusCode::FORBIDDEN)
-            .body(b"forbidden".to_vec())
-            .unwrap_or_else(|_| Response::new(Vec::new()));
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.out
+            .write_all(bytes)
+            .map_err(|e| format!("write pdf failed: {e}"))?;
+        self.pos += bytes.len() as u64;
+        Ok(())
     }
 
-    let bytes = match std::fs::read(&requested_canon) {
-        Ok(b) => b,
-        Err(_) => {
-            return Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(b"not found".to_vec())
-                .unwrap_or_else(|_| Response::new(Vec::new()));
+    fn begin(&mut self, id: usize) -> Result<(), String> {
+        if self.offsets.len() <= id {
+            self.offsets.resize(id + 1, 0);
         }
-    };
-
-    let mime = mime_from_path(&requested_canon);
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", mime)
-        .body(bytes)
-        .unwrap_or_else(|_| Response::new(Vec::new()))
-}
+        self.offsets[id] = self.pos;
+        self.write(format!("{id} 0 obj\n").as_bytes())
+    }
 
-#[tauri::command]
-async fn api_local_favorites_list(
-    kind: Option<String>,
-    store: tauri::State<'_, LocalFavoritesStore>,
-) -> Result<LocalFavoritesListResponse, String> {
-    let tree = store.tree()?;
-    let latest_tree = read_latest_tree()?;
+    fn object(&mut self, id: usize, body: &str) -> Result<(), String> {
+        self.begin(id)?;
+        self.write(body.as_bytes())?;
+        self.write(b"\nendobj\n")
+    }
 
-    let kind = kind.unwrap_or_else(|| "all".to_string());
-    let kind = kind.trim();
-    let filter_multi = if kind.eq_ignore_ascii_case("single") {
-        Some(false)
-    } else if kind.eq_ignore_ascii_case("multi") {
-        Some(true)
-    } else {
-        None
-    };
+    fn stream(&mut self, id: usize, dict: &str, data: &[u8]) -> Result<(), String> {
+        self.begin(id)?;
+        self.write(format!("<< {dict} /Length {} >>\nstream\n", data.len()).as_bytes())?;
+        self.write(data)?;
+        self.write(b"\nendstream\nendobj\n")
+    }
 
-    let mut latest_map = HashMap::new();
-    for res in latest_tree.iter() {
-        let (k, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
-        if let Ok(entry) = serde_json::from_slice::<LatestChapterEntry>(&v) {
-            latest_map.insert(k.to_vec(), entry);
+    fn finish(mut self, root: usize, info: usize) -> Result<W, String> {
+        let xref = self.pos;
+        let count = self.offsets.len();
+        let mut table = format!("xref\n0 {count}\n0000000000 65535 f \n");
+        for off in &self.offsets[1..] {
+            table.push_str(&format!("{off:010} 00000 n \n"));
         }
+        table.push_str(&format!(
+            "trailer\n<< /Size {count} /Root {root} 0 R /Info {info} 0 R >>\nstartxref\n{xref}\n%%EOF\n"
+        ));
+        self.write(table.as_bytes())?;
+        Ok(self.out)
     }
+}
 
-    let mut items = Vec::new();
-    let mut total: u64 = 0;
-    let mut filtered: u64 = 0;
-    for res in tree.iter() {
-        let (_, v) = res.map_err(|e| format!("sled iter failed: {e}"))?;
-        let it: LocalFavoriteItem =
-            bincode::deserialize(&v).map_err(|e| format!("decode local favorite failed: {e}"))?;
-        total += 1;
-        let latest = latest_map
-            .get(it.aid.as_bytes())
-            .and_then(|entry| entry.latest_chapter_sort.clone());
-        if let Some(need_multi) = filter_multi {
-            let is_multi = latest.is_some();
-            if need_multi != is_multi {
-                continue;
+// One page per image, sized to the image, with a bookmark per chapter.
+fn write_pdf(
+    path: &std::path::Path,
+    plan: &ExportPlan,
+    max_dimension: Option<u32>,
+    quality: Option<u8>,
+    on_page: &mut dyn FnMut(),
+) -> Result<u64, String> {
+    const CATALOG: usize = 1;
+    const PAGES: usize = 2;
+    const INFO: usize = 3;
+    const OUTLINES: usize = 4;
+    let chapters: Vec<&ExportChapter> = plan.chapters.iter().filter(|c| !c.pages.is_empty()).collect();
+    let first_outline = OUTLINES + 1;
+    let first_page = first_outline + chapters.len();
+
+    let tmp = path.with_extension("pdf.part");
+    let res = (|| {
+        let file = std::fs::File::create(&tmp).map_err(|e| format!("create pdf failed: {e}"))?;
+        let mut pdf = PdfWriter::new(std::io::BufWriter::new(file));
+        pdf.write(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+
+        let mut kids = Vec::new();
+        let mut chapter_pages = Vec::with_capacity(chapters.len());
+        for chapter in &chapters {
+            chapter_pages.push(first_page + kids.len() * 3);
+            for page in &chapter.pages {
+                let id = first_page + kids.len() * 3;
+                let img = pdf_page_image(page, max_dimension, quality)?;
+                let scale = (PDF_MAX_PAGE_POINTS / img.width.max(img.height) as f64).min(1.0);
+                let (w, h) = (img.width as f64 * scale, img.height as f64 * scale);
+                pdf.object(
+                    id,
+                    &format!(
+                        "<< /Type /Page /Parent {PAGES} 0 R /MediaBox [0 0 {w:.2} {h:.2}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                        id + 1,
+                        id + 2
+                    ),
+                )?;
+                let color = if img.gray { "/DeviceGray" } else { "/DeviceRGB" };
+                pdf.stream(
+                    id + 1,
+                    &format!(
+                        "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {color} /BitsPerComponent 8 /Filter /DCTDecode",
+                        img.width, img.height
+                    ),
+                    &img.data,
+                )?;
+                pdf.stream(id + 2, "", format!("q {w:.2} 0 0 {h:.2} 0 0 cm /Im0 Do Q").as_bytes())?;
+                kids.push(format!("{id} 0 R"));
+                on_page();
             }
         }
-        filtered += 1;
-        items.push(LocalFavoriteView {
-            aid: it.aid,
-            title: it.title,
-            author: it.author,
-            cover_url: it.cover_url,
-            added_at: it.added_at,
-            updated_at: it.updated_at,
-            latest_chapter_sort: latest,
-        });
-    }
-    items.sort_by_key(|x| -x.updated_at);
-    Ok(LocalFavoritesListResponse {
-        total,
-        filtered,
-        list: items,
-    })
-}
+        if kids.is_empty() {
+            return Err("no pages to export".to_string());
+        }
+        pdf.object(
+            PAGES,
+            &format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len()),
+        )?;
+
+        let book_title = if plan.title.trim().is_empty() { &plan.aid } else { plan.title.trim() };
+        for (index, chapter) in chapters.iter().enumerate() {
+            let id = first_outline + index;
+            let title = if chapters.len() == 1 {
+                book_title.to_string()
+            } else {
+                export_chapter_title(chapter, index)
+            };
+            let mut body = format!(
+                "<< /Title {} /Parent {OUTLINES} 0 R /Dest [{} 0 R /Fit]",
+                pdf_text(&title),
+                chapter_pages[index]
+            );
+            if index > 0 {
+                body.push_str(&format!(" /Prev {} 0 R", id - 1));
+            }
+            if index + 1 < chapters.len() {
+                body.push_str(&format!(" /Next {} 0 R", id + 1));
+            }
+            body.push_str(" >>");
+            pdf.object(id, &body)?;
+        }
+        pdf.object(
+            OUTLINES,
+            &format!(
+                "<< /Type /Outlines /First {first_outline} 0 R /Last {} 0 R /Count {} >>",
+                first_outline + chapters.len() - 1,
+                chapters.len()
+            ),
+        )?;
+        pdf.object(
+            CATALOG,
+            &format!("<< /Type /Catalog /Pages {PAGES} 0 R /Outlines {OUTLINES} 0 R /PageMode /UseOutlines >>"),
+        )?;
+
+        let (year, month, day, hour, minute, second) =
+            civil_from_unix(unix_secs(std::time::SystemTime::now()));
+        let mut info = format!(
+            "<< /Title {} /Creator (jm) /Producer (jm) /CreationDate (D:{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}Z)",
+            pdf_text(book_title)
+        );
+        if !plan.authors.is_empty() {
+            info.push_str(&format!(" /Author {}", pdf_text(&plan.authors.join(", "))));
+        }
+        if !plan.tags.is_empty() {
+            info.push_str(&format!(" /Keywords {}", pdf_text(&plan.tags.join(", "))));
+        }
+        if !plan.description.trim().is_empty() {
+            info.push_str(&format!(" /Subject {}", pdf_text(plan.description.trim())));
+        }
+        info.push_str(" >>");
+        pdf.object(INFO, &info)?;
 
-#[tauri::command]
-async fn api_local_favorite_has(
-    aid: String,
-    store: tauri::State<'_, LocalFavoritesStore>,
-) -> Result<bool, String> {
-    let tree = store.tree()?;
-    Ok(tree.get(aid.as_bytes()).map_err(|e| format!("sled get failed: {e}"))?.is_some())
+        let mut out = pdf.finish(CATALOG, INFO)?;
+        out.flush().map_err(|e| format!("write pdf failed: {e}"))?;
+        drop(out);
+        std::fs::rename(&tmp, path).map_err(|e| format!("commit pdf failed: {e}"))?;
+        std::fs::metadata(path)
+            .map(|m| m.len())
+            .map_err(|e| format!("stat pdf failed: {e}"))
+    })();
+    if res.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    res
 }
 
+// One PDF for the selection. `max_dimension` (px, longest side) and `quality` (1-100)
+// re-encode pages to keep the file small; by default pages keep their native resolution.
 #[tauri::command]
-async fn api_local_favorite_toggle(
-    aid: String,
-    title: Option<String>,
-    author: Option<String>,
-    cover_url: Option<String>,
-    store: tauri::State<'_, LocalFavoritesStore>,
+async fn api_export_pdf(
     app: tauri::AppHandle,
-) -> Result<bool, String> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("system time error: {e}"))?
-        .as_secs() as i64;
-
-    let tree = store.tree()?;
-
-    if tree
-        .get(aid.as_bytes())
-        .map_err(|e| format!("sled get failed: {e}"))?
-        .is_some()
-    {
-        tree.remove(aid.as_bytes())
-            .map_err(|e| format!("sled remove failed: {e}"))?;
-        let _ = tree.flush();
-        logl!("[tauri][localfav] removed aid={}", aid);
-        let _ = read_latest_tree().and_then(|t| {
-            let _ = t.remove(aid.as_bytes());
-            let _ = t.flush();
-            Ok(())
-        });
-        let _ = read_latest_seen_tree().and_then(|t| {
-            let _ = t.remove(aid.as_bytes());
-            let _ = t.flush();
-            Ok(())
-        });
-        let _ = read_follow_tree().and_then(|t| {
-            let _ = t.remove(aid.as_bytes());
-            let _ = t.flush();
-            Ok(())
-        });
-        return Ok(false);
+    aid: String,
+    chapter_ids: Option<Vec<String>>,
+    max_dimension: Option<u32>,
+    quality: Option<u8>,
+    dir: Option<String>,
+) -> Result<ComicExportResult, String> {
+    let started = Instant::now();
+    let aid = aid.trim().to_string();
+    if aid.is_empty() {
+        return Err("aid is empty".to_string());
     }
+    let out_dir = export_output_dir(dir)?;
+    let plan = prepare_export(&app, &aid, &chapter_ids.unwrap_or_default(), "pdf").await?;
+    ensure_write_space(&out_dir, export_pages_bytes(&plan))?;
 
-    let title_log = title.clone();
-    let item = LocalFavoriteItem {
-        aid: aid.clone(),
-        title: title.unwrap_or_default(),
-        author: author.unwrap_or_default(),
-        cover_url: cover_url.unwrap_or_default(),
-        added_at: now,
-        updated_at: now,
-    };
-    let val = bincode::serialize(&item).map_err(|e| format!("encode local favorite failed: {e}"))?;
-    tree.insert(aid.as_bytes(), val)
-        .map_err(|e| format!("sled insert failed: {e}"))?;
-    let _ = tree.flush();
-    logl!("[tauri][localfav] added aid={} title={:?}", aid, title_log);
+    let chapters = plan.chapters.len() as u64;
+    let pages = plan.chapters.iter().map(|c| c.pages.len() as u64).sum();
+    let downloaded_pages = plan.downloaded;
     let handle = app.clone();
-    std::thread::spawn(move || {
-        tauri::async_runtime::block_on(async {
-            if let Err(e) = scan_latest_chapters(handle).await {
-                logl!("[tauri][latest] scan failed: {e}");
+    let path = out_dir.join(format!("{}.pdf", export_file_stem(&plan)));
+    let (file, bytes) = tauri::async_runtime::spawn_blocking(move || {
+        let mut progress = ExportProgress {
+            aid: plan.aid.clone(),
+            format: "pdf".to_string(),
+            phase: "write".to_string(),
+            done: 0,
+            total: pages,
+        };
+        emit_export_progress(&handle, &progress);
+        let bytes = write_pdf(&path, &plan, max_dimension, quality, &mut || {
+            progress.done += 1;
+            if progress.done.is_multiple_of(20) {
+                emit_export_progress(&handle, &progress);
             }
-        });
-    });
-    Ok(true)
+        })?;
+        progress.phase = "done".to_string();
+        emit_export_progress(&handle, &progress);
+        Ok::<_, String>((path.to_string_lossy().to_string(), bytes))
+    })
+    .await
+    .map_err(|e| format!("pdf export task failed: {e}"))??;
+
+    let result = ComicExportResult {
+        files: vec![file],
+        chapters,
+        pages,
+        bytes,
+        downloaded_pages,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    logl!(
+        "[tauri][export] pdf aid={} pages={} downloaded={} bytes={} cost_ms={}",
+        aid,
+        result.pages,
+        result.downloaded_pages,
+        result.bytes,
+        result.elapsed_ms
+    );
+    Ok(result)
 }
 
-#[tauri::command]
-async fn api_read_cancel(
-    read_key: String,
-    registry: tauri::State<'_, CancelRegistry>,
-) -> Result<(), String> {
-    logl!("[tauri][cancel] read_key={:?} set=true", read_key);
-    registry.cancel(&read_key);
-    Ok(())
+const LIBRARY_MANIFEST_FILE: &str = ".jm-library.json";
+
+fn library_dir() -> Option<std::path::PathBuf> {
+    config_state()
+        .lock()
+        .ok()
+        .and_then(|c| c.library_dir.clone())
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .map(std::path::PathBuf::from)
+}
+
+// `Chapter 001 - name.cbz`; the number follows the same rules as exports.
+fn library_chapter_file_name(chapter: &ExportChapter, index: usize) -> String {
+    let label = export_chapter_label(chapter, index);
+    match chapter.name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => format!("Chapter {label} - {}.cbz", sanitize_file_name(name)),
+        None => format!("Chapter {label}.cbz"),
+    }
+}
+
+// Series folders are matched by their `[aid]` suffix, so a renamed title moves the folder.
+fn find_library_folder(root: &std::path::Path, aid: &str) -> Option<std::path::PathBuf> {
+    let suffix = format!("[{aid}]");
+    std::fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| {
+            p.is_dir()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with(&suffix))
+        })
 }
 
-#[tauri::command]
-async fn api_image_descramble(url: String, num: i64) -> Result<ImagePayload, String> {
-    let started = Instant::now();
-    logl!("[tauri][img] start url={:?} num={}", url, num);
+// Mylar-style `series.json`, which Komga reads for series metadata.
+fn library_series_json(plan: &ExportPlan) -> Result<Vec<u8>, String> {
+    let value = serde_json::json!({
+        "version": "1.0.2",
+        "metadata": {
+            "type": "comicSeries",
+            "name": plan.title,
+            "comicid": plan.aid,
+            "publisher": "",
+            "description_text": plan.description,
+            "booktype": "Print",
+            "total_issues": plan.series_count,
+            "status": "Continuing",
+        }
+    });
+    serde_json::to_vec_pretty(&value).map_err(|e| format!("encode series.json failed: {e}"))
+}
 
-    let client = http_client()?;
-    let resp = client
-        .get(&url)
-        .header("accept-encoding", "identity")
-        .send()
-        .await
-        .map_err(|e| format!("request failed: {e}"))?;
+// Writes archives for every fully cached chapter of `aid` and renames what moved.
+fn sync_library_comic(
+    root: &std::path::Path,
+    base_dir: &std::path::Path,
+    aid: &str,
+    report: &mut LibrarySyncReport,
+) -> Result<(), String> {
+    let Some(source) = downloaded_source(aid)? else {
+        return Ok(());
+    };
+    let read_dir = page_cache_dir(base_dir, "read", Some(aid));
+    let mut positions = Vec::new();
+    let mut chapters = Vec::new();
+    for (index, chapter) in source.chapters.iter().enumerate() {
+        let pages: Option<Vec<std::path::PathBuf>> = chapter
+            .pages
+            .iter()
+            .map(|p| find_cached_image(&read_dir, &page_cache_key(&p.url, p.num)))
+            .collect();
+        match pages.filter(|p| chapter.resolved && !p.is_empty()) {
+            Some(pages) => {
+                positions.push((index, chapter.chapter_id.clone()));
+                chapters.push(ExportChapter {
+                    sort: chapter.sort.clone(),
+                    name: chapter.name.clone(),
+                    pages,
+                });
+            }
+            None => report.incomplete += 1,
+        }
+    }
+    if chapters.is_empty() {
+        return Ok(());
+    }
+    let plan = ExportPlan {
+        aid: aid.to_string(),
+        title: source.title.clone(),
+        authors: source.authors.clone(),
+        tags: source.tags.clone(),
+        description: source.description.clone(),
+        chapters,
+        series_count: (source.kind == "album").then_some(source.chapters.len()),
+        downloaded: 0,
+    };
 
-    let status = resp.status();
-    if !status.is_success() {
-        logl!(
-            "[tauri][img] http_error status={} url={:?} cost_ms={}",
-            status,
-            url,
-            started.elapsed().as_millis()
+    let folder = root.join(export_file_stem(&plan));
+    if !folder.exists() {
+        if let Some(old) = find_library_folder(root, aid) {
+            std::fs::rename(&old, &folder).map_err(|e| format!("rename series folder failed: {e}"))?;
+            logl!("[tauri][library] renamed {:?} -> {:?}", old, folder);
+            report.renamed += 1;
+        }
+    }
+    std::fs::create_dir_all(&folder).map_err(|e| format!("mkdir failed: {e}"))?;
+    let manifest_path = folder.join(LIBRARY_MANIFEST_FILE);
+    let mut manifest: LibraryManifest = std::fs::read(&manifest_path)
+        .ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default();
+    manifest.aid = aid.to_string();
+    manifest.title = plan.title.clone();
+
+    for (chapter, (index, chapter_id)) in plan.chapters.iter().zip(&positions) {
+        let file = library_chapter_file_name(chapter, *index);
+        let pages = chapter.pages.len() as u64;
+        let existing = manifest
+            .chapters
+            .get(chapter_id)
+            .filter(|e| folder.join(&e.file).exists())
+            .cloned();
+        if let Some(old) = existing {
+            if old.pages == pages {
+                if old.file == file {
+                    report.unchanged += 1;
+                    continue;
+                }
+                std::fs::rename(folder.join(&old.file), folder.join(&file))
+                    .map_err(|e| format!("rename chapter failed: {e}"))?;
+                report.renamed += 1;
+                manifest.chapters.insert(
+                    chapter_id.clone(),
+                    LibraryChapterFile { file, pages, updated_at: now_millis() },
+                );
+                continue;
+            }
+            if old.file != file {
+                let _ = std::fs::remove_file(folder.join(&old.file));
+            }
+        }
+        let page_refs: Vec<&std::path::PathBuf> = chapter.pages.iter().collect();
+        let bytes = page_refs
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+        ensure_write_space(&folder, bytes)?;
+        let info = comic_info_xml(&plan, Some((*index, chapter)), page_refs.len());
+        write_cbz(&folder.join(&file), &page_refs, &info)?;
+        report.written += 1;
+        manifest.chapters.insert(
+            chapter_id.clone(),
+            LibraryChapterFile { file, pages, updated_at: now_millis() },
         );
-        return Err(format!("image http error: {status}"));
     }
 
-    let bytes = resp
-        .bytes()
-        .await
-        .map_err(|e| format!("read image failed: {e}"))?;
-
-    let (out, fmt) = descramble_image_bytes(&bytes, num)?;
-    let mime = mime_from_format(fmt).to_string();
-    let data_b64 = base64::engine::general_purpose::STANDARD.encode(out);
-
-    logl!(
-        "[tauri][img] ok url={:?} num={} mime={} b64_len={} cost_ms={}",
-        url,
-        num,
-        mime,
-        data_b64.len(),
-        started.elapsed().as_millis()
-    );
-    Ok(ImagePayload { mime, data_b64 })
+    write_file_atomic(&folder.join("series.json"), &library_series_json(&plan)?)?;
+    let cover = folder.join("cover.jpg");
+    if !cover.exists() {
+        if let Some(first) = plan.chapters.first().and_then(|c| c.pages.first()) {
+            // Same JPEG pass-through/re-encode the PDF export uses.
+            let img = pdf_page_image(first, None, None)?;
+            write_file_atomic(&cover, &img.data)?;
+        }
+    }
+    let val =
+        serde_json::to_vec_pretty(&manifest).map_err(|e| format!("encode library manifest failed: {e}"))?;
+    write_file_atomic(&manifest_path, &val)?;
+    report.comics += 1;
+    Ok(())
 }
 
-#[tauri::command]
-async fn api_image_descramble_file(
-    app: tauri::AppHandle,
-    url: String,
-    num: i64,
-    aid: Option<String>,
-    read_key: Option<String>,
-    registry: tauri::State<'_, CancelRegistry>,
-) -> Result<String, String> {
-    let started = Instant::now();
-    let num = if num <= 1 { 1 } else { num };
-    logl!(
-        "[tauri][imgfile] start url={:?} num={} read_key={:?}",
-        url, num, read_key
-    );
+// `aids` empty means every comic that has a download job.
+fn sync_library(app: &tauri::AppHandle, aids: Vec<String>) -> Result<LibrarySyncReport, String> {
+    let root = library_dir().ok_or_else(|| "library dir not set".to_string())?;
+    let base_dir = resolve_read_cache_dir(app)?;
+    let _guard = LIBRARY_SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    std::fs::create_dir_all(&root).map_err(|e| format!("mkdir failed: {e}"))?;
+    let aids = if aids.is_empty() {
+        let mut all: Vec<String> = load_download_jobs()?.into_iter().map(|j| j.aid).collect();
+        all.sort();
+        all.dedup();
+        all
+    } else {
+        aids
+    };
+    let mut report = LibrarySyncReport::default();
+    for aid in aids {
+        if let Err(e) = sync_library_comic(&root, &base_dir, &aid, &mut report) {
+            logl!("[tauri][library] sync failed aid={} err={}", aid, e);
+            report.errors.push(format!("{aid}: {e}"));
+        }
+    }
+    Ok(report)
+}
 
-    let token = read_key.as_deref().map(|k| registry.token_for(k));
-    if let Some(t) = &token {
-        if t.load(Ordering::Relaxed) {
+fn spawn_library_sync(app: &tauri::AppHandle, aids: Vec<String>) {
+    if library_dir().is_none() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || match sync_library(&app, aids) {
+        Ok(r) => {
             logl!(
-                "[tauri][imgfile] cancelled(before) url={:?} num={} read_key={:?} cost_ms={}",
-                url,
-                num,
-                read_key,
-                started.elapsed().as_millis()
+                "[tauri][library] synced comics={} written={} renamed={} errors={}",
+                r.comics,
+                r.written,
+                r.renamed,
+                r.errors.len()
             );
-            return Err("cancelled".to_string());
+        }
+        Err(e) => {
+            logl!("[tauri][library] sync failed: {e}");
+        }
+    });
+}
+
+#[tauri::command]
+async fn api_library_sync(app: tauri::AppHandle, aid: Option<String>) -> Result<LibrarySyncReport, String> {
+    let aids = aid.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).into_iter().collect();
+    tauri::async_runtime::spawn_blocking(move || sync_library(&app, aids))
+        .await
+        .map_err(|e| format!("library sync task failed: {e}"))?
+}
+
+const LOCAL_COMIC_PREFIX: &str = "local-";
+const LOCAL_EXTRACT_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+const LOCAL_EXTRACT_CHECK_BYTES: u64 = 64 * 1024 * 1024;
+const LOCAL_ARCHIVE_EXTS: &[&str] = &["cbz", "zip"];
+
+impl LocalComic {
+    fn view(&self) -> LocalComicView {
+        LocalComicView {
+            id: self.id.clone(),
+            source: self.source.clone(),
+            kind: self.kind.clone(),
+            title: self.title.clone(),
+            series: self.series.clone(),
+            number: self.number.clone(),
+            authors: self.authors.clone(),
+            tags: self.tags.clone(),
+            description: self.description.clone(),
+            page_count: self.pages.len() as u64,
+            available: std::path::Path::new(&self.source).exists(),
+            imported_at: self.imported_at,
+            updated_at: self.updated_at,
         }
     }
+}
 
-    let base_dir = resolve_read_cache_dir(&app)?;
-    let out_dir = match aid.as_deref() {
-        Some(a) => base_dir.join("read").join(sanitize_path_component(a)),
-        None => base_dir.join("read").join("unknown"),
+fn load_local_comic(id: &str) -> Result<Option<LocalComic>, String> {
+    let tree = read_local_comic_tree()?;
+    let Some(val) = tree
+        .get(id.as_bytes())
+        .map_err(|e| format!("read local comic failed: {e}"))?
+    else {
+        return Ok(None);
     };
-    std::fs::create_dir_all(&out_dir).map_err(|e| format!("mkdir failed: {e}"))?;
+    serde_json::from_slice(&val)
+        .map(Some)
+        .map_err(|e| format!("decode local comic failed: {e}"))
+}
 
-    let key = md5_hex(&format!("{url}|{num}"));
-    let cached_png = out_dir.join(format!("{key}.png"));
-    if cached_png.exists() {
-        logl!(
-            "[tauri][imgfile] hit num={} out={:?} read_key={:?} cost_ms={}",
-            num,
-            cached_png,
-            read_key,
-            started.elapsed().as_millis()
-        );
-        return Ok(cached_png.to_string_lossy().to_string());
-    }
-    let cached_jpg = out_dir.join(format!("{key}.jpg"));
-    if cached_jpg.exists() {
-        logl!(
-            "[tauri][imgfile] hit num={} out={:?} read_key={:?} cost_ms={}",
-            num,
-            cached_jpg,
-            read_key,
-            started.elapsed().as_millis()
-        );
-        return Ok(cached_jpg.to_string_lossy().to_string());
+fn has_ext(name: &str, exts: &[&str]) -> bool {
+    std::path::Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .is_some_and(|e| exts.contains(&e.as_str()) || (e == "jpeg" && exts.contains(&"jpg")))
+}
+
+// `page2` < `page10`; digit runs compare by value, everything else case-insensitively.
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let na: String = std::iter::from_fn(|| a.next_if(|c| c.is_ascii_digit())).collect();
+                let nb: String = std::iter::from_fn(|| b.next_if(|c| c.is_ascii_digit())).collect();
+                let (ta, tb) = (na.trim_start_matches('0'), nb.trim_start_matches('0'));
+                let ord = ta
+                    .len()
+                    .cmp(&tb.len())
+                    .then_with(|| ta.cmp(tb))
+                    .then_with(|| na.len().cmp(&nb.len()));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a.next();
+                b.next();
+            }
+        }
     }
+}
 
-    let client = http_client()?;
-    let resp = client
-        .get(&url)
-        .header("accept-encoding", "identity")
-        .send()
-        .await
-        .map_err(|e| format!("request failed: {e}"))?;
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
 
-    if let Some(t) = &token {
-        if t.load(Ordering::Relaxed) {
-            logl!(
-                "[tauri][imgfile] cancelled(after_http) url={:?} num={} read_key={:?} cost_ms={}",
-                url,
-                num,
-                read_key,
-                started.elapsed().as_millis()
-            );
-            return Err("cancelled".to_string());
+fn xml_tag_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    let text = xml_unescape(xml[start..end].trim());
+    (!text.is_empty()).then_some(text)
+}
+
+fn apply_comic_info(comic: &mut LocalComic, xml: &str) {
+    let list = |tag: &str| -> Vec<String> {
+        xml_tag_text(xml, tag)
+            .map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    comic.series = xml_tag_text(xml, "Series");
+    comic.number = xml_tag_text(xml, "Number");
+    if let Some(title) = xml_tag_text(xml, "Title").or_else(|| comic.series.clone()) {
+        comic.title = title;
+    }
+    comic.authors = list("Writer");
+    if comic.authors.is_empty() {
+        comic.authors = list("Penciller");
+    }
+    comic.tags = list("Tags");
+    for genre in list("Genre") {
+        if !comic.tags.contains(&genre) {
+            comic.tags.push(genre);
         }
     }
+    comic.description = xml_tag_text(xml, "Summary").unwrap_or_default();
+}
 
-    let status = resp.status();
-    if !status.is_success() {
-        logl!(
-            "[tauri][imgfile] http_error status={} url={:?} cost_ms={}",
-            status,
-            url,
-            started.elapsed().as_millis()
-        );
-        return Err(format!("image http error: {status}"));
+// Image paths under `dir`, relative and `/`-separated, in natural order.
+fn local_folder_pages(dir: &std::path::Path) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                stack.push(path);
+            } else if has_ext(&path.to_string_lossy(), CACHED_IMAGE_EXTS) {
+                if let Ok(rel) = path.strip_prefix(dir) {
+                    out.push(rel.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
     }
+    out.sort_by(|a, b| natural_cmp(a, b));
+    out
+}
 
-    let bytes = resp
-        .bytes()
-        .await
-        .map_err(|e| format!("read image failed: {e}"))?;
+fn local_comic_id(path: &std::path::Path) -> String {
+    let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let digest = md5_hex(&canonical.to_string_lossy());
+    format!("{LOCAL_COMIC_PREFIX}{}", &digest[..16])
+}
 
-    if let Some(t) = &token {
-        if t.load(Ordering::Relaxed) {
-            logl!(
-                "[tauri][imgfile] cancelled(after_body) url={:?} num={} read_key={:?} cost_ms={}",
-                url,
-                num,
-                read_key,
-                started.elapsed().as_millis()
-            );
-            return Err("cancelled".to_string());
+fn read_local_comic(path: &std::path::Path) -> Result<LocalComic, String> {
+    let source = std::fs::canonicalize(path).map_err(|e| format!("resolve path failed: {e}"))?;
+    let mut comic = LocalComic {
+        id: local_comic_id(&source),
+        source: source.to_string_lossy().to_string(),
+        title: source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    if source.is_dir() {
+        comic.kind = "folder".to_string();
+        comic.pages = local_folder_pages(&source);
+        if let Ok(xml) = std::fs::read_to_string(source.join("ComicInfo.xml")) {
+            apply_comic_info(&mut comic, &xml);
+        }
+    } else {
+        comic.kind = "archive".to_string();
+        if let Ok(meta) = std::fs::metadata(&source) {
+            comic.source_size = meta.len();
+            comic.source_modified = meta
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0);
+        }
+        let file = std::fs::File::open(&source).map_err(|e| format!("open archive failed: {e}"))?;
+        let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("read archive failed: {e}"))?;
+        let names: Vec<String> = zip.file_names().map(|n| n.to_string()).collect();
+        comic.pages = names
+            .iter()
+            .filter(|n| !n.ends_with('/') && !n.starts_with("__MACOSX/"))
+            .filter(|n| !n.rsplit('/').next().unwrap_or(n).starts_with('.'))
+            .filter(|n| has_ext(n, CACHED_IMAGE_EXTS))
+            .cloned()
+            .collect();
+        comic.pages.sort_by(|a, b| natural_cmp(a, b));
+        let info = names
+            .iter()
+            .find(|n| n.rsplit('/').next().is_some_and(|f| f.eq_ignore_ascii_case("ComicInfo.xml")))
+            .cloned();
+        if let Some(name) = info {
+            let mut xml = String::new();
+            if let Ok(mut entry) = zip.by_name(&name) {
+                let _ = std::io::Read::read_to_string(&mut entry, &mut xml);
+            }
+            apply_comic_info(&mut comic, &xml);
         }
     }
+    if comic.pages.is_empty() {
+        return Err(format!("no images in {:?}", source));
+    }
+    Ok(comic)
+}
 
-    let (out, fmt) = match descramble_image_bytes_with_cancel(&bytes, num, token.as_deref()) {
-        Ok(v) => v,
-        Err(e) if e == "cancelled" => {
-            logl!(
-                "[tauri][imgfile] cancelled(descramble) url={:?} num={} read_key={:?} cost_ms={}",
-                url,
-                num,
-                read_key,
-                started.elapsed().as_millis()
-            );
-            return Err("cancelled".to_string());
+// A folder holding images is one comic; otherwise its archives and image subfolders are.
+fn local_import_candidates(path: &std::path::Path) -> Result<Vec<std::path::PathBuf>, String> {
+    if path.is_file() {
+        if has_ext(&path.to_string_lossy(), LOCAL_ARCHIVE_EXTS) {
+            return Ok(vec![path.to_path_buf()]);
         }
-        Err(e) => return Err(e),
-    };
-    let ext = match fmt {
-        ImageFormat::Jpeg => "jpg",
-        ImageFormat::Png => "png",
-        _ => "bin",
-    };
+        return Err(format!("not a cbz/zip archive: {:?}", path));
+    }
+    let entries = std::fs::read_dir(path).map_err(|e| format!("read dir failed: {e}"))?;
+    let mut children = Vec::new();
+    let mut has_images = false;
+    for entry in entries.flatten() {
+        let child = entry.path();
+        let name = child.to_string_lossy().to_string();
+        if child.is_file() && has_ext(&name, CACHED_IMAGE_EXTS) {
+            has_images = true;
+        } else if (child.is_file() && has_ext(&name, LOCAL_ARCHIVE_EXTS))
+            || (child.is_dir() && !local_folder_pages(&child).is_empty())
+        {
+            children.push(child);
+        }
+    }
+    if has_images {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    children.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(children)
+}
 
-    let out_path = out_dir.join(format!("{key}.{ext}"));
-    if !out_path.exists() {
-        std::fs::write(&out_path, &out).map_err(|e| format!("write file failed: {e}"))?;
+// Imports are idempotent: the id comes from the canonical path, so re-importing refreshes
+// pages and metadata but keeps the original import time (and read progress).
+#[tauri::command]
+async fn api_local_import(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> Result<Vec<LocalComicView>, String> {
+    let base = resolve_read_cache_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let tree = read_local_comic_tree()?;
+        let mut out = Vec::new();
+        for raw in paths {
+            let path = std::path::PathBuf::from(raw.trim());
+            for candidate in local_import_candidates(&path)? {
+                let mut comic = match read_local_comic(&candidate) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        logl!("[tauri][local] skip {:?}: {}", candidate, e);
+                        continue;
+                    }
+                };
+                let now = now_millis();
+                let previous = load_local_comic(&comic.id)?;
+                comic.imported_at = previous.as_ref().map(|c| c.imported_at).unwrap_or(now);
+                // Extracted files are named by page index, so they go stale once the
+                // archive or its page order changes.
+                let stale = previous.as_ref().is_some_and(|p| {
+                    p.pages != comic.pages
+                        || p.source_size != comic.source_size
+                        || p.source_modified != comic.source_modified
+                });
+                let dir = local_extract_dir(&base, &comic.id);
+                if stale && dir.exists() {
+                    if let Err(e) = std::fs::remove_dir_all(&dir) {
+                        logl!("[tauri][local] clear extracted pages failed id={} err={}", comic.id, e);
+                    }
+                }
+                comic.updated_at = now;
+                let val = serde_json::to_vec(&comic)
+                    .map_err(|e| format!("encode local comic failed: {e}"))?;
+                tree.insert(comic.id.as_bytes(), val)
+                    .map_err(|e| format!("write local comic failed: {e}"))?;
+                logl!(
+                    "[tauri][local] imported id={} pages={} source={:?}",
+                    comic.id,
+                    comic.pages.len(),
+                    comic.source
+                );
+                out.push(comic.view());
+            }
+        }
+        let _ = tree.flush();
+        Ok(out)
+    })
+    .await
+    .map_err(|e| format!("local import task failed: {e}"))?
+}
+
+#[tauri::command]
+fn api_local_comics() -> Result<Vec<LocalComicView>, String> {
+    let tree = read_local_comic_tree()?;
+    let mut out = Vec::new();
+    for item in tree.iter() {
+        let (_, val) = item.map_err(|e| format!("sled iter failed: {e}"))?;
+        match serde_json::from_slice::<LocalComic>(&val) {
+            Ok(comic) => out.push(comic.view()),
+            Err(e) => {
+                logl!("[tauri][local] skip undecodable comic err={}", e);
+            }
+        }
     }
+    out.sort_by(|a, b| natural_cmp(&a.title, &b.title));
+    Ok(out)
+}
 
-    logl!(
-        "[tauri][imgfile] ok num={} out={:?} out_bytes={} read_key={:?} cost_ms={}",
-        num,
-        out_path,
-        out.len(),
-        read_key,
-        started.elapsed().as_millis()
-    );
+// Full record including the page list; page `i` is `jmcache://localhost/local/<id>/<i>`.
+#[tauri::command]
+fn api_local_comic_get(id: String) -> Result<Option<LocalComic>, String> {
+    load_local_comic(&id)
+}
 
-    Ok(out_path.to_string_lossy().to_string())
+// Forgets the comic and its extracted pages; the source files are never touched.
+#[tauri::command]
+fn api_local_comic_remove(
+    app: tauri::AppHandle,
+    id: String,
+    clear_progress: Option<bool>,
+) -> Result<(), String> {
+    if !id.starts_with(LOCAL_COMIC_PREFIX) {
+        return Err(format!("not a local comic: {id}"));
+    }
+    let tree = read_local_comic_tree()?;
+    tree.remove(id.as_bytes())
+        .map_err(|e| format!("remove local comic failed: {e}"))?;
+    let _ = tree.flush();
+    ensure_cache_not_moving()?;
+    let dir = local_extract_dir(&resolve_read_cache_dir(&app)?, &id);
+    if dir.exists() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    if clear_progress.unwrap_or(false) {
+        let progress = read_progress_tree()?;
+        progress
+            .remove(id.as_bytes())
+            .map_err(|e| format!("delete read progress failed: {e}"))?;
+        let _ = progress.flush();
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -4161,6 +11258,10 @@ async fn api_favorite_folder_move(
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let migration_notes = migrate_legacy_dirs();
+    for note in &migration_notes {
+        logl!("[tauri][dirs] {note}");
+    }
     tauri::Builder::default()
         .manage(CancelRegistry::default())
         .plugin(tauri_plugin_opener::init())
@@ -4179,12 +11280,26 @@ pub fn run() {
                 }
             }
             app.manage(LocalFavoritesStore::open());
+            let _ = APP_HANDLE.set(app.handle().clone());
+            resume_download_jobs(app.handle());
+            // The static scope in tauri.conf.json only knows the default locations.
+            if let Ok(base) = resolve_read_cache_dir(app.handle()) {
+                if let Err(e) = app.asset_protocol_scope().allow_directory(&base, true) {
+                    logl!("[tauri][cache] allow asset scope {:?} failed: {}", base, e);
+                }
+            }
             let handle = app.handle().clone();
-            std::thread::spawn(move || loop {
-                if let Err(e) = update_read_cache_stats(handle.clone()) {
-                    logl!("[tauri][cache] scan failed: {e}");
+            std::thread::spawn(move || {
+                let res = resolve_read_cache_dir(&handle).and_then(|base| ensure_read_cache_index(&base));
+                if let Err(e) = res {
+                    logl!("[tauri][cache] index build failed: {e}");
+                }
+                loop {
+                    if let Ok(base) = resolve_read_cache_dir(&handle) {
+                        disk_guard_tick(&base);
+                    }
+                    std::thread::sleep(Duration::from_secs(60));
                 }
-                std::thread::sleep(Duration::from_secs(600));
             });
             let follow_handle = app.handle().clone();
             std::thread::spawn(move || loop {
@@ -4216,11 +11331,22 @@ pub fn run() {
             });
             Ok(())
         })
-        .register_uri_scheme_protocol("jmcache", |ctx, request| jmcache_protocol(ctx, request))
+        .register_asynchronous_uri_scheme_protocol("jmcache", |ctx, request, responder| {
+            jmcache_protocol(ctx, request, responder)
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             api_config_get,
             api_config_set_socks_proxy,
+            api_config_set_cover_cache_max_bytes,
+            api_config_set_min_free_bytes,
+            api_config_set_memory_cache_max_bytes,
+            api_config_set_library_dir,
+            api_config_set_offline_mode,
+            api_offline_status,
+            api_offline_comics,
+            api_memory_cache_stats,
+            api_memory_cache_clear,
             app_update_check,
             app_update_download,
             api_read_progress_upsert,
@@ -4241,6 +11367,17 @@ pub fn run() {
             api_read_cache_stats,
             api_read_cache_refresh,
             api_read_cache_cleanup,
+            api_read_cache_evict,
+            api_cover_cache_clear,
+            api_disk_space_status,
+            api_cache_dir_move,
+            api_read_cache_comics,
+            api_read_cache_comic_chapters,
+            api_read_cache_delete_comics,
+            api_read_cache_delete_chapters,
+            api_read_cache_policy_list,
+            api_read_cache_policy_set,
+            api_read_cache_repair,
             api_register_captcha,
             api_register,
             api_register_verify,
@@ -4264,7 +11401,32 @@ pub fn run() {
             api_segmentation_nums,
             api_image_descramble,
             api_image_descramble_file,
+            api_chapter_thumbnails,
+            api_chapter_page_dimensions,
             api_read_cancel,
+            api_download_enqueue,
+            api_download_list,
+            api_download_get,
+            api_download_pause,
+            api_download_resume,
+            api_download_retry,
+            api_download_cancel,
+            api_download_verify,
+            api_download_repair,
+            api_auto_download_settings,
+            api_config_set_auto_download,
+            api_auto_download_set_rule,
+            api_auto_download_log,
+            api_config_set_bandwidth,
+            api_bandwidth_status,
+            api_export_cbz,
+            api_export_epub,
+            api_export_pdf,
+            api_library_sync,
+            api_local_import,
+            api_local_comics,
+            api_local_comic_get,
+            api_local_comic_remove,
             api_favorites,
             api_favorite_toggle,
             api_favorite_folder_add,
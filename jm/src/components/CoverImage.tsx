@@ -9,6 +9,16 @@ type CoverImageProps = {
 const coverCache = new Map<string, string>();
 const coverFetches = new Map<string, Promise<string>>();
 
+// Cached cover URLs embed absolute cache paths; drop them when the cache dir moves.
+void (async () => {
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    await listen("cache-dir-moved", () => coverCache.clear());
+  } catch {
+    // Not running inside Tauri.
+  }
+})();
+
 async function fetchCover(src: string): Promise<string> {
   const cached = coverCache.get(src);
   if (cached) return cached;